@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE status DROP COLUMN message;
+ALTER TABLE status DROP COLUMN success;
+UPDATE status SET status_code = 404 WHERE status_code IS NULL;
+ALTER TABLE status MODIFY status_code INTEGER NOT NULL;
+
+ALTER TABLE projects DROP COLUMN check_config;
+ALTER TABLE projects DROP COLUMN check_type;
@@ -0,0 +1,9 @@
+-- Each project now chooses which probe is used to check it, with a probe specific JSON config
+ALTER TABLE projects ADD COLUMN check_type VARCHAR(32) NOT NULL DEFAULT 'http';
+ALTER TABLE projects ADD COLUMN check_config TEXT;
+
+-- Status samples record the probe outcome directly, status_code is only set for probes that have one
+ALTER TABLE status MODIFY status_code INTEGER;
+ALTER TABLE status ADD COLUMN success BOOL NOT NULL DEFAULT false;
+ALTER TABLE status ADD COLUMN message TEXT;
+UPDATE status SET success = (status_code >= 200 AND status_code < 300);
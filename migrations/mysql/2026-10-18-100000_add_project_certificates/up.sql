@@ -0,0 +1,13 @@
+-- The most recent leaf certificate seen for each HTTPS project
+CREATE TABLE project_certificates (
+    id INTEGER PRIMARY KEY AUTO_INCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP() NOT NULL,
+    project INTEGER UNIQUE NOT NULL,
+    not_after DATETIME NOT NULL,
+    issuer TEXT NOT NULL,
+    sans TEXT NOT NULL,
+    notified_threshold INTEGER,
+    FOREIGN KEY (project) REFERENCES projects(id)
+);
+
+ALTER TABLE status ADD COLUMN degraded BOOL NOT NULL DEFAULT false;
@@ -0,0 +1,2 @@
+-- Seconds between each check of a project
+ALTER TABLE projects ADD COLUMN check_interval INTEGER NOT NULL DEFAULT 90;
@@ -0,0 +1,9 @@
+-- Push based monitors, the monitored job calls POST /heartbeat/{token}
+CREATE TABLE heartbeats (
+    id INTEGER PRIMARY KEY AUTO_INCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP() NOT NULL,
+    project INTEGER UNIQUE NOT NULL,
+    token VARCHAR(64) UNIQUE NOT NULL,
+    last_ping DATETIME,
+    FOREIGN KEY (project) REFERENCES projects(id)
+);
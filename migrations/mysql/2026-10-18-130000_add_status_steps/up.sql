@@ -0,0 +1,2 @@
+-- Per step timings of multi step checks, as JSON
+ALTER TABLE status ADD COLUMN steps TEXT;
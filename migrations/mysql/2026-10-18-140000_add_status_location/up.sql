@@ -0,0 +1,2 @@
+-- The probe location that recorded each sample, samples from before agents are from the server
+ALTER TABLE status ADD COLUMN location VARCHAR(64) NOT NULL DEFAULT 'local';
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE projects DROP COLUMN notify_degraded;
+ALTER TABLE projects DROP COLUMN latency_use_p95;
+ALTER TABLE projects DROP COLUMN latency_critical_ms;
+ALTER TABLE projects DROP COLUMN latency_warn_ms;
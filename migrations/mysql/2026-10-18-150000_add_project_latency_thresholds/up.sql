@@ -0,0 +1,5 @@
+-- Response times that mark a project as degraded or down, in milliseconds
+ALTER TABLE projects ADD COLUMN latency_warn_ms INTEGER;
+ALTER TABLE projects ADD COLUMN latency_critical_ms INTEGER;
+ALTER TABLE projects ADD COLUMN latency_use_p95 BOOL NOT NULL DEFAULT false;
+ALTER TABLE projects ADD COLUMN notify_degraded BOOL NOT NULL DEFAULT false;
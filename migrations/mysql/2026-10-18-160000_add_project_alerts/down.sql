@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE project_alerts;
+ALTER TABLE projects DROP COLUMN flap_window_minutes;
+ALTER TABLE projects DROP COLUMN flap_threshold;
+ALTER TABLE projects DROP COLUMN up_after_successes;
+ALTER TABLE projects DROP COLUMN down_after_failures;
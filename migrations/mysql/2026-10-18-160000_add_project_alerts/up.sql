@@ -0,0 +1,17 @@
+-- How many consecutive results change a project between up and down, and when it is flapping
+ALTER TABLE projects ADD COLUMN down_after_failures INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE projects ADD COLUMN up_after_successes INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE projects ADD COLUMN flap_threshold INTEGER;
+ALTER TABLE projects ADD COLUMN flap_window_minutes INTEGER NOT NULL DEFAULT 60;
+
+-- What subscribers have last been told about each project
+CREATE TABLE project_alerts (
+    id INTEGER PRIMARY KEY AUTO_INCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP() NOT NULL,
+    project INTEGER UNIQUE NOT NULL,
+    down BOOL NOT NULL DEFAULT false,
+    streak INTEGER NOT NULL DEFAULT 0,
+    flapping BOOL NOT NULL DEFAULT false,
+    changed DATETIME DEFAULT CURRENT_TIMESTAMP() NOT NULL,
+    FOREIGN KEY (project) REFERENCES projects(id)
+);
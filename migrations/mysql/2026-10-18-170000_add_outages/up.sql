@@ -0,0 +1,71 @@
+-- Each period a project was down, opened and closed by the update job
+CREATE TABLE outages (
+    id INTEGER PRIMARY KEY AUTO_INCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP() NOT NULL,
+    project INTEGER NOT NULL,
+    started DATETIME NOT NULL,
+    ended DATETIME,
+    cause TEXT,
+    first_status_code INTEGER,
+    last_status_code INTEGER,
+    FOREIGN KEY (project) REFERENCES projects(id),
+    INDEX outages_project_started (project, started)
+);
+
+-- Rebuild past outages from the status history, each run of failed samples is an outage that
+-- ended with the next successful sample. Samples from before this migration are all from the
+-- server's own location
+INSERT INTO outages (project, started, ended, cause, first_status_code, last_status_code)
+SELECT
+    failures.project,
+    MIN(failures.created),
+    CASE
+        WHEN COUNT(failures.next_created) = COUNT(*) THEN MAX(failures.next_created)
+        WHEN MAX(project_alerts.down) THEN NULL
+        -- Still failing but not enough times for subscribers to have been told
+        ELSE MAX(failures.created)
+    END,
+    MAX(failures.first_message),
+    MAX(failures.first_status_code),
+    MAX(failures.last_status_code)
+FROM (
+    SELECT
+        runs.project,
+        runs.run,
+        runs.created,
+        runs.next_created,
+        FIRST_VALUE(runs.message) OVER (
+            PARTITION BY runs.project, runs.run ORDER BY runs.created, runs.id
+        ) AS first_message,
+        FIRST_VALUE(runs.status_code) OVER (
+            PARTITION BY runs.project, runs.run ORDER BY runs.created, runs.id
+        ) AS first_status_code,
+        FIRST_VALUE(runs.status_code) OVER (
+            PARTITION BY runs.project, runs.run ORDER BY runs.created DESC, runs.id DESC
+        ) AS last_status_code
+    FROM (
+        SELECT
+            id,
+            project,
+            created,
+            success,
+            status_code,
+            message,
+            -- Failures between the same two successful samples share a run
+            SUM(success) OVER (PARTITION BY project ORDER BY created, id) AS run,
+            LEAD(created) OVER (PARTITION BY project ORDER BY created, id) AS next_created
+        FROM status
+        WHERE location = 'local'
+    ) runs
+    WHERE NOT runs.success
+) failures
+LEFT JOIN project_alerts ON project_alerts.project = failures.project
+GROUP BY failures.project, failures.run;
+
+-- Projects that are down without a failed sample are in an outage that started when
+-- subscribers were told
+INSERT INTO outages (project, started)
+SELECT project_alerts.project, project_alerts.changed
+FROM project_alerts
+LEFT JOIN outages ON outages.project = project_alerts.project AND outages.ended IS NULL
+WHERE project_alerts.down AND outages.id IS NULL;
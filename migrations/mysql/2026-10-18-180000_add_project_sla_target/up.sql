@@ -0,0 +1,2 @@
+-- The uptime percentage that a project is expected to meet over 30 days
+ALTER TABLE projects ADD COLUMN sla_target DOUBLE;
@@ -0,0 +1,13 @@
+-- Service level objectives, a project can have several.
+-- Availability objectives are measured by time, latency objectives by the share of checks that
+-- were successful and faster than the threshold
+CREATE TABLE slos (
+    id INTEGER PRIMARY KEY AUTO_INCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP() NOT NULL,
+    project INTEGER NOT NULL,
+    objective DOUBLE NOT NULL,
+    window_days INTEGER NOT NULL DEFAULT 30,
+    latency_threshold_ms INTEGER,
+    burning BOOL NOT NULL DEFAULT false,
+    FOREIGN KEY (project) REFERENCES projects(id)
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX status_rollups_period ON status_rollups;
+DROP INDEX status_created ON status;
+DROP INDEX status_project_created ON status;
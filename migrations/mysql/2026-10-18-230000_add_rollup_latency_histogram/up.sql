@@ -0,0 +1,3 @@
+-- JSON object of how many successful samples of each rollup took up to each latency bucket, in
+-- milliseconds. Rollups from before this was added don't have one
+ALTER TABLE status_rollups ADD COLUMN latency_histogram TEXT;
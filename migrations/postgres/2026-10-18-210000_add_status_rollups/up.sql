@@ -0,0 +1,21 @@
+-- Hourly and daily summaries of the status samples of each project, kept after the raw samples
+-- are pruned. period_seconds is 3600 for hourly rollups and 86400 for daily ones.
+-- status_codes is a JSON object of how many samples had each status code
+CREATE TABLE status_rollups (
+    id SERIAL PRIMARY KEY NOT NULL,
+    created TIMESTAMP DEFAULT (NOW() AT TIME ZONE 'utc') NOT NULL,
+    project INTEGER NOT NULL,
+    period_seconds INTEGER NOT NULL,
+    period_start TIMESTAMP NOT NULL,
+    samples INTEGER NOT NULL,
+    failures INTEGER NOT NULL,
+    degraded INTEGER NOT NULL,
+    up_seconds INTEGER NOT NULL,
+    down_seconds INTEGER NOT NULL,
+    min_latency_ms INTEGER,
+    avg_latency_ms INTEGER,
+    p95_latency_ms INTEGER,
+    status_codes TEXT NOT NULL,
+    FOREIGN KEY (project) REFERENCES projects(id)
+);
+CREATE UNIQUE INDEX status_rollups_project_period ON status_rollups (project, period_seconds, period_start);
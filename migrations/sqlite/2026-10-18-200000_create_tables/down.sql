@@ -0,0 +1,15 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE slos;
+DROP TABLE outages;
+DROP TABLE project_alerts;
+DROP TABLE heartbeats;
+DROP TABLE project_certificates;
+DROP TABLE webhook_subscriptions;
+DROP TABLE sms_subscriptions;
+DROP TABLE email_subscriptions;
+DROP TABLE settings;
+DROP TABLE incident_status_update;
+DROP TABLE incident_status_type;
+DROP TABLE incidents;
+DROP TABLE status;
+DROP TABLE projects;
@@ -0,0 +1,141 @@
+-- The whole schema as of the MySQL migrations up to 2026-10-18-190000_add_slos, later changes get
+-- their own migration for each backend.
+-- CURRENT_TIMESTAMP is in UTC, like the rest of YouUp expects
+CREATE TABLE projects (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    url VARCHAR(256) NOT NULL,
+    name VARCHAR(32) UNIQUE NOT NULL,
+    description TEXT,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    check_type VARCHAR(32) NOT NULL DEFAULT 'http',
+    check_config TEXT,
+    check_interval INTEGER NOT NULL DEFAULT 90,
+    latency_warn_ms INTEGER,
+    latency_critical_ms INTEGER,
+    latency_use_p95 BOOLEAN NOT NULL DEFAULT false,
+    notify_degraded BOOLEAN NOT NULL DEFAULT false,
+    down_after_failures INTEGER NOT NULL DEFAULT 1,
+    up_after_successes INTEGER NOT NULL DEFAULT 1,
+    flap_threshold INTEGER,
+    flap_window_minutes INTEGER NOT NULL DEFAULT 60,
+    sla_target DOUBLE
+);
+
+-- time is how long the request takes in ms
+CREATE TABLE status (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    project INTEGER NOT NULL REFERENCES projects(id),
+    time INTEGER NOT NULL,
+    status_code INTEGER,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    success BOOLEAN NOT NULL DEFAULT false,
+    message TEXT,
+    degraded BOOLEAN NOT NULL DEFAULT false,
+    steps TEXT,
+    location VARCHAR(64) NOT NULL DEFAULT 'local'
+);
+
+CREATE TABLE incidents (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    project INTEGER NOT NULL REFERENCES projects(id)
+);
+
+CREATE TABLE incident_status_type (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    colour VARCHAR(16) NOT NULL,
+    title VARCHAR(64) NOT NULL
+);
+
+CREATE TABLE incident_status_update (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    status_type INTEGER NOT NULL REFERENCES incident_status_type(id),
+    message VARCHAR(256) NOT NULL,
+    incident INTEGER NOT NULL REFERENCES incidents(id)
+);
+
+CREATE TABLE settings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    name VARCHAR(256) NOT NULL,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE email_subscriptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    email VARCHAR(256) NOT NULL,
+    confirmed BOOLEAN NOT NULL DEFAULT false
+);
+
+CREATE TABLE sms_subscriptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    phone_number VARCHAR(256) NOT NULL,
+    confirmed BOOLEAN NOT NULL DEFAULT false
+);
+
+CREATE TABLE webhook_subscriptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    url VARCHAR(256) NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true
+);
+
+-- The most recent leaf certificate seen for each HTTPS project
+CREATE TABLE project_certificates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    project INTEGER UNIQUE NOT NULL REFERENCES projects(id),
+    not_after DATETIME NOT NULL,
+    issuer TEXT NOT NULL,
+    sans TEXT NOT NULL,
+    notified_threshold INTEGER
+);
+
+-- Push based monitors, the monitored job calls POST /heartbeat/{token}
+CREATE TABLE heartbeats (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    project INTEGER UNIQUE NOT NULL REFERENCES projects(id),
+    token VARCHAR(64) UNIQUE NOT NULL,
+    last_ping DATETIME
+);
+
+-- What subscribers have last been told about each project
+CREATE TABLE project_alerts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    project INTEGER UNIQUE NOT NULL REFERENCES projects(id),
+    down BOOLEAN NOT NULL DEFAULT false,
+    streak INTEGER NOT NULL DEFAULT 0,
+    flapping BOOLEAN NOT NULL DEFAULT false,
+    changed DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+-- Each period a project was down, opened and closed by the update job
+CREATE TABLE outages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    project INTEGER NOT NULL REFERENCES projects(id),
+    started DATETIME NOT NULL,
+    ended DATETIME,
+    cause TEXT,
+    first_status_code INTEGER,
+    last_status_code INTEGER
+);
+CREATE INDEX outages_project_started ON outages (project, started);
+
+-- Service level objectives, a project can have several
+CREATE TABLE slos (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    created DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    project INTEGER NOT NULL REFERENCES projects(id),
+    objective DOUBLE NOT NULL,
+    window_days INTEGER NOT NULL DEFAULT 30,
+    latency_threshold_ms INTEGER,
+    burning BOOLEAN NOT NULL DEFAULT false
+);
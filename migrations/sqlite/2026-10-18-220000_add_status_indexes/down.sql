@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX status_rollups_period;
+DROP INDEX status_created;
+DROP INDEX status_project_created;
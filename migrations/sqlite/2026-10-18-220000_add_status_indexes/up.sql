@@ -0,0 +1,4 @@
+-- The status pages and rollups look up samples by project and time, and pruning by time alone
+CREATE INDEX status_project_created ON status (project, created);
+CREATE INDEX status_created ON status (created);
+CREATE INDEX status_rollups_period ON status_rollups (period_seconds, period_start);
@@ -0,0 +1,138 @@
+use crate::api::agent::{AgentProject, AgentReport};
+use crate::checks::{CheckContext, CheckResult, check_for_project};
+use crate::models::Project;
+use crate::settings;
+use crate::update_job::{SCHEDULER_TICK, Schedule, check_with_retry};
+use actix_rt::spawn;
+use anyhow::Context;
+use reqwest::Client;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tracing::error;
+
+/// How often the list of projects is fetched from the server
+const PROJECT_REFRESH: Duration = Duration::from_secs(60);
+
+/// Everything an agent needs to check projects and report back, shared between all running checks
+struct AgentState {
+    client: Client,
+    server: String,
+    token: String,
+    location: String,
+    context: CheckContext,
+    workers: Semaphore,
+}
+
+impl AgentState {
+    async fn fetch_projects(&self) -> anyhow::Result<Vec<Project>> {
+        let projects = self
+            .client
+            .get(format!("{}/api/agent/projects", self.server))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("Unable to fetch projects")?
+            .error_for_status()
+            .context("Server refused to list projects")?
+            .json::<Vec<AgentProject>>()
+            .await
+            .context("Invalid project list")?;
+
+        Ok(projects.into_iter().map(Project::from).collect())
+    }
+
+    async fn report(&self, project: &Project, result: &CheckResult) -> anyhow::Result<()> {
+        self.client
+            .post(format!("{}/api/agent/results", self.server))
+            .bearer_auth(&self.token)
+            .json(&AgentReport::new(&self.location, project.id, result))
+            .send()
+            .await
+            .context("Unable to send result")?
+            .error_for_status()
+            .context("Server refused result")?;
+        Ok(())
+    }
+}
+
+async fn check_project(state: &AgentState, project: &Project) {
+    tracing::info!("Checking {}", project.name);
+
+    let result = match check_for_project(&state.context, project) {
+        Ok(check) => check_with_retry(check.as_ref(), &state.workers).await,
+        Err(e) => {
+            error!("Unable to create check for {}: {e:?}", project.name);
+            CheckResult::failure(
+                Duration::ZERO,
+                None,
+                format!("Invalid check configuration: {e}"),
+            )
+        }
+    };
+
+    if let Err(e) = state.report(project, &result).await {
+        error!("Failed to report result for {}: {e:?}", project.name);
+    }
+}
+
+/// Check projects from this location and report the results to the server, instead of
+/// running the web server
+#[tracing::instrument]
+pub async fn run_agent() -> anyhow::Result<()> {
+    let _span = tracing::info_span!("Agent");
+
+    let state = Rc::new(AgentState {
+        client: Client::new(),
+        server: settings::agent_server()
+            .context("AGENT_SERVER must be set")?
+            .trim_end_matches('/')
+            .to_string(),
+        token: settings::agent_token().context("AGENT_TOKEN must be set")?,
+        location: settings::agent_location()
+            .context("LOCATION must be set, unable to find the hostname")?,
+        context: CheckContext::new(None, settings::agent_allow_scripts())?,
+        workers: Semaphore::new(settings::max_concurrent_checks()),
+    });
+    let schedule = Rc::new(RefCell::new(Schedule::default()));
+
+    tracing::info!(
+        "Running agent at '{}', reporting to {}",
+        state.location,
+        state.server
+    );
+
+    let mut projects_list = Vec::new();
+    let mut last_refresh: Option<Instant> = None;
+
+    loop {
+        if last_refresh.is_none_or(|r| r.elapsed() >= PROJECT_REFRESH) {
+            match state.fetch_projects().await {
+                Ok(projects) => {
+                    projects_list = projects;
+                    last_refresh = Some(Instant::now());
+                }
+                Err(e) => error!("Failed to refresh projects, using the last known list: {e:?}"),
+            }
+        }
+
+        let now = Instant::now();
+        for project in &projects_list {
+            let interval = Duration::from_secs(project.check_interval.max(0) as u64);
+            if !schedule.borrow_mut().take_if_due(project.id, interval, now) {
+                continue;
+            }
+
+            let state = state.clone();
+            let schedule = schedule.clone();
+            let project = project.clone();
+            spawn(async move {
+                check_project(&state, &project).await;
+                schedule.borrow_mut().finished(project.id);
+            });
+        }
+
+        actix_rt::time::sleep(SCHEDULER_TICK).await;
+    }
+}
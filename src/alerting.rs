@@ -0,0 +1,249 @@
+use chrono::Duration;
+
+/// How check results are turned into down and up notifications for a project
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlertPolicy {
+    /// Consecutive failing results before a project is considered down
+    pub down_after: i32,
+    /// Consecutive successful results before a down project is considered up again
+    pub up_after: i32,
+    /// How many changes between up and down within the flap window make a project flapping,
+    /// flap detection is disabled if not set
+    pub flap_threshold: Option<i32>,
+    pub flap_window_minutes: i32,
+}
+
+impl Default for AlertPolicy {
+    fn default() -> Self {
+        Self {
+            down_after: 1,
+            up_after: 1,
+            flap_threshold: None,
+            flap_window_minutes: 60,
+        }
+    }
+}
+
+impl AlertPolicy {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.down_after >= 1 && self.up_after >= 1,
+            "At least one result is needed to change between up and down"
+        );
+        anyhow::ensure!(
+            self.flap_threshold.is_none_or(|t| t >= 2),
+            "The flap threshold must be at least 2 changes"
+        );
+        anyhow::ensure!(
+            self.flap_window_minutes >= 1,
+            "The flap window must be at least a minute"
+        );
+        Ok(())
+    }
+
+    pub fn flap_window(&self) -> Duration {
+        Duration::minutes(i64::from(self.flap_window_minutes))
+    }
+}
+
+/// What subscribers have last been told about a project
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AlertState {
+    pub down: bool,
+    /// How many consecutive results have disagreed with `down`
+    pub streak: i32,
+    /// Notifications are paused while the project keeps changing between up and down
+    pub flapping: bool,
+}
+
+/// A change that subscribers should be told about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertEvent {
+    Down,
+    Up,
+}
+
+/// Count how many times a project changed between up and down, given whether it was down at
+/// each point in time, in order
+pub fn count_changes(down: impl IntoIterator<Item = bool>) -> usize {
+    let mut down = down.into_iter();
+    let Some(mut previous) = down.next() else {
+        return 0;
+    };
+
+    let mut changes = 0;
+    for down in down {
+        if down != previous {
+            changes += 1;
+        }
+        previous = down;
+    }
+    changes
+}
+
+impl AlertState {
+    /// Move on to the next state after a result, `is_down` is the quorum verdict including that
+    /// result and `changes` is how often the project changed between up and down within the
+    /// flap window
+    pub fn next(
+        self,
+        policy: &AlertPolicy,
+        is_down: bool,
+        changes: usize,
+    ) -> (Self, Option<AlertEvent>) {
+        let flapping = policy
+            .flap_threshold
+            .is_some_and(|threshold| changes >= usize::try_from(threshold).unwrap_or(0));
+
+        // Nothing is counted while flapping, the project has to settle down before it can change
+        if flapping || self.flapping {
+            return (
+                Self {
+                    down: self.down,
+                    streak: 0,
+                    flapping,
+                },
+                None,
+            );
+        }
+
+        if is_down == self.down {
+            return (Self { streak: 0, ..self }, None);
+        }
+
+        let streak = self.streak + 1;
+        let required = if self.down {
+            policy.up_after
+        } else {
+            policy.down_after
+        };
+
+        if streak >= required {
+            let event = if is_down {
+                AlertEvent::Down
+            } else {
+                AlertEvent::Up
+            };
+            (
+                Self {
+                    down: is_down,
+                    streak: 0,
+                    flapping: false,
+                },
+                Some(event),
+            )
+        } else {
+            (Self { streak, ..self }, None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::alerting::{AlertEvent, AlertPolicy, AlertState, count_changes};
+
+    /// Feed results through the state machine, returning every event
+    fn run(policy: &AlertPolicy, results: &[bool]) -> (AlertState, Vec<AlertEvent>) {
+        let mut state = AlertState::default();
+        let mut history = vec![];
+        let mut events = vec![];
+
+        for is_down in results {
+            history.push(*is_down);
+            let (next, event) = state.next(policy, *is_down, count_changes(history.clone()));
+            state = next;
+            events.extend(event);
+        }
+
+        (state, events)
+    }
+
+    #[test]
+    fn default_policy_alerts_immediately() {
+        let (state, events) = run(&AlertPolicy::default(), &[false, true, false]);
+
+        assert_eq!(events, vec![AlertEvent::Down, AlertEvent::Up]);
+        assert!(!state.down);
+    }
+
+    #[test]
+    fn consecutive_results_are_needed() {
+        let policy = AlertPolicy {
+            down_after: 3,
+            up_after: 2,
+            ..AlertPolicy::default()
+        };
+
+        let (state, events) = run(&policy, &[true, true, false, true, true]);
+        assert!(events.is_empty());
+        assert_eq!(state.streak, 2);
+
+        let (state, events) = run(&policy, &[true, true, true, false, true, false, false]);
+        assert_eq!(events, vec![AlertEvent::Down, AlertEvent::Up]);
+        assert!(!state.down);
+    }
+
+    #[test]
+    fn flapping_suppresses_alerts() {
+        let policy = AlertPolicy {
+            flap_threshold: Some(3),
+            ..AlertPolicy::default()
+        };
+
+        let (state, events) = run(&policy, &[false, true, false, true, false, true]);
+
+        // The first two changes are alerted, after that the project is flapping
+        assert_eq!(events, vec![AlertEvent::Down, AlertEvent::Up]);
+        assert!(state.flapping);
+        assert!(!state.down);
+    }
+
+    #[test]
+    fn flapping_stops_once_settled() {
+        let policy = AlertPolicy {
+            flap_threshold: Some(3),
+            ..AlertPolicy::default()
+        };
+        let state = AlertState {
+            down: false,
+            streak: 0,
+            flapping: true,
+        };
+
+        let (state, event) = state.next(&policy, true, 1);
+        assert!(!state.flapping);
+        assert_eq!(event, None);
+
+        let (state, event) = state.next(&policy, true, 1);
+        assert!(state.down);
+        assert_eq!(event, Some(AlertEvent::Down));
+    }
+
+    #[test]
+    fn changes_are_counted() {
+        assert_eq!(count_changes([]), 0);
+        assert_eq!(count_changes([true]), 0);
+        assert_eq!(count_changes([false, false, true, true, false]), 2);
+    }
+
+    #[test]
+    fn invalid_policy() {
+        assert!(AlertPolicy::default().validate().is_ok());
+        assert!(
+            AlertPolicy {
+                down_after: 0,
+                ..AlertPolicy::default()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            AlertPolicy {
+                flap_threshold: Some(1),
+                ..AlertPolicy::default()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use crate::checks::{CheckResult, StepTiming, heartbeat};
+use crate::data::project_repository::ProjectRepositoryData;
+use crate::models::Project;
+use crate::notifications::Notifiers;
+use crate::settings;
+use crate::update_job::{ResultRepositoryData, record_result};
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::web::{Data, Json};
+use actix_web::{HttpRequest, HttpResponse, get, post};
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A project as sent to agents, with just enough to build its check. This includes the whole
+/// check config, such as request headers and journey bodies, so agents see any credentials in it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentProject {
+    pub id: i32,
+    pub name: String,
+    pub url: String,
+    pub check_type: String,
+    pub check_config: Option<String>,
+    pub check_interval: i32,
+}
+
+impl From<Project> for AgentProject {
+    fn from(project: Project) -> Self {
+        Self {
+            id: project.id,
+            name: project.name,
+            url: project.url,
+            check_type: project.check_type,
+            check_config: project.check_config,
+            check_interval: project.check_interval,
+        }
+    }
+}
+
+impl From<AgentProject> for Project {
+    fn from(project: AgentProject) -> Self {
+        Self {
+            id: project.id,
+            url: project.url,
+            name: project.name,
+            description: None,
+            created: Utc::now().naive_utc(),
+            enabled: true,
+            check_type: project.check_type,
+            check_config: project.check_config,
+            check_interval: project.check_interval,
+            // Thresholds are applied by the server
+            latency_warn_ms: None,
+            latency_critical_ms: None,
+            latency_use_p95: false,
+            notify_degraded: false,
+            down_after_failures: 1,
+            up_after_successes: 1,
+            flap_threshold: None,
+            flap_window_minutes: 60,
+            sla_target: None,
+        }
+    }
+}
+
+/// The result of an agent checking a project once
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentReport {
+    pub location: String,
+    pub project: i32,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub status_code: Option<u16>,
+    pub message: Option<String>,
+    pub degraded: bool,
+    #[serde(default)]
+    pub steps: Vec<StepTiming>,
+    /// When the check finished on the agent
+    pub created: NaiveDateTime,
+}
+
+impl AgentReport {
+    pub fn new(location: &str, project: i32, result: &CheckResult) -> Self {
+        Self {
+            location: location.to_string(),
+            project,
+            duration_ms: result.duration.as_millis() as u64,
+            success: result.success,
+            status_code: result.status_code,
+            message: result.message.clone(),
+            degraded: result.degraded,
+            steps: result.steps.clone(),
+            created: Utc::now().naive_utc(),
+        }
+    }
+
+    fn to_check_result(&self) -> CheckResult {
+        CheckResult {
+            duration: Duration::from_millis(self.duration_ms),
+            success: self.success,
+            status_code: self.status_code,
+            message: self.message.clone(),
+            degraded: self.degraded,
+            certificate: None,
+            steps: self.steps.clone(),
+        }
+    }
+}
+
+/// Check the bearer token of an agent request, agents are disabled if there is no token set
+fn is_agent(request: &HttpRequest) -> bool {
+    let Some(token) = settings::agent_token() else {
+        return false;
+    };
+
+    request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .is_some_and(|t| constant_time_eq(t.as_bytes(), token.as_bytes()))
+}
+
+/// How far ahead of the server's clock an agent's clock can be
+const MAX_CLOCK_SKEW: chrono::Duration = chrono::Duration::seconds(30);
+
+/// Reports from the future would be shown as the latest result until then, and ones older than
+/// the stale window would be ignored when deciding if the project is down
+fn check_report_time(created: NaiveDateTime, now: NaiveDateTime) -> Result<(), &'static str> {
+    if created > now + MAX_CLOCK_SKEW {
+        Err("Report is from the future, check the agent's clock")
+    } else if now.signed_duration_since(created) > settings::location_stale_after() {
+        Err("Report is older than LOCATION_STALE_MINUTES")
+    } else {
+        Ok(())
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[get("/api/agent/projects")]
+pub async fn get_agent_projects(
+    request: HttpRequest,
+    projects: ProjectRepositoryData,
+) -> HttpResponse {
+    let _span = tracing::info_span!("Agent projects");
+
+    if !is_agent(&request) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    match projects.get_all_enabled_projects() {
+        // Heartbeats are pushed to this instance, there is nothing for an agent to check
+        Ok(projects) => HttpResponse::Ok().json(
+            projects
+                .into_iter()
+                .filter(|p| !p.uses_check_type(heartbeat::CHECK_TYPE))
+                .map(AgentProject::from)
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => {
+            tracing::warn!("Failed to get projects for agent: {e:?}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[post("/api/agent/results")]
+pub async fn post_agent_result(
+    request: HttpRequest,
+    report: Json<AgentReport>,
+    projects: ProjectRepositoryData,
+    result_repo: ResultRepositoryData,
+    notifiers: Data<Notifiers>,
+) -> HttpResponse {
+    let _span = tracing::info_span!("Agent result", location = %report.location);
+
+    if !is_agent(&request) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    if report.location.is_empty() || report.location.len() > 64 {
+        return HttpResponse::BadRequest().body("Location must be between 1 and 64 characters");
+    }
+
+    // Results from the server's own location would be overwritten by its own checks
+    if report.location == settings::location() {
+        return HttpResponse::BadRequest().body("Location is already used by the server");
+    }
+
+    if let Err(e) = check_report_time(report.created, Utc::now().naive_utc()) {
+        return HttpResponse::BadRequest().body(e);
+    }
+
+    let Some(project) = projects.get_project_by_id(report.project) else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    if !project.enabled {
+        return HttpResponse::BadRequest().body("Project is disabled");
+    }
+
+    record_result(
+        result_repo.get_ref().as_ref(),
+        &notifiers,
+        &project,
+        &report.location,
+        &report.to_check_result(),
+        report.created,
+    )
+    .await;
+
+    HttpResponse::Ok().finish()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::api::agent::check_report_time;
+    use chrono::{Duration, NaiveDate};
+
+    #[test]
+    fn report_time_must_be_recent() {
+        let now = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+
+        assert!(check_report_time(now - Duration::minutes(1), now).is_ok());
+        assert!(check_report_time(now + Duration::seconds(10), now).is_ok());
+        assert!(check_report_time(now + Duration::minutes(5), now).is_err());
+        assert!(check_report_time(now - Duration::hours(1), now).is_err());
+    }
+}
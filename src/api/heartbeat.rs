@@ -0,0 +1,20 @@
+use crate::data::heartbeat_repository::HeartbeatRepositoryData;
+use actix_web::web::Path;
+use actix_web::{HttpResponse, post};
+
+#[post("/heartbeat/{token}")]
+pub async fn post_heartbeat(
+    token: Path<(String,)>,
+    heartbeats: HeartbeatRepositoryData,
+) -> HttpResponse {
+    let _span = tracing::info_span!("Heartbeat");
+
+    match heartbeats.record_ping(&token.as_ref().0) {
+        Ok(true) => HttpResponse::Ok().finish(),
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(e) => {
+            tracing::warn!("Failed to record heartbeat: {e:?}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -0,0 +1,670 @@
+use crate::api::agent::{get_agent_projects, post_agent_result};
+use crate::api::heartbeat::post_heartbeat;
+use crate::data::alert_repository::AlertRepository;
+use crate::data::certificate_repository::CertificateRepository;
+use crate::data::email_subscription_repository::EmailSubscriptionRepository;
+use crate::data::heartbeat_repository::HeartbeatRepository;
+use crate::data::incident_repository::IncidentRepository;
+use crate::data::outage_repository::OutageRepository;
+use crate::data::project_repository::ProjectRepository;
+use crate::data::rollup_repository::RollupRepository;
+use crate::data::settings_repository::SettingsRepository;
+use crate::data::slo_repository::SloRepository;
+use crate::data::sms_subscription_repository::SmsSubscriptionRepository;
+use crate::data::status_repository::StatusRepository;
+use crate::data::webhook_subscription_repository::WebhookSubscriptionRepository;
+use crate::form_email_subscribe::{get_email_confirm, post_email_subscribe};
+use crate::notifications::Notifiers;
+use crate::notifications::mailer::Mailer;
+use crate::notifications::sms::SMSNotifier;
+use crate::notifications::webhook::WebhookNotifier;
+use crate::settings;
+use crate::settings::PersistedSettings;
+use crate::template::index::template_index::{IndexRepository, get_index, head_index};
+use crate::template::template_admin_dashboard::{
+    DashboardRepository, get_admin_dashboard, post_admin_dashboard,
+};
+use crate::template::template_admin_incident::get_admin_incidents;
+use crate::template::template_admin_incident_new::{
+    get_admin_incidents_new, post_admin_incidents_new,
+};
+use crate::template::template_admin_incident_status_new::{
+    get_admin_incident_status_new, post_admin_incident_status_new,
+};
+use crate::template::template_admin_login::{get_admin_login, post_admin_login};
+use crate::template::template_admin_project_new::get_admin_project_new;
+use crate::template::template_admin_reports::{get_admin_report, get_admin_reports};
+use crate::template::template_admin_slo::{post_admin_slo_delete, post_admin_slo_new};
+use crate::template::template_admin_subscriptions::{
+    get_admin_subscriptions, post_admin_subscriptions,
+};
+use crate::template::template_embed::get_embed;
+use crate::template::template_feed_atom::get_atom_feed;
+use crate::template::template_feed_rss::get_rss_feed;
+use crate::template::template_history::get_incident_history;
+use crate::template::template_incident_details::get_incident_details;
+use crate::template::template_uptime::get_uptime;
+use crate::update_job::ResultRepository;
+use actix_files::Files;
+use actix_session::SessionMiddleware;
+use actix_session::config::CookieContentSecurity;
+use actix_session::storage::CookieSessionStore;
+use actix_web::cookie::{Key, SameSite};
+use actix_web::web::{Data, ServiceConfig};
+use std::sync::Arc;
+
+/// Every repository that the routes use, implemented by the database
+pub trait Repositories:
+    ProjectRepository
+    + IncidentRepository
+    + StatusRepository
+    + RollupRepository
+    + EmailSubscriptionRepository
+    + SmsSubscriptionRepository
+    + WebhookSubscriptionRepository
+    + CertificateRepository
+    + HeartbeatRepository
+    + AlertRepository
+    + OutageRepository
+    + SloRepository
+    + SettingsRepository
+    + Clone
+    + 'static
+{
+}
+
+impl<T> Repositories for T where
+    T: ProjectRepository
+        + IncidentRepository
+        + StatusRepository
+        + RollupRepository
+        + EmailSubscriptionRepository
+        + SmsSubscriptionRepository
+        + WebhookSubscriptionRepository
+        + CertificateRepository
+        + HeartbeatRepository
+        + AlertRepository
+        + OutageRepository
+        + SloRepository
+        + SettingsRepository
+        + Clone
+        + 'static
+{
+}
+
+/// The notification channels, with their subscribers from `repos`
+pub fn notifiers<R: Repositories>(
+    repos: &R,
+    mailer: &Arc<Mailer>,
+    sms: &Arc<SMSNotifier>,
+    webhook: &Arc<WebhookNotifier>,
+) -> Notifiers {
+    Notifiers {
+        mailer: mailer.clone(),
+        sms: sms.clone(),
+        webhook: webhook.clone(),
+        email_subscription_repo: Box::new(repos.clone()) as Box<dyn EmailSubscriptionRepository>,
+        sms_subscription_repo: Box::new(repos.clone()) as Box<dyn SmsSubscriptionRepository>,
+        webhook_subscription_repo: Box::new(repos.clone())
+            as Box<dyn WebhookSubscriptionRepository>,
+    }
+}
+
+/// Register the repositories, notifiers and every route, shared by the server and the tests
+pub fn configure<R: Repositories>(
+    cfg: &mut ServiceConfig,
+    repos: &R,
+    mailer: &Arc<Mailer>,
+    sms: &Arc<SMSNotifier>,
+    webhook: &Arc<WebhookNotifier>,
+) {
+    cfg.app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn ProjectRepository>
+    ))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn IncidentRepository>
+    ))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn StatusRepository>
+    ))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn EmailSubscriptionRepository>
+    ))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn SmsSubscriptionRepository>
+    ))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn WebhookSubscriptionRepository>
+    ))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn CertificateRepository>
+    ))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn HeartbeatRepository>
+    ))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn AlertRepository>
+    ))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn OutageRepository>
+    ))
+    .app_data(Data::new(Box::new(repos.clone()) as Box<dyn SloRepository>))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn RollupRepository>
+    ))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn ResultRepository>
+    ))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn DashboardRepository>
+    ))
+    .app_data(Data::new(
+        Box::new(repos.clone()) as Box<dyn IndexRepository>
+    ))
+    .app_data(Data::new(PersistedSettings::new(Box::new(repos.clone()))))
+    .app_data(Data::new(notifiers(repos, mailer, sms, webhook)))
+    .app_data(Data::new(mailer.clone()))
+    .app_data(Data::new(sms.clone()))
+    .app_data(Data::new(webhook.clone()))
+    .service(Files::new("/static", "./static"))
+    .service(get_index)
+    .service(head_index)
+    .service(get_uptime)
+    .service(get_incident_details)
+    .service(get_admin_login)
+    .service(post_admin_login)
+    .service(get_rss_feed)
+    .service(get_atom_feed)
+    .service(get_incident_history)
+    .service(get_embed)
+    .service(get_admin_dashboard)
+    .service(post_admin_dashboard)
+    .service(post_admin_slo_new)
+    .service(post_admin_slo_delete)
+    .service(get_admin_reports)
+    .service(get_admin_report)
+    .service(get_admin_subscriptions)
+    .service(post_admin_subscriptions)
+    .service(post_email_subscribe)
+    .service(get_admin_incidents)
+    .service(get_email_confirm)
+    .service(get_admin_incidents_new)
+    .service(post_admin_incidents_new)
+    .service(get_admin_incident_status_new)
+    .service(post_admin_incident_status_new)
+    .service(get_admin_project_new)
+    .service(post_heartbeat)
+    .service(get_agent_projects)
+    .service(post_agent_result);
+}
+
+/// Cookie sessions that the admin login is stored in
+pub fn session_middleware() -> SessionMiddleware<CookieSessionStore> {
+    SessionMiddleware::builder(
+        CookieSessionStore::default(),
+        Key::try_from(settings::private_key().as_slice())
+            .expect("Invalid PRIVATE_KEY, must be 64 bytes"),
+    )
+    .cookie_name("you-up-auth".to_string())
+    .cookie_secure(!settings::insecure())
+    .cookie_content_security(CookieContentSecurity::Private)
+    .cookie_same_site(SameSite::Strict)
+    .build()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::api::agent::{AgentProject, AgentReport};
+    use crate::app::{configure, session_middleware};
+    use crate::data::email_subscription_repository::EmailSubscriptionRepository;
+    use crate::data::heartbeat_repository::HeartbeatRepository;
+    use crate::data::in_memory::InMemoryDatabase;
+    use crate::data::status_repository::StatusRepository;
+    use crate::models::NewStatus;
+    use crate::notifications::mailer::Mailer;
+    use crate::notifications::sms::SMSNotifier;
+    use crate::notifications::webhook::WebhookNotifier;
+    use crate::report::ReportMonth;
+    use crate::settings;
+    use crate::settings::CUSTOM_STYLE;
+    use actix_identity::IdentityMiddleware;
+    use actix_web::App;
+    use actix_web::body::MessageBody;
+    use actix_web::cookie::Cookie;
+    use actix_web::dev::ServiceResponse;
+    use actix_web::http::header::{AUTHORIZATION, LOCATION};
+    use actix_web::http::{Method, StatusCode};
+    use actix_web::test::{TestRequest, call_service, init_service, read_body, read_body_json};
+    use chrono::{Duration, Utc};
+    use std::sync::Arc;
+
+    macro_rules! init_app {
+        ($repos: expr) => {
+            init_service(
+                App::new()
+                    .configure(|cfg| {
+                        configure(
+                            cfg,
+                            &$repos,
+                            &Arc::new(Mailer::default()),
+                            &Arc::new(SMSNotifier::default()),
+                            &Arc::new(WebhookNotifier::default()),
+                        )
+                    })
+                    .wrap(IdentityMiddleware::default())
+                    .wrap(session_middleware()),
+            )
+            .await
+        };
+    }
+
+    /// A project that has been checked a few times, and a status type to post incidents with
+    fn repos() -> InMemoryDatabase {
+        let repos = InMemoryDatabase::default();
+        let project = repos.add_project("Website", "https://example.com");
+        repos.add_incident_status_type("Investigating", "#ff0000");
+
+        let now = Utc::now().naive_utc();
+        let statuses = (1..=3)
+            .map(|i| NewStatus {
+                project: project.id,
+                time: 120,
+                status_code: Some(200),
+                created: now - Duration::minutes(i),
+                success: true,
+                message: None,
+                degraded: false,
+                steps: None,
+                location: settings::location(),
+            })
+            .collect::<Vec<_>>();
+        repos.add_statuses(&statuses).unwrap();
+
+        repos
+    }
+
+    fn login_request(password: &str) -> TestRequest {
+        TestRequest::post().uri("/admin").set_form([
+            ("username", settings::admin_username()),
+            ("password", password.to_string()),
+        ])
+    }
+
+    fn session_cookie<B>(response: &ServiceResponse<B>) -> Cookie<'static> {
+        response
+            .response()
+            .cookies()
+            .find(|c| c.name() == "you-up-auth")
+            .expect("No session cookie")
+            .into_owned()
+    }
+
+    fn location<B>(response: &ServiceResponse<B>) -> &str {
+        response
+            .headers()
+            .get(LOCATION)
+            .and_then(|l| l.to_str().ok())
+            .unwrap_or_default()
+    }
+
+    async fn body_text<B: MessageBody>(response: ServiceResponse<B>) -> String {
+        String::from_utf8(read_body(response).await.to_vec()).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn public_pages_render() {
+        let repos = repos();
+        let project = repos.tables().projects[0].clone();
+        repos
+            .tables()
+            .settings
+            .insert(CUSTOM_STYLE.to_string(), "--in-memory-style".to_string());
+        let app = init_app!(repos);
+
+        for uri in [
+            "/".to_string(),
+            "/uptime".to_string(),
+            "/incidents".to_string(),
+            "/feed/rss".to_string(),
+            "/feed/atom".to_string(),
+            format!("/embed/{}", project.id),
+            "/admin".to_string(),
+        ] {
+            let response = call_service(&app, TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(response.status(), StatusCode::OK, "GET {uri}");
+        }
+
+        let response = call_service(&app, TestRequest::get().uri("/").to_request()).await;
+        let body = body_text(response).await;
+        assert!(body.contains("Website"));
+        assert!(body.contains("--in-memory-style"));
+
+        let response = call_service(
+            &app,
+            TestRequest::default()
+                .method(Method::HEAD)
+                .uri("/")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn admin_pages_need_login() {
+        let repos = repos();
+        let app = init_app!(repos);
+
+        for uri in [
+            "/admin/dashboard",
+            "/admin/incidents",
+            "/admin/incidents/new",
+            "/admin/incident/1/status/new",
+            "/admin/subscriptions",
+            "/admin/reports",
+            "/admin/reports/2026-09/csv",
+            "/admin/project/new?_new_name=API",
+        ] {
+            let response = call_service(&app, TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(
+                response.status(),
+                StatusCode::PERMANENT_REDIRECT,
+                "GET {uri}"
+            );
+            assert_eq!(location(&response), "/admin", "GET {uri}");
+        }
+        assert_eq!(repos.tables().projects.len(), 1);
+
+        let response = call_service(&app, login_request("not the password").to_request()).await;
+        assert_eq!(location(&response), "/admin");
+    }
+
+    #[actix_rt::test]
+    async fn admin_can_manage_projects_and_incidents() {
+        let repos = repos();
+        let project = repos.tables().projects[0].clone();
+        let app = init_app!(repos);
+
+        let response = call_service(
+            &app,
+            login_request(&settings::admin_password()).to_request(),
+        )
+        .await;
+        assert_eq!(location(&response), "/admin/dashboard");
+        let cookie = session_cookie(&response);
+
+        let month = ReportMonth::containing(Utc::now().naive_utc());
+        for uri in [
+            "/admin/dashboard".to_string(),
+            "/admin/incidents".to_string(),
+            "/admin/incidents/new".to_string(),
+            "/admin/subscriptions".to_string(),
+            "/admin/reports".to_string(),
+            format!("/admin/reports/{month}/csv"),
+            format!("/admin/reports/{month}/html"),
+            format!("/admin/reports/{}/csv", month.minus(11)),
+        ] {
+            let request = TestRequest::get().uri(&uri).cookie(cookie.clone());
+            let response = call_service(&app, request.to_request()).await;
+            assert_eq!(response.status(), StatusCode::OK, "GET {uri}");
+        }
+
+        let request = TestRequest::get()
+            .uri("/admin/project/new?_new_name=API")
+            .cookie(cookie.clone());
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(location(&response), "/admin/dashboard");
+        assert_eq!(repos.tables().projects.len(), 2);
+
+        let request = TestRequest::post()
+            .uri("/admin/dashboard")
+            .cookie(cookie.clone())
+            .set_form([
+                ("project_id", project.id.to_string()),
+                ("name", "Homepage".to_string()),
+                ("description", "The main site".to_string()),
+                ("url", project.url.clone()),
+                ("enabled", "on".to_string()),
+                ("check_type", "http".to_string()),
+                ("check_config", "".to_string()),
+                ("check_interval", "60".to_string()),
+                ("latency_warn_ms", "500".to_string()),
+                ("latency_critical_ms", "".to_string()),
+                ("down_after_failures", "2".to_string()),
+                ("up_after_successes", "1".to_string()),
+                ("flap_threshold", "".to_string()),
+                ("flap_window_minutes", "60".to_string()),
+                ("sla_target", "99.9".to_string()),
+            ]);
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let updated = repos.tables().projects[0].clone();
+        assert_eq!(updated.name, "Homepage");
+        assert_eq!(updated.check_interval, 60);
+        assert_eq!(updated.latency_warn_ms, Some(500));
+        assert_eq!(updated.down_after_failures, 2);
+        assert_eq!(updated.sla_target, Some(99.9));
+
+        // Nothing is saved if any section is invalid, and every problem is shown
+        let request = TestRequest::post()
+            .uri("/admin/dashboard")
+            .cookie(cookie.clone())
+            .set_form([
+                ("project_id", project.id.to_string()),
+                ("name", "Homepage".to_string()),
+                ("description", "The main site".to_string()),
+                ("url", project.url.clone()),
+                ("enabled", "on".to_string()),
+                ("check_type", "http".to_string()),
+                ("check_config", "".to_string()),
+                ("check_interval", "30".to_string()),
+                ("latency_warn_ms", "fast".to_string()),
+                ("latency_critical_ms", "".to_string()),
+                ("down_after_failures", "3".to_string()),
+                ("up_after_successes", "1".to_string()),
+                ("flap_threshold", "".to_string()),
+                ("flap_window_minutes", "60".to_string()),
+                ("sla_target", "200".to_string()),
+            ]);
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_text(response).await;
+        assert!(body.contains("Invalid latency thresholds for &#39;Homepage&#39;"));
+        assert!(body.contains("Invalid SLA for &#39;Homepage&#39;"));
+        assert_eq!(repos.tables().projects[0].check_interval, 60);
+        assert_eq!(repos.tables().projects[0].down_after_failures, 2);
+
+        let request = TestRequest::post()
+            .uri("/admin/slo/new")
+            .cookie(cookie.clone())
+            .set_form([
+                ("project_id", project.id.to_string()),
+                ("objective", "99.5".to_string()),
+                ("window_days", "30".to_string()),
+                ("latency_threshold_ms", "".to_string()),
+            ]);
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(repos.tables().slos.len(), 1);
+
+        let slo = repos.tables().slos[0].clone();
+        let request = TestRequest::post()
+            .uri("/admin/slo/delete")
+            .cookie(cookie.clone())
+            .set_form([("slo_id", slo.id.to_string())]);
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(repos.tables().slos.is_empty());
+
+        let request = TestRequest::post()
+            .uri("/admin/subscriptions")
+            .cookie(cookie.clone());
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = TestRequest::post()
+            .uri("/admin/incidents/new")
+            .cookie(cookie.clone())
+            .set_form([
+                ("project", "Homepage"),
+                ("status_type", "Investigating"),
+                ("date", ""),
+                ("message", "Looking into slow responses"),
+            ]);
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let incident = repos.tables().incidents[0].clone();
+        assert_eq!(incident.project, project.id);
+
+        let uri = format!("/admin/incident/{}/status/new", incident.id);
+        let request = TestRequest::get().uri(&uri).cookie(cookie.clone());
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = TestRequest::post()
+            .uri(&uri)
+            .cookie(cookie.clone())
+            .set_form([
+                ("status_type", "Investigating"),
+                ("date", ""),
+                ("message", "Still looking"),
+            ]);
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(repos.tables().incident_status_updates.len(), 2);
+
+        for uri in ["/".to_string(), format!("/incident/{}", incident.id)] {
+            let response = call_service(&app, TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(response.status(), StatusCode::OK, "GET {uri}");
+            assert!(
+                body_text(response).await.contains("Still looking"),
+                "GET {uri}"
+            );
+        }
+    }
+
+    #[actix_rt::test]
+    async fn heartbeats_and_subscriptions() {
+        let repos = repos();
+        let project = repos.tables().projects[0].clone();
+        repos.ensure_heartbeat(project.id).unwrap();
+        let token = repos.tables().heartbeats[0].token.clone();
+        let app = init_app!(repos);
+
+        let uri = format!("/heartbeat/{token}");
+        let response = call_service(&app, TestRequest::post().uri(&uri).to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(repos.tables().heartbeats[0].last_ping.is_some());
+
+        let response = call_service(
+            &app,
+            TestRequest::post().uri("/heartbeat/unknown").to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let request = TestRequest::post()
+            .uri("/subscribe/email")
+            .set_form([("email", "not an email")]);
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(repos.tables().email_subscriptions.is_empty());
+
+        let subscription = repos.subscribe("someone@example.com").unwrap();
+        let uri = format!("/subscribe/email/confirm?id={}", subscription.id);
+        let response = call_service(&app, TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(repos.tables().email_subscriptions[0].confirmed);
+
+        // Agents have to send the AGENT_TOKEN
+        let response = call_service(
+            &app,
+            TestRequest::get().uri("/api/agent/projects").to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_rt::test]
+    async fn agents_fetch_projects_and_report_results() {
+        // SAFETY: every test that sets AGENT_TOKEN sets it to the same value
+        unsafe { std::env::set_var("AGENT_TOKEN", "agent-test-token") };
+        let repos = repos();
+        let project = repos.tables().projects[0].clone();
+        let app = init_app!(repos);
+
+        let request = TestRequest::get()
+            .uri("/api/agent/projects")
+            .insert_header((AUTHORIZATION, "Bearer not-the-token"));
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let request = TestRequest::get()
+            .uri("/api/agent/projects")
+            .insert_header((AUTHORIZATION, "Bearer agent-test-token"));
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let projects: Vec<AgentProject> = read_body_json(response).await;
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].id, project.id);
+        assert_eq!(projects[0].url, project.url);
+
+        let report = |location: &str| AgentReport {
+            location: location.to_string(),
+            project: project.id,
+            created: Utc::now().naive_utc(),
+            duration_ms: 250,
+            success: false,
+            status_code: Some(503),
+            message: Some("Service unavailable".to_string()),
+            degraded: false,
+            steps: Vec::new(),
+        };
+
+        // The server's own location is taken
+        let request = TestRequest::post()
+            .uri("/api/agent/results")
+            .insert_header((AUTHORIZATION, "Bearer agent-test-token"))
+            .set_json(report(&settings::location()));
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Reports have to be recent
+        let request = TestRequest::post()
+            .uri("/api/agent/results")
+            .insert_header((AUTHORIZATION, "Bearer agent-test-token"))
+            .set_json(AgentReport {
+                created: Utc::now().naive_utc() - Duration::days(1),
+                ..report("eu-west")
+            });
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let request = TestRequest::post()
+            .uri("/api/agent/results")
+            .insert_header((AUTHORIZATION, "Bearer agent-test-token"))
+            .set_json(report("eu-west"));
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Disabled projects aren't checked by agents, so late results for them are refused
+        repos.tables().projects[0].enabled = false;
+        let request = TestRequest::post()
+            .uri("/api/agent/results")
+            .insert_header((AUTHORIZATION, "Bearer agent-test-token"))
+            .set_json(report("eu-west"));
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let tables = repos.tables();
+        let stored: Vec<_> = tables
+            .status
+            .iter()
+            .filter(|s| s.location == "eu-west")
+            .collect();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].project, project.id);
+        assert_eq!(stored[0].time, 250);
+        assert!(!stored[0].success);
+    }
+}
@@ -0,0 +1,157 @@
+use regex::Regex;
+use reqwest::Response;
+use serde::{Deserialize, Deserializer};
+
+/// A condition that the body of a response must meet for the check to pass
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BodyAssertion {
+    Contains {
+        value: String,
+    },
+    NotContains {
+        value: String,
+    },
+    Regex {
+        #[serde(deserialize_with = "deserialize_regex")]
+        pattern: Regex,
+    },
+    /// The value at the given JSON pointer (RFC 6901) must equal `equals`
+    JsonPointer {
+        pointer: String,
+        equals: serde_json::Value,
+    },
+}
+
+impl BodyAssertion {
+    /// Check the assertion against a response body, giving the reason on failure
+    pub fn check(&self, body: &str) -> Result<(), String> {
+        match self {
+            BodyAssertion::Contains { value } => {
+                if body.contains(value.as_str()) {
+                    Ok(())
+                } else {
+                    Err(format!("body does not contain '{value}'"))
+                }
+            }
+            BodyAssertion::NotContains { value } => {
+                if body.contains(value.as_str()) {
+                    Err(format!("body contains '{value}'"))
+                } else {
+                    Ok(())
+                }
+            }
+            BodyAssertion::Regex { pattern } => {
+                if pattern.is_match(body) {
+                    Ok(())
+                } else {
+                    Err(format!("body does not match /{pattern}/"))
+                }
+            }
+            BodyAssertion::JsonPointer { pointer, equals } => {
+                let json = serde_json::from_str::<serde_json::Value>(body)
+                    .map_err(|e| format!("body is not valid JSON: {e}"))?;
+
+                match json.pointer(pointer) {
+                    Some(value) if value == equals => Ok(()),
+                    Some(value) => Err(format!("{pointer} is {value}, expected {equals}")),
+                    None => Err(format!("{pointer} not found in body")),
+                }
+            }
+        }
+    }
+}
+
+/// The largest response body that assertions are checked against
+pub const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Read a response body in chunks, giving up once it is larger than `MAX_BODY_BYTES`
+pub async fn read_body(mut response: Response) -> Result<String, String> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Unable to read body: {e}"))?
+    {
+        if body.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err(format!(
+                "Assertion failed: body is larger than {} KiB",
+                MAX_BODY_BYTES / 1024
+            ));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Check all assertions against a body, stopping at the first failure
+pub fn check_body(assertions: &[BodyAssertion], body: &str) -> Result<(), String> {
+    assertions
+        .iter()
+        .try_for_each(|a| a.check(body))
+        .map_err(|reason| format!("Assertion failed: {reason}"))
+}
+
+pub(crate) fn deserialize_regex<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::checks::assertions::{BodyAssertion, check_body};
+
+    fn parse(json: &str) -> Vec<BodyAssertion> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn contains_and_not_contains() {
+        let assertions = parse(
+            r#"[{"type": "contains", "value": "Welcome"}, {"type": "not_contains", "value": "Error"}]"#,
+        );
+
+        assert!(check_body(&assertions, "<h1>Welcome</h1>").is_ok());
+        assert_eq!(
+            check_body(&assertions, "<h1>Welcome</h1><p>Error</p>"),
+            Err("Assertion failed: body contains 'Error'".to_string())
+        );
+        assert_eq!(
+            check_body(&assertions, "<h1>Bad Gateway</h1>"),
+            Err("Assertion failed: body does not contain 'Welcome'".to_string())
+        );
+    }
+
+    #[test]
+    fn regex() {
+        let assertions = parse(r#"[{"type": "regex", "pattern": "version \\d+\\.\\d+"}]"#);
+
+        assert!(check_body(&assertions, "running version 1.2").is_ok());
+        assert!(check_body(&assertions, "running version unknown").is_err());
+    }
+
+    #[test]
+    fn invalid_regex_is_rejected() {
+        assert!(
+            serde_json::from_str::<Vec<BodyAssertion>>(r#"[{"type": "regex", "pattern": "("}]"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn json_pointer() {
+        let assertions =
+            parse(r#"[{"type": "json_pointer", "pointer": "/status", "equals": "ok"}]"#);
+
+        assert!(check_body(&assertions, r#"{"status": "ok"}"#).is_ok());
+        assert_eq!(
+            check_body(&assertions, r#"{"status": "down"}"#),
+            Err(r#"Assertion failed: /status is "down", expected "ok""#.to_string())
+        );
+        assert!(check_body(&assertions, "{}").is_err());
+        assert!(check_body(&assertions, "not json").is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::Context;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use x509_parser::extensions::GeneralName;
+use x509_parser::parse_x509_certificate;
+
+/// Days before expiry at which subscribers are warned, most severe last
+pub const EXPIRY_NOTIFICATION_THRESHOLDS: [i32; 4] = [30, 14, 7, 1];
+
+/// Details of the leaf certificate presented by a server
+#[derive(Clone, Debug)]
+pub struct CertificateInfo {
+    pub not_after: NaiveDateTime,
+    pub issuer: String,
+    pub sans: Vec<String>,
+}
+
+impl CertificateInfo {
+    pub fn from_der(der: &[u8]) -> anyhow::Result<Self> {
+        let (_, certificate) =
+            parse_x509_certificate(der).context("Unable to parse certificate")?;
+
+        let not_after = DateTime::from_timestamp(certificate.validity().not_after.timestamp(), 0)
+            .context("Certificate expiry out of range")?
+            .naive_utc();
+
+        let sans = certificate
+            .subject_alternative_name()
+            .context("Invalid subject alternative names")?
+            .map(|san| {
+                san.value
+                    .general_names
+                    .iter()
+                    .filter_map(|name| match name {
+                        GeneralName::DNSName(dns) => Some(dns.to_string()),
+                        GeneralName::IPAddress(ip) => match ip.len() {
+                            4 => <[u8; 4]>::try_from(*ip)
+                                .ok()
+                                .map(|ip| std::net::Ipv4Addr::from(ip).to_string()),
+                            16 => <[u8; 16]>::try_from(*ip)
+                                .ok()
+                                .map(|ip| std::net::Ipv6Addr::from(ip).to_string()),
+                            _ => None,
+                        },
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            not_after,
+            issuer: certificate.issuer().to_string(),
+            sans,
+        })
+    }
+
+    pub fn days_remaining(&self) -> i64 {
+        self.not_after
+            .signed_duration_since(Utc::now().naive_utc())
+            .num_days()
+    }
+}
+
+/// Find the expiry threshold that subscribers should be warned about, if a new one has been
+/// crossed since `last_notified`
+pub fn crossed_threshold(days_remaining: i64, last_notified: Option<i32>) -> Option<i32> {
+    let current = EXPIRY_NOTIFICATION_THRESHOLDS
+        .iter()
+        .copied()
+        .filter(|threshold| days_remaining <= i64::from(*threshold))
+        .min()?;
+
+    match last_notified {
+        Some(last_notified) if last_notified <= current => None,
+        _ => Some(current),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::checks::certificate::crossed_threshold;
+
+    #[test]
+    fn no_threshold_before_30_days() {
+        assert_eq!(crossed_threshold(90, None), None);
+        assert_eq!(crossed_threshold(31, None), None);
+    }
+
+    #[test]
+    fn notify_once_per_threshold() {
+        assert_eq!(crossed_threshold(30, None), Some(30));
+        assert_eq!(crossed_threshold(29, Some(30)), None);
+        assert_eq!(crossed_threshold(14, Some(30)), Some(14));
+        assert_eq!(crossed_threshold(10, Some(14)), None);
+        assert_eq!(crossed_threshold(0, Some(14)), Some(1));
+    }
+
+    #[test]
+    fn skipped_thresholds_only_notify_the_most_severe() {
+        assert_eq!(crossed_threshold(5, None), Some(7));
+        assert_eq!(crossed_threshold(1, Some(30)), Some(1));
+    }
+}
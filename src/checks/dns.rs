@@ -0,0 +1,335 @@
+use crate::checks::{Check, CheckFuture, CheckResult, default_timeout_ms};
+use actix_rt::time::timeout;
+use anyhow::Context;
+use hickory_resolver::config::{NameServerConfigGroup, ResolverConfig};
+use hickory_resolver::name_server::TokioConnectionProvider;
+use hickory_resolver::proto::rr::RecordType;
+use hickory_resolver::{Resolver, TokioResolver};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+pub const CHECK_TYPE: &str = "dns";
+
+/// The record types that can be asserted on
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DnsRecordType {
+    #[default]
+    A,
+    Aaaa,
+    Cname,
+    Mx,
+    Txt,
+}
+
+impl DnsRecordType {
+    fn record_type(self) -> RecordType {
+        match self {
+            DnsRecordType::A => RecordType::A,
+            DnsRecordType::Aaaa => RecordType::AAAA,
+            DnsRecordType::Cname => RecordType::CNAME,
+            DnsRecordType::Mx => RecordType::MX,
+            DnsRecordType::Txt => RecordType::TXT,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DnsCheckConfig {
+    pub name: String,
+    #[serde(default)]
+    pub record_type: DnsRecordType,
+    /// `ip` or `ip:port` of the resolver to ask, the system resolver is used if not set
+    #[serde(default)]
+    pub resolver: Option<String>,
+    /// The exact set of answers expected, any answer is accepted if empty
+    #[serde(default)]
+    pub expected: Vec<String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl DnsCheckConfig {
+    pub fn resolver_address(&self) -> anyhow::Result<Option<SocketAddr>> {
+        self.resolver
+            .as_deref()
+            .map(|resolver| {
+                resolver
+                    .parse::<SocketAddr>()
+                    .or_else(|_| resolver.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+                    .with_context(|| format!("Invalid resolver address '{resolver}'"))
+            })
+            .transpose()
+    }
+}
+
+/// Normalise an answer so that trailing dots and case in names don't matter
+fn normalise_answer(record_type: DnsRecordType, answer: &str) -> String {
+    match record_type {
+        DnsRecordType::Txt => answer.to_string(),
+        _ => answer.trim().trim_end_matches('.').to_lowercase(),
+    }
+}
+
+/// Compare the answers from the resolver to the expected ones, returning why they differ
+pub fn check_answers(
+    record_type: DnsRecordType,
+    expected: &[String],
+    answers: &[String],
+) -> Result<(), String> {
+    let answers: BTreeSet<String> = answers
+        .iter()
+        .map(|a| normalise_answer(record_type, a))
+        .collect();
+
+    if answers.is_empty() {
+        return Err("No records found".to_string());
+    }
+
+    if expected.is_empty() {
+        return Ok(());
+    }
+
+    let expected: BTreeSet<String> = expected
+        .iter()
+        .map(|e| normalise_answer(record_type, e))
+        .collect();
+
+    if expected == answers {
+        Ok(())
+    } else {
+        Err(format!(
+            "Expected {:?} but got {:?}",
+            expected.into_iter().collect::<Vec<_>>(),
+            answers.into_iter().collect::<Vec<_>>()
+        ))
+    }
+}
+
+/// Resolves a name and checks the answer set
+pub struct DnsCheck {
+    config: DnsCheckConfig,
+    resolver: TokioResolver,
+}
+
+impl DnsCheck {
+    pub fn new(config: DnsCheckConfig) -> anyhow::Result<Self> {
+        let mut builder = match config.resolver_address()? {
+            Some(address) => Resolver::builder_with_config(
+                ResolverConfig::from_parts(
+                    None,
+                    vec![],
+                    NameServerConfigGroup::from_ips_clear(&[address.ip()], address.port(), true),
+                ),
+                TokioConnectionProvider::default(),
+            ),
+            None => TokioResolver::builder_tokio().context("Unable to read system DNS config")?,
+        };
+
+        builder.options_mut().timeout = Duration::from_millis(config.timeout_ms);
+
+        Ok(Self {
+            resolver: builder.build(),
+            config,
+        })
+    }
+}
+
+impl Check for DnsCheck {
+    fn run(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            let DnsCheckConfig {
+                name,
+                record_type,
+                expected,
+                timeout_ms,
+                ..
+            } = &self.config;
+
+            let lookup_start_time = Instant::now();
+            let lookup = timeout(
+                Duration::from_millis(*timeout_ms),
+                self.resolver
+                    .lookup(name.as_str(), record_type.record_type()),
+            )
+            .await;
+            let lookup_duration = lookup_start_time.elapsed();
+
+            let answers = match lookup {
+                // The answer can include the CNAMEs followed to get to the record, skip those
+                Ok(Ok(lookup)) => lookup
+                    .record_iter()
+                    .filter(|r| r.record_type() == record_type.record_type())
+                    .map(|r| r.data().to_string())
+                    .collect::<Vec<_>>(),
+                Ok(Err(e)) if e.is_no_records_found() => vec![],
+                Ok(Err(e)) => {
+                    return CheckResult::failure(
+                        lookup_duration,
+                        None,
+                        format!("Unable to resolve {record_type:?} {name}: {e}"),
+                    );
+                }
+                Err(_) => {
+                    return CheckResult::failure(
+                        lookup_duration,
+                        None,
+                        format!("Timed out resolving {record_type:?} {name} after {timeout_ms}ms"),
+                    );
+                }
+            };
+
+            match check_answers(*record_type, expected, &answers) {
+                Ok(()) => CheckResult::success(lookup_duration, None),
+                Err(reason) => CheckResult::failure(
+                    lookup_duration,
+                    None,
+                    format!("{record_type:?} {name}: {reason}"),
+                ),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::checks::Check;
+    use crate::checks::dns::{DnsCheck, DnsCheckConfig, DnsRecordType, check_answers};
+    use actix_rt::net::UdpSocket;
+    use hickory_resolver::proto::op::{Message, MessageType, ResponseCode};
+    use hickory_resolver::proto::rr::rdata::A;
+    use hickory_resolver::proto::rr::{RData, Record};
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    /// Start a DNS server that answers every query for `stub.test.` with the given A records
+    async fn stub_server(addresses: Vec<Ipv4Addr>) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = socket.local_addr().unwrap();
+
+        actix_rt::spawn(async move {
+            let mut buf = [0u8; 512];
+            loop {
+                let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let request = Message::from_vec(&buf[..len]).unwrap();
+                let query = request.queries()[0].clone();
+
+                let mut response = Message::new();
+                response
+                    .set_id(request.id())
+                    .set_message_type(MessageType::Response)
+                    .set_recursion_available(true)
+                    .add_query(query.clone());
+
+                if query.name().to_ascii() == "stub.test." {
+                    for ip in &addresses {
+                        response.add_answer(Record::from_rdata(
+                            query.name().clone(),
+                            60,
+                            RData::A(A(*ip)),
+                        ));
+                    }
+                } else {
+                    response.set_response_code(ResponseCode::NXDomain);
+                }
+
+                socket
+                    .send_to(&response.to_vec().unwrap(), peer)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        address
+    }
+
+    fn config(resolver: SocketAddr, name: &str, expected: &[&str]) -> DnsCheckConfig {
+        DnsCheckConfig {
+            name: name.to_string(),
+            record_type: DnsRecordType::A,
+            resolver: Some(resolver.to_string()),
+            expected: expected.iter().map(|e| e.to_string()).collect(),
+            timeout_ms: 1000,
+        }
+    }
+
+    #[test]
+    fn answers_are_compared_as_a_set() {
+        let expected = vec!["Mail.Example.com.".to_string(), "10.0.0.1".to_string()];
+        let answers = vec!["10.0.0.1".to_string(), "mail.example.com".to_string()];
+        assert!(check_answers(DnsRecordType::Cname, &expected, &answers).is_ok());
+
+        let answers = vec!["10.0.0.2".to_string()];
+        assert!(check_answers(DnsRecordType::A, &expected, &answers).is_err());
+    }
+
+    #[test]
+    fn any_answer_is_accepted_without_expectations() {
+        assert!(check_answers(DnsRecordType::A, &[], &["10.0.0.1".to_string()]).is_ok());
+        assert!(check_answers(DnsRecordType::A, &[], &[]).is_err());
+    }
+
+    #[test]
+    fn txt_answers_are_case_sensitive() {
+        let expected = vec!["v=spf1 -all".to_string()];
+        let answers = vec!["V=SPF1 -ALL".to_string()];
+        assert!(check_answers(DnsRecordType::Txt, &expected, &answers).is_err());
+    }
+
+    #[test]
+    fn resolver_port_defaults_to_53() {
+        let mut config = config("127.0.0.1:5353".parse().unwrap(), "stub.test", &[]);
+        assert_eq!(
+            config.resolver_address().unwrap(),
+            Some("127.0.0.1:5353".parse().unwrap())
+        );
+
+        config.resolver = Some("127.0.0.1".to_string());
+        assert_eq!(
+            config.resolver_address().unwrap(),
+            Some("127.0.0.1:53".parse().unwrap())
+        );
+
+        config.resolver = Some("not an address".to_string());
+        assert!(config.resolver_address().is_err());
+    }
+
+    #[actix_rt::test]
+    async fn resolve_expected_records() {
+        let server =
+            stub_server(vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2)]).await;
+
+        let result = DnsCheck::new(config(server, "stub.test.", &["10.0.0.2", "10.0.0.1"]))
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(result.success, "{:?}", result.message);
+    }
+
+    #[actix_rt::test]
+    async fn resolve_unexpected_records() {
+        let server = stub_server(vec![Ipv4Addr::new(10, 0, 0, 3)]).await;
+
+        let result = DnsCheck::new(config(server, "stub.test.", &["10.0.0.1"]))
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(!result.success);
+        assert!(result.message.unwrap().contains("10.0.0.3"));
+    }
+
+    #[actix_rt::test]
+    async fn resolve_missing_name() {
+        let server = stub_server(vec![Ipv4Addr::new(10, 0, 0, 1)]).await;
+
+        let result = DnsCheck::new(config(server, "missing.test.", &[]))
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(!result.success);
+    }
+}
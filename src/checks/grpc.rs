@@ -0,0 +1,246 @@
+use crate::checks::{Check, CheckFuture, CheckResult, default_timeout_ms};
+use actix_rt::time::timeout;
+use anyhow::Context;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tonic::Code;
+use tonic::transport::{ClientTlsConfig, Endpoint};
+use tonic_health::pb::HealthCheckRequest;
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::health_client::HealthClient;
+
+pub const CHECK_TYPE: &str = "grpc";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GrpcCheckConfig {
+    pub host: String,
+    pub port: u16,
+    /// The service to ask about, by default the health of the whole server
+    #[serde(default)]
+    pub service: String,
+    #[serde(default)]
+    pub tls: bool,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl GrpcCheckConfig {
+    pub fn endpoint(&self) -> anyhow::Result<Endpoint> {
+        let scheme = if self.tls { "https" } else { "http" };
+        let timeout = Duration::from_millis(self.timeout_ms);
+
+        let endpoint = Endpoint::from_shared(format!("{scheme}://{}:{}", self.host, self.port))
+            .context("Invalid gRPC address")?
+            .connect_timeout(timeout)
+            .timeout(timeout);
+
+        if self.tls {
+            endpoint
+                .tls_config(
+                    ClientTlsConfig::new()
+                        .with_native_roots()
+                        .domain_name(&self.host),
+                )
+                .context("Invalid TLS config")
+        } else {
+            Ok(endpoint)
+        }
+    }
+}
+
+/// Describe the service being checked, the empty name is the server as a whole
+fn describe_service(service: &str) -> String {
+    if service.is_empty() {
+        "Server".to_string()
+    } else {
+        format!("Service '{service}'")
+    }
+}
+
+/// Turn the status reported by the health service into a result, only `SERVING` is up
+pub fn serving_result(duration: Duration, service: &str, status: i32) -> CheckResult {
+    let status = ServingStatus::try_from(status).unwrap_or(ServingStatus::Unknown);
+
+    if status == ServingStatus::Serving {
+        let mut result = CheckResult::success(duration, None);
+        result.message = Some(status.as_str_name().to_string());
+        result
+    } else {
+        CheckResult::failure(
+            duration,
+            None,
+            format!("{} is {}", describe_service(service), status.as_str_name()),
+        )
+    }
+}
+
+/// Calls `grpc.health.v1.Health/Check` and expects the service to be serving
+pub struct GrpcCheck {
+    config: GrpcCheckConfig,
+    endpoint: Endpoint,
+}
+
+impl GrpcCheck {
+    pub fn new(config: GrpcCheckConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            endpoint: config.endpoint()?,
+            config,
+        })
+    }
+}
+
+impl Check for GrpcCheck {
+    fn run(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            let GrpcCheckConfig {
+                host,
+                port,
+                service,
+                timeout_ms,
+                ..
+            } = &self.config;
+
+            let request_start_time = Instant::now();
+            let response = timeout(Duration::from_millis(*timeout_ms), async {
+                let channel = self
+                    .endpoint
+                    .connect()
+                    .await
+                    .map_err(|e| format!("Unable to connect to {host}:{port}: {e}"))?;
+
+                HealthClient::new(channel)
+                    .check(HealthCheckRequest {
+                        service: service.clone(),
+                    })
+                    .await
+                    .map(|response| response.into_inner().status)
+                    .map_err(|status| match status.code() {
+                        // Servers are meant to use NOT_FOUND for services they don't know about
+                        Code::NotFound => format!(
+                            "{} is {}",
+                            describe_service(service),
+                            ServingStatus::ServiceUnknown.as_str_name()
+                        ),
+                        Code::Unimplemented => {
+                            "Server doesn't implement the gRPC health service".to_string()
+                        }
+                        code => format!("Health check failed with {code:?}: {}", status.message()),
+                    })
+            })
+            .await;
+            let request_duration = request_start_time.elapsed();
+
+            match response {
+                Ok(Ok(status)) => serving_result(request_duration, service, status),
+                Ok(Err(message)) => CheckResult::failure(request_duration, None, message),
+                Err(_) => CheckResult::failure(
+                    request_duration,
+                    None,
+                    format!("Timed out checking {host}:{port} after {timeout_ms}ms"),
+                ),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::checks::Check;
+    use crate::checks::grpc::{GrpcCheck, GrpcCheckConfig, serving_result};
+    use actix_rt::net::TcpListener;
+    use std::time::Duration;
+    use tonic::transport::Server;
+    use tonic::transport::server::TcpIncoming;
+    use tonic_health::ServingStatus;
+    use tonic_health::server::health_reporter;
+
+    /// Run a health service that reports the given status for `test.Service`
+    async fn health_server(status: ServingStatus) -> u16 {
+        let (reporter, service) = health_reporter();
+        reporter.set_service_status("test.Service", status).await;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        actix_rt::spawn(
+            Server::builder()
+                .add_service(service)
+                .serve_with_incoming(TcpIncoming::from(listener)),
+        );
+
+        port
+    }
+
+    fn check(port: u16, service: &str) -> GrpcCheck {
+        GrpcCheck::new(GrpcCheckConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            service: service.to_string(),
+            tls: false,
+            timeout_ms: 2000,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn serving_status() {
+        let result = serving_result(Duration::ZERO, "", 1);
+        assert!(result.success);
+        assert_eq!(result.message.unwrap(), "SERVING");
+
+        let result = serving_result(Duration::ZERO, "test.Service", 2);
+        assert!(!result.success);
+        assert_eq!(
+            result.message.unwrap(),
+            "Service 'test.Service' is NOT_SERVING"
+        );
+
+        let result = serving_result(Duration::ZERO, "", 0);
+        assert!(!result.success);
+        assert_eq!(result.message.unwrap(), "Server is UNKNOWN");
+
+        assert!(!serving_result(Duration::ZERO, "", 42).success);
+    }
+
+    #[actix_rt::test]
+    async fn serving() {
+        let port = health_server(ServingStatus::Serving).await;
+
+        // The whole server is always reported as serving
+        assert!(check(port, "").run().await.success);
+        assert!(check(port, "test.Service").run().await.success);
+    }
+
+    #[actix_rt::test]
+    async fn not_serving() {
+        let port = health_server(ServingStatus::NotServing).await;
+
+        let result = check(port, "test.Service").run().await;
+        assert!(!result.success);
+        assert!(result.message.unwrap().contains("NOT_SERVING"));
+    }
+
+    #[actix_rt::test]
+    async fn unknown_service() {
+        let port = health_server(ServingStatus::Serving).await;
+
+        let result = check(port, "other.Service").run().await;
+        assert!(!result.success);
+        assert!(result.message.unwrap().contains("SERVICE_UNKNOWN"));
+    }
+
+    #[actix_rt::test]
+    async fn no_server() {
+        // Bind then drop the listener so we know the port is free
+        let port = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let result = check(port, "").run().await;
+        assert!(!result.success);
+        assert!(result.message.unwrap().contains("Unable to connect"));
+    }
+}
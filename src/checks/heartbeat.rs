@@ -0,0 +1,146 @@
+use crate::checks::{Check, CheckFuture, CheckResult};
+use crate::models::Heartbeat;
+use crate::time_formatter;
+use chrono::{NaiveDateTime, Utc};
+use serde::Deserialize;
+use std::time::Duration;
+
+pub const CHECK_TYPE: &str = "heartbeat";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HeartbeatCheckConfig {
+    /// The monitored job must ping at least this often
+    pub interval_minutes: u32,
+    /// Extra time allowed on top of the interval before the job is considered down
+    #[serde(default = "default_grace_minutes")]
+    pub grace_minutes: u32,
+}
+
+fn default_grace_minutes() -> u32 {
+    5
+}
+
+/// Checks that the monitored job has called `POST /heartbeat/{token}` recently, rather than
+/// polling anything itself
+pub struct HeartbeatCheck {
+    config: HeartbeatCheckConfig,
+    heartbeat: Heartbeat,
+}
+
+impl HeartbeatCheck {
+    pub fn new(config: HeartbeatCheckConfig, heartbeat: Heartbeat) -> Self {
+        Self { config, heartbeat }
+    }
+}
+
+impl Check for HeartbeatCheck {
+    fn run(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            evaluate_heartbeat(
+                &self.config,
+                self.heartbeat.last_ping,
+                self.heartbeat.created,
+                Utc::now().naive_utc(),
+            )
+        })
+    }
+
+    fn retryable(&self) -> bool {
+        false
+    }
+}
+
+/// A heartbeat that has never been pinged is measured from when it was created, to give the job
+/// a chance to start
+pub fn evaluate_heartbeat(
+    config: &HeartbeatCheckConfig,
+    last_ping: Option<NaiveDateTime>,
+    created: NaiveDateTime,
+    now: NaiveDateTime,
+) -> CheckResult {
+    let last_seen = last_ping.unwrap_or(created);
+    let allowed = chrono::Duration::minutes(
+        i64::from(config.interval_minutes) + i64::from(config.grace_minutes),
+    );
+    let since_last_seen = now.signed_duration_since(last_seen);
+
+    if since_last_seen <= allowed {
+        CheckResult::success(Duration::ZERO, None)
+    } else if last_ping.is_none() {
+        CheckResult::failure(Duration::ZERO, None, "No heartbeat received yet")
+    } else {
+        CheckResult::failure(
+            Duration::ZERO,
+            None,
+            format!(
+                "Missed heartbeat, last received {} ago",
+                time_formatter::format_duration(&since_last_seen)
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::checks::heartbeat::{HeartbeatCheckConfig, evaluate_heartbeat};
+    use chrono::{Duration, TimeZone, Utc};
+
+    const CONFIG: HeartbeatCheckConfig = HeartbeatCheckConfig {
+        interval_minutes: 60,
+        grace_minutes: 10,
+    };
+
+    #[test]
+    fn recent_ping_is_up() {
+        let now = Utc
+            .with_ymd_and_hms(2020, 9, 25, 12, 0, 0)
+            .unwrap()
+            .naive_utc();
+        let created = now - Duration::days(7);
+
+        assert!(
+            evaluate_heartbeat(&CONFIG, Some(now - Duration::minutes(69)), created, now).success
+        );
+    }
+
+    #[test]
+    fn missed_ping_is_down() {
+        let now = Utc
+            .with_ymd_and_hms(2020, 9, 25, 12, 0, 0)
+            .unwrap()
+            .naive_utc();
+        let created = now - Duration::days(7);
+
+        let result = evaluate_heartbeat(&CONFIG, Some(now - Duration::minutes(130)), created, now);
+        assert!(!result.success);
+        assert_eq!(
+            result.message.unwrap(),
+            "Missed heartbeat, last received 2 hours 10 minutes ago"
+        );
+    }
+
+    #[test]
+    fn never_pinged_uses_creation_time() {
+        let now = Utc
+            .with_ymd_and_hms(2020, 9, 25, 12, 0, 0)
+            .unwrap()
+            .naive_utc();
+
+        assert!(evaluate_heartbeat(&CONFIG, None, now - Duration::minutes(30), now).success);
+        assert!(!evaluate_heartbeat(&CONFIG, None, now - Duration::minutes(90), now).success);
+    }
+
+    #[test]
+    fn large_intervals_do_not_overflow() {
+        let config = HeartbeatCheckConfig {
+            interval_minutes: u32::MAX,
+            grace_minutes: u32::MAX,
+        };
+        let now = Utc
+            .with_ymd_and_hms(2020, 9, 25, 12, 0, 0)
+            .unwrap()
+            .naive_utc();
+
+        assert!(evaluate_heartbeat(&config, Some(now - Duration::days(365)), now, now).success);
+    }
+}
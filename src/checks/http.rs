@@ -0,0 +1,484 @@
+use crate::checks::assertions::{BodyAssertion, check_body, read_body};
+use crate::checks::certificate::CertificateInfo;
+use crate::checks::{Check, CheckContext, CheckFuture, CheckResult, default_timeout_ms};
+use crate::models::Project;
+use crate::settings;
+use anyhow::Context;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::tls::TlsInfo;
+use reqwest::{Client, Method, StatusCode};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+pub const CHECK_TYPE: &str = "http";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HttpCheckConfig {
+    #[serde(default = "default_method", deserialize_with = "deserialize_method")]
+    pub method: Method,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Status codes that count as up, any 2xx status if empty
+    #[serde(default)]
+    pub expected_status: Vec<ExpectedStatus>,
+    #[serde(default = "default_follow_redirects")]
+    pub follow_redirects: bool,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Conditions the response body must meet, on top of an expected status code
+    #[serde(default)]
+    pub assertions: Vec<BodyAssertion>,
+}
+
+impl HttpCheckConfig {
+    pub fn header_map(&self) -> anyhow::Result<HeaderMap> {
+        self.headers
+            .iter()
+            .map(|(name, value)| {
+                Ok((
+                    HeaderName::try_from(name.as_str())
+                        .with_context(|| format!("Invalid header name '{name}'"))?,
+                    HeaderValue::try_from(value.as_str())
+                        .with_context(|| format!("Invalid value for header '{name}'"))?,
+                ))
+            })
+            .collect()
+    }
+
+    pub fn is_expected_status(&self, status: StatusCode) -> bool {
+        is_expected_status(&self.expected_status, status)
+    }
+}
+
+/// Check a status against a list of expected statuses, any 2xx status if the list is empty
+pub(crate) fn is_expected_status(expected_status: &[ExpectedStatus], status: StatusCode) -> bool {
+    if expected_status.is_empty() {
+        status.is_success()
+    } else {
+        expected_status.iter().any(|e| e.matches(status))
+    }
+}
+
+/// Describe a list of expected statuses for use in failure messages
+pub(crate) fn describe_expected_status(expected_status: &[ExpectedStatus]) -> String {
+    if expected_status.is_empty() {
+        "2xx".to_string()
+    } else {
+        expected_status
+            .iter()
+            .map(ExpectedStatus::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+pub(crate) fn default_method() -> Method {
+    Method::GET
+}
+
+fn default_follow_redirects() -> bool {
+    true
+}
+
+pub(crate) fn deserialize_method<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Method, D::Error> {
+    let method = String::deserialize(deserializer)?;
+    Method::from_bytes(method.to_ascii_uppercase().as_bytes()).map_err(serde::de::Error::custom)
+}
+
+/// A status code that is considered healthy, either exactly (`401`) or by class (`"3xx"`)
+#[derive(Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(try_from = "serde_json::Value")]
+pub enum ExpectedStatus {
+    Code(u16),
+    Class(u16),
+}
+
+impl ExpectedStatus {
+    pub fn matches(&self, status: StatusCode) -> bool {
+        match self {
+            ExpectedStatus::Code(code) => status.as_u16() == *code,
+            ExpectedStatus::Class(class) => status.as_u16() / 100 == *class,
+        }
+    }
+}
+
+impl TryFrom<serde_json::Value> for ExpectedStatus {
+    type Error = String;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        let invalid = || format!("Invalid expected status {value}, use e.g. 401 or \"3xx\"");
+
+        match &value {
+            serde_json::Value::Number(code) => code
+                .as_u64()
+                .and_then(|code| u16::try_from(code).ok())
+                .map(ExpectedStatus::Code)
+                .ok_or_else(invalid),
+            serde_json::Value::String(status) => match status.to_ascii_lowercase().as_bytes() {
+                [class @ b'1'..=b'5', b'x', b'x'] => {
+                    Ok(ExpectedStatus::Class(u16::from(class - b'0')))
+                }
+                _ => status
+                    .parse()
+                    .map(ExpectedStatus::Code)
+                    .map_err(|_| invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Display for ExpectedStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpectedStatus::Code(code) => write!(f, "{code}"),
+            ExpectedStatus::Class(class) => write!(f, "{class}xx"),
+        }
+    }
+}
+
+/// Checks that a request to the project url returns an expected response
+pub struct HttpCheck {
+    client: Client,
+    url: String,
+    headers: HeaderMap,
+    config: HttpCheckConfig,
+}
+
+impl HttpCheck {
+    pub fn new(
+        context: &CheckContext,
+        project: &Project,
+        config: HttpCheckConfig,
+    ) -> anyhow::Result<Self> {
+        let client = if config.follow_redirects {
+            context.http.clone()
+        } else {
+            context.http_no_redirect.clone()
+        };
+
+        Ok(Self {
+            client,
+            url: project.url.clone(),
+            headers: config.header_map()?,
+            config,
+        })
+    }
+}
+
+impl Check for HttpCheck {
+    fn run(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            let mut request = self
+                .client
+                .request(self.config.method.clone(), &self.url)
+                .headers(self.headers.clone())
+                .timeout(Duration::from_millis(self.config.timeout_ms));
+            if let Some(body) = &self.config.body {
+                request = request.body(body.clone());
+            }
+
+            let req_start_time = Instant::now();
+            let response = request.send().await;
+            let req_duration = req_start_time.elapsed();
+
+            let response = match response {
+                Ok(response) => response,
+                Err(e) if e.is_timeout() => {
+                    return CheckResult::failure(
+                        req_duration,
+                        None,
+                        format!("Request timed out after {}ms", self.config.timeout_ms),
+                    );
+                }
+                Err(e) => {
+                    return CheckResult::failure(
+                        req_duration,
+                        None,
+                        format!("Request failed: {e}"),
+                    );
+                }
+            };
+
+            let status = response.status();
+            let mut result = if self.config.is_expected_status(status) {
+                CheckResult::success(req_duration, Some(status.as_u16()))
+            } else {
+                CheckResult::failure(
+                    req_duration,
+                    Some(status.as_u16()),
+                    format!(
+                        "Received status code {status}, expected {}",
+                        describe_expected_status(&self.config.expected_status)
+                    ),
+                )
+            };
+
+            result.certificate = response
+                .extensions()
+                .get::<TlsInfo>()
+                .and_then(|tls| tls.peer_certificate())
+                .and_then(|der| match CertificateInfo::from_der(der) {
+                    Ok(certificate) => Some(certificate),
+                    Err(e) => {
+                        tracing::warn!("Unable to read certificate for {}: {e:?}", self.url);
+                        None
+                    }
+                });
+
+            if result.success
+                && let Some(certificate) = &result.certificate
+                && certificate.days_remaining() < settings::certificate_warning_days()
+            {
+                let days_remaining = certificate.days_remaining();
+                result.mark_degraded(format!("Certificate expires in {days_remaining} days"));
+            }
+
+            if result.success && !self.config.assertions.is_empty() {
+                let status_code = result.status_code;
+                let failure = read_body(response)
+                    .await
+                    .and_then(|body| check_body(&self.config.assertions, &body))
+                    .err();
+
+                if let Some(reason) = failure {
+                    let certificate = result.certificate.take();
+                    result = CheckResult::failure(req_duration, status_code, reason);
+                    result.certificate = certificate;
+                }
+            }
+
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::checks::assertions::MAX_BODY_BYTES;
+    use crate::checks::http::{ExpectedStatus, HttpCheck, HttpCheckConfig};
+    use crate::checks::{Check, CheckContext};
+    use crate::models::{Project, fixtures};
+    use actix_rt::net::TcpListener;
+    use chrono::Datelike;
+    use reqwest::{Client, Method, StatusCode};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::TlsAcceptor;
+    use tokio_rustls::rustls::ServerConfig;
+    use tokio_rustls::rustls::crypto::aws_lc_rs;
+    use tokio_rustls::rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+
+    /// Accept a single HTTPS request with a self signed certificate for localhost
+    async fn self_signed_server() -> String {
+        let key = rcgen::KeyPair::generate().unwrap();
+        let mut params =
+            rcgen::CertificateParams::new(vec!["localhost".to_string(), "127.0.0.1".to_string()])
+                .unwrap();
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, "YouUp Test");
+        params.not_after = rcgen::date_time_ymd(2099, 1, 1);
+        let certificate = params.self_signed(&key).unwrap();
+
+        let config = ServerConfig::builder_with_provider(Arc::new(aws_lc_rs::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![certificate.der().clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der())),
+            )
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        actix_rt::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = acceptor.accept(stream).await.unwrap();
+
+            let mut request = vec![];
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let read = stream.read(&mut buf).await.unwrap();
+                if read == 0 {
+                    return;
+                }
+                request.extend_from_slice(&buf[..read]);
+            }
+
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok")
+                .await
+                .unwrap();
+            stream.shutdown().await.unwrap();
+        });
+
+        format!("https://localhost:{port}")
+    }
+
+    /// Answer a single plain HTTP request with the given body
+    async fn plain_server(body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        actix_rt::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let mut request = vec![];
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let read = stream.read(&mut buf).await.unwrap();
+                if read == 0 {
+                    return;
+                }
+                request.extend_from_slice(&buf[..read]);
+            }
+
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            );
+            // The check may hang up before the whole body is sent
+            let _ = stream.write_all(head.as_bytes()).await;
+            let _ = stream.write_all(&body).await;
+            let _ = stream.shutdown().await;
+        });
+
+        format!("http://127.0.0.1:{port}")
+    }
+
+    fn context() -> CheckContext {
+        let client = Client::new();
+        CheckContext {
+            http: client.clone(),
+            http_no_redirect: client,
+            heartbeat_repo: None,
+            allow_scripts: false,
+        }
+    }
+
+    fn parse(json: &str) -> HttpCheckConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn defaults_to_get_expecting_2xx() {
+        let config = parse("{}");
+
+        assert_eq!(config.method, Method::GET);
+        assert!(config.follow_redirects);
+        assert!(config.is_expected_status(StatusCode::NO_CONTENT));
+        assert!(!config.is_expected_status(StatusCode::MOVED_PERMANENTLY));
+        assert!(!config.is_expected_status(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    fn expected_status_codes_and_classes() {
+        let config = parse(r#"{"method": "post", "expected_status": [401, "3xx", "204"]}"#);
+
+        assert_eq!(config.method, Method::POST);
+        assert_eq!(
+            config.expected_status,
+            vec![
+                ExpectedStatus::Code(401),
+                ExpectedStatus::Class(3),
+                ExpectedStatus::Code(204)
+            ]
+        );
+        assert!(config.is_expected_status(StatusCode::UNAUTHORIZED));
+        assert!(config.is_expected_status(StatusCode::FOUND));
+        assert!(config.is_expected_status(StatusCode::NO_CONTENT));
+        assert!(!config.is_expected_status(StatusCode::OK));
+    }
+
+    #[test]
+    fn invalid_config_is_rejected() {
+        assert!(
+            serde_json::from_str::<HttpCheckConfig>(r#"{"expected_status": ["6xx"]}"#).is_err()
+        );
+        assert!(serde_json::from_str::<HttpCheckConfig>(r#"{"method": "GE T"}"#).is_err());
+        assert!(
+            parse(r#"{"headers": {"Authorization": "Bearer\n"}}"#)
+                .header_map()
+                .is_err()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn reads_the_server_certificate() {
+        let url = self_signed_server().await;
+        let client = Client::builder()
+            .tls_info(true)
+            .tls_danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let context = CheckContext {
+            http: client.clone(),
+            http_no_redirect: client,
+            heartbeat_repo: None,
+            allow_scripts: false,
+        };
+        let project = Project {
+            url,
+            ..fixtures::project(1)
+        };
+
+        let result = HttpCheck::new(&context, &project, parse("{}"))
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(result.success, "{:?}", result.message);
+        let certificate = result.certificate.unwrap();
+        assert_eq!(certificate.not_after.year(), 2099);
+        assert_eq!(certificate.issuer, "CN=YouUp Test");
+        assert_eq!(certificate.sans, vec!["localhost", "127.0.0.1"]);
+    }
+
+    #[actix_rt::test]
+    async fn body_assertions_are_checked() {
+        let project = Project {
+            url: plain_server(b"status: ok".to_vec()).await,
+            ..fixtures::project(1)
+        };
+        let config = parse(r#"{"assertions": [{"type": "contains", "value": "ok"}]}"#);
+
+        let result = HttpCheck::new(&context(), &project, config)
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(result.success, "{:?}", result.message);
+    }
+
+    #[actix_rt::test]
+    async fn oversized_bodies_fail_the_assertions() {
+        let project = Project {
+            url: plain_server(vec![b'a'; MAX_BODY_BYTES + 1]).await,
+            ..fixtures::project(1)
+        };
+        let config = parse(r#"{"assertions": [{"type": "contains", "value": "a"}]}"#);
+
+        let result = HttpCheck::new(&context(), &project, config)
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(!result.success);
+        assert_eq!(result.status_code, Some(200));
+        assert_eq!(
+            result.message.as_deref(),
+            Some("Assertion failed: body is larger than 1024 KiB")
+        );
+    }
+}
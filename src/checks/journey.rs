@@ -0,0 +1,508 @@
+use crate::checks::assertions::{BodyAssertion, check_body, deserialize_regex, read_body};
+use crate::checks::http::{
+    ExpectedStatus, default_method, describe_expected_status, deserialize_method,
+    is_expected_status,
+};
+use crate::checks::{Check, CheckFuture, CheckResult, StepTiming, default_timeout_ms};
+use crate::models::Project;
+use anyhow::Context;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use reqwest::{Client, Method, Url};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+pub const CHECK_TYPE: &str = "http_journey";
+
+lazy_static! {
+    static ref VARIABLE: Regex = Regex::new(r"\{\{\s*(\w+)\s*\}\}").unwrap();
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct JourneyCheckConfig {
+    pub steps: Vec<JourneyStep>,
+    /// Applies to each step separately
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// A single request in a journey, `{{variable}}` in the url, headers and body is replaced with
+/// a value captured by an earlier step
+#[derive(Deserialize, Debug, Clone)]
+pub struct JourneyStep {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default = "default_method", deserialize_with = "deserialize_method")]
+    pub method: Method,
+    /// Either absolute or relative to the project url
+    pub url: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub expected_status: Vec<ExpectedStatus>,
+    #[serde(default)]
+    pub assertions: Vec<BodyAssertion>,
+    #[serde(default)]
+    pub captures: Vec<Capture>,
+}
+
+impl JourneyStep {
+    fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("{} {}", self.method, self.url))
+    }
+}
+
+/// A value taken from a response, to be used by later steps
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Capture {
+    /// The value of a response header, e.g. a token
+    Header { variable: String, header: String },
+    /// The value at a JSON pointer (RFC 6901) in the body
+    JsonPointer { variable: String, pointer: String },
+    /// The first group of a regex matched against the body, or the whole match if it has no groups
+    Regex {
+        variable: String,
+        #[serde(deserialize_with = "deserialize_regex")]
+        pattern: Regex,
+    },
+}
+
+impl Capture {
+    fn variable(&self) -> &str {
+        match self {
+            Capture::Header { variable, .. }
+            | Capture::JsonPointer { variable, .. }
+            | Capture::Regex { variable, .. } => variable,
+        }
+    }
+
+    /// Pull the value out of a response, giving the reason on failure
+    pub fn extract(&self, headers: &HeaderMap, body: &str) -> Result<String, String> {
+        match self {
+            Capture::Header { header, .. } => headers
+                .get(header.as_str())
+                .ok_or_else(|| format!("header '{header}' not found"))?
+                .to_str()
+                .map(str::to_string)
+                .map_err(|_| format!("header '{header}' is not valid text")),
+            Capture::JsonPointer { pointer, .. } => {
+                let json = serde_json::from_str::<serde_json::Value>(body)
+                    .map_err(|e| format!("body is not valid JSON: {e}"))?;
+
+                match json.pointer(pointer) {
+                    // Use strings as is, rather than as a quoted JSON string
+                    Some(serde_json::Value::String(value)) => Ok(value.clone()),
+                    Some(value) => Ok(value.to_string()),
+                    None => Err(format!("{pointer} not found in body")),
+                }
+            }
+            Capture::Regex { pattern, .. } => pattern
+                .captures(body)
+                .and_then(|c| c.get(1).or_else(|| c.get(0)))
+                .map(|m| m.as_str().to_string())
+                .ok_or_else(|| format!("body does not match /{pattern}/")),
+        }
+    }
+}
+
+/// Replace each `{{variable}}` in `template` with its captured value
+pub fn substitute(template: &str, variables: &HashMap<String, String>) -> Result<String, String> {
+    let mut missing = None;
+    let result = VARIABLE.replace_all(template, |c: &Captures| match variables.get(&c[1]) {
+        Some(value) => value.clone(),
+        None => {
+            missing.get_or_insert_with(|| c[1].to_string());
+            String::new()
+        }
+    });
+
+    match missing {
+        Some(variable) => Err(format!("'{variable}' has not been captured")),
+        None => Ok(result.into_owned()),
+    }
+}
+
+fn header_map(
+    headers: &BTreeMap<String, String>,
+    variables: &HashMap<String, String>,
+) -> Result<HeaderMap, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            Ok((
+                HeaderName::try_from(name.as_str())
+                    .map_err(|_| format!("Invalid header name '{name}'"))?,
+                HeaderValue::try_from(substitute(value, variables)?)
+                    .map_err(|_| format!("Invalid value for header '{name}'"))?,
+            ))
+        })
+        .collect()
+}
+
+impl JourneyCheckConfig {
+    /// Catch mistakes that can be found without running the journey
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(!self.steps.is_empty(), "A journey needs at least one step");
+
+        let mut captured = HashMap::new();
+        for step in &self.steps {
+            let step_name = step.display_name();
+
+            // Every variable used must have been captured by an earlier step
+            for template in step
+                .headers
+                .values()
+                .chain(step.body.iter())
+                .chain(std::iter::once(&step.url))
+            {
+                substitute(template, &captured)
+                    .map_err(|e| anyhow::anyhow!("Step '{step_name}': {e}"))?;
+            }
+            header_map(&step.headers, &captured)
+                .map_err(|e| anyhow::anyhow!("Step '{step_name}': {e}"))?;
+
+            for capture in &step.captures {
+                captured.insert(capture.variable().to_string(), String::new());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs a list of requests in order, sharing cookies and captured values between them
+pub struct JourneyCheck {
+    base_url: Url,
+    config: JourneyCheckConfig,
+}
+
+impl JourneyCheck {
+    pub fn new(project: &Project, config: JourneyCheckConfig) -> anyhow::Result<Self> {
+        config.validate()?;
+
+        Ok(Self {
+            base_url: Url::parse(&project.url).context("Invalid project url")?,
+            config,
+        })
+    }
+
+    /// Run a single step, returning the status code of the response and recording any captures
+    async fn run_step(
+        &self,
+        client: &Client,
+        step: &JourneyStep,
+        variables: &mut HashMap<String, String>,
+    ) -> Result<u16, (Option<u16>, String)> {
+        let url = substitute(&step.url, variables).map_err(|e| (None, e))?;
+        let url = self
+            .base_url
+            .join(&url)
+            .map_err(|e| (None, format!("Invalid url '{url}': {e}")))?;
+
+        let mut request = client
+            .request(step.method.clone(), url)
+            .headers(header_map(&step.headers, variables).map_err(|e| (None, e))?)
+            .timeout(Duration::from_millis(self.config.timeout_ms));
+        if let Some(body) = &step.body {
+            request = request.body(substitute(body, variables).map_err(|e| (None, e))?);
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) if e.is_timeout() => {
+                return Err((
+                    None,
+                    format!("Request timed out after {}ms", self.config.timeout_ms),
+                ));
+            }
+            Err(e) => return Err((None, format!("Request failed: {e}"))),
+        };
+
+        let status = response.status();
+        let status_code = Some(status.as_u16());
+        if !is_expected_status(&step.expected_status, status) {
+            return Err((
+                status_code,
+                format!(
+                    "Received status code {status}, expected {}",
+                    describe_expected_status(&step.expected_status)
+                ),
+            ));
+        }
+
+        let headers = response.headers().clone();
+        let body = read_body(response).await.map_err(|e| (status_code, e))?;
+
+        check_body(&step.assertions, &body).map_err(|e| (status_code, e))?;
+
+        for capture in &step.captures {
+            let value = capture.extract(&headers, &body).map_err(|e| {
+                (
+                    status_code,
+                    format!("Unable to capture '{}': {e}", capture.variable()),
+                )
+            })?;
+            variables.insert(capture.variable().to_string(), value);
+        }
+
+        Ok(status.as_u16())
+    }
+}
+
+impl Check for JourneyCheck {
+    fn run(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            let mut steps = Vec::with_capacity(self.config.steps.len());
+
+            // Each run starts with an empty cookie jar, just like a new visitor
+            let client = match Client::builder().cookie_store(true).build() {
+                Ok(client) => client,
+                Err(e) => {
+                    return CheckResult::failure(
+                        Duration::ZERO,
+                        None,
+                        format!("Unable to create http client: {e}"),
+                    );
+                }
+            };
+
+            let mut variables = HashMap::new();
+            let mut status_code = None;
+            let mut failure = None;
+
+            for (index, step) in self.config.steps.iter().enumerate() {
+                let step_start_time = Instant::now();
+                let outcome = self.run_step(&client, step, &mut variables).await;
+                let step_duration = step_start_time.elapsed();
+
+                let name = step.display_name();
+                steps.push(StepTiming {
+                    name: name.clone(),
+                    duration_ms: step_duration.as_millis() as u64,
+                });
+
+                match outcome {
+                    Ok(code) => status_code = Some(code),
+                    Err((code, reason)) => {
+                        status_code = code;
+                        failure = Some(format!("Step {} ({name}) failed: {reason}", index + 1));
+                        break;
+                    }
+                }
+            }
+
+            let duration = steps
+                .iter()
+                .map(|s| Duration::from_millis(s.duration_ms))
+                .sum();
+
+            let mut result = match failure {
+                None => CheckResult::success(duration, status_code),
+                Some(reason) => CheckResult::failure(duration, status_code, reason),
+            };
+            result.steps = steps;
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::checks::Check;
+    use crate::checks::assertions::MAX_BODY_BYTES;
+    use crate::checks::journey::{Capture, JourneyCheck, JourneyCheckConfig, substitute};
+    use crate::models::{Project, fixtures};
+    use actix_web::cookie::Cookie;
+    use actix_web::{App, HttpRequest, HttpResponse, HttpServer, web};
+    use http::header::HeaderMap;
+    use std::collections::HashMap;
+
+    fn parse(json: &str) -> JourneyCheckConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn substitute_variables() {
+        let variables = HashMap::from([("token".to_string(), "abc".to_string())]);
+
+        assert_eq!(
+            substitute("Bearer {{token}}", &variables).unwrap(),
+            "Bearer abc"
+        );
+        assert_eq!(
+            substitute("{{ token }}/{{token}}", &variables).unwrap(),
+            "abc/abc"
+        );
+        assert_eq!(
+            substitute("no variables", &variables).unwrap(),
+            "no variables"
+        );
+        assert!(substitute("{{missing}}", &variables).is_err());
+    }
+
+    #[test]
+    fn capture_values() {
+        let captures: Vec<Capture> = serde_json::from_str(
+            r#"[
+                {"type": "json_pointer", "variable": "token", "pointer": "/auth/token"},
+                {"type": "regex", "variable": "csrf", "pattern": "csrf=([a-z]+)"},
+                {"type": "header", "variable": "request_id", "header": "X-Request-Id"}
+            ]"#,
+        )
+        .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Request-Id", "42".parse().unwrap());
+        let body = r#"{"auth": {"token": "abc"}, "form": "csrf=xyz"}"#;
+
+        assert_eq!(captures[0].extract(&headers, body).unwrap(), "abc");
+        assert_eq!(captures[1].extract(&headers, body).unwrap(), "xyz");
+        assert_eq!(captures[2].extract(&headers, body).unwrap(), "42");
+        assert!(captures[0].extract(&headers, "{}").is_err());
+        assert!(captures[2].extract(&HeaderMap::new(), body).is_err());
+    }
+
+    #[test]
+    fn variables_must_be_captured_before_use() {
+        assert!(parse(r#"{"steps": []}"#).validate().is_err());
+        assert!(
+            parse(r#"{"steps": [{"url": "/account", "headers": {"Authorization": "{{token}}"}}]}"#)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            parse(
+                r#"{"steps": [
+                    {"url": "/login", "captures": [{"type": "header", "variable": "token", "header": "X-Token"}]},
+                    {"url": "/account", "headers": {"Authorization": "{{token}}"}}
+                ]}"#
+            )
+            .validate()
+            .is_ok()
+        );
+    }
+
+    async fn login() -> HttpResponse {
+        HttpResponse::Ok()
+            .cookie(Cookie::new("session", "s3cret"))
+            .json(serde_json::json!({"token": "abc"}))
+    }
+
+    async fn account(request: HttpRequest) -> HttpResponse {
+        let has_session = request
+            .cookie("session")
+            .map(|c| c.value() == "s3cret")
+            .unwrap_or(false);
+        let has_token = request
+            .headers()
+            .get("Authorization")
+            .map(|h| h == "Bearer abc")
+            .unwrap_or(false);
+
+        if has_session && has_token {
+            HttpResponse::Ok().body("Welcome back")
+        } else {
+            HttpResponse::Unauthorized().finish()
+        }
+    }
+
+    fn project(url: String) -> Project {
+        Project {
+            name: "Journey".to_string(),
+            url,
+            check_type: super::CHECK_TYPE.to_string(),
+            ..fixtures::project(1)
+        }
+    }
+
+    #[actix_rt::test]
+    async fn run_journey() {
+        let server = HttpServer::new(|| {
+            App::new()
+                .route("/login", web::post().to(login))
+                .route("/account", web::get().to(account))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let address = server.addrs()[0];
+        actix_rt::spawn(server.run());
+
+        let config = parse(
+            r#"{"steps": [
+                {"name": "login", "method": "POST", "url": "/login", "captures": [{"type": "json_pointer", "variable": "token", "pointer": "/token"}]},
+                {"name": "account", "url": "/account", "headers": {"Authorization": "Bearer {{token}}"}, "assertions": [{"type": "contains", "value": "Welcome"}]}
+            ]}"#,
+        );
+        let result = JourneyCheck::new(&project(format!("http://{address}/")), config)
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(result.success, "{:?}", result.message);
+        assert_eq!(result.status_code, Some(200));
+        assert_eq!(
+            result
+                .steps
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["login", "account"]
+        );
+
+        // Without the login step there is no session cookie or token
+        let config = parse(
+            r#"{"steps": [{"name": "account", "url": "/account", "headers": {"Authorization": "Bearer abc"}}]}"#,
+        );
+        let result = JourneyCheck::new(&project(format!("http://{address}/")), config)
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(!result.success);
+        assert_eq!(result.status_code, Some(401));
+        assert!(
+            result
+                .message
+                .unwrap()
+                .starts_with("Step 1 (account) failed")
+        );
+    }
+
+    #[actix_rt::test]
+    async fn oversized_bodies_fail_the_step() {
+        let server = HttpServer::new(|| {
+            App::new().route(
+                "/large",
+                web::get().to(|| async { HttpResponse::Ok().body(vec![b'a'; MAX_BODY_BYTES + 1]) }),
+            )
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let address = server.addrs()[0];
+        actix_rt::spawn(server.run());
+
+        let config = parse(
+            r#"{"steps": [{"name": "large", "url": "/large", "assertions": [{"type": "contains", "value": "a"}]}]}"#,
+        );
+        let result = JourneyCheck::new(&project(format!("http://{address}/")), config)
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(!result.success);
+        assert_eq!(result.status_code, Some(200));
+        assert_eq!(
+            result.message.unwrap(),
+            "Step 1 (large) failed: Assertion failed: body is larger than 1024 KiB"
+        );
+    }
+}
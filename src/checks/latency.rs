@@ -0,0 +1,253 @@
+use crate::checks::CheckResult;
+use std::collections::BTreeMap;
+
+/// How many of the most recent samples the rolling p95 is taken over
+pub const P95_SAMPLES: i64 = 20;
+
+/// Response time limits for a project, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyThresholds {
+    /// Slower responses are shown as degraded
+    pub warn_ms: Option<i32>,
+    /// Slower responses count as a failure
+    pub critical_ms: Option<i32>,
+    /// Compare the rolling p95 of recent samples, rather than just the latest one
+    pub use_p95: bool,
+}
+
+impl LatencyThresholds {
+    pub fn is_set(&self) -> bool {
+        self.warn_ms.is_some() || self.critical_ms.is_some()
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for threshold in [self.warn_ms, self.critical_ms].into_iter().flatten() {
+            anyhow::ensure!(threshold > 0, "Latency thresholds must be positive");
+        }
+        if let (Some(warn_ms), Some(critical_ms)) = (self.warn_ms, self.critical_ms) {
+            anyhow::ensure!(
+                warn_ms < critical_ms,
+                "The warning latency must be lower than the critical latency"
+            );
+        }
+        Ok(())
+    }
+
+    /// Fail or degrade a successful result if `latency_ms` is over a threshold,
+    /// `latency_ms` is either the duration of the result or the rolling p95
+    pub fn apply(&self, result: &mut CheckResult, latency_ms: u64) {
+        if !result.success {
+            return;
+        }
+
+        let measure = if self.use_p95 {
+            "p95 response time"
+        } else {
+            "Response time"
+        };
+        let over = |threshold: Option<i32>| {
+            threshold.filter(|threshold| latency_ms > u64::try_from(*threshold).unwrap_or(0))
+        };
+
+        if let Some(critical_ms) = over(self.critical_ms) {
+            result.success = false;
+            result.degraded = false;
+            result.message = Some(format!(
+                "{measure} {latency_ms}ms is over the critical threshold of {critical_ms}ms"
+            ));
+        } else if let Some(warn_ms) = over(self.warn_ms)
+            && !result.degraded
+        {
+            result.mark_degraded(format!(
+                "{measure} {latency_ms}ms is over the warning threshold of {warn_ms}ms"
+            ));
+        }
+    }
+}
+
+/// The 95th percentile of some samples, using the nearest rank
+pub fn p95(samples: &[u64]) -> Option<u64> {
+    percentile(samples, 95)
+}
+
+/// A percentile of some samples, using the nearest rank
+pub fn percentile(samples: &[u64], percentile: usize) -> Option<u64> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    let mut samples = samples.to_vec();
+    samples.sort_unstable();
+    let rank = (samples.len() * percentile).div_ceil(100);
+    samples.get(rank.saturating_sub(1)).copied()
+}
+
+/// The upper bound of the bucket a response time falls in, in 10ms steps up to a second, 100ms
+/// steps up to 10 seconds and whole seconds above that. Rollups keep a histogram of these buckets
+/// instead of every response time
+pub fn latency_bucket(ms: i32) -> i32 {
+    let step: u32 = if ms <= 1000 {
+        10
+    } else if ms <= 10_000 {
+        100
+    } else {
+        1000
+    };
+    let ms = u32::try_from(ms).unwrap_or(0);
+    i32::try_from(ms.div_ceil(step) * step).unwrap_or(i32::MAX)
+}
+
+/// Count how many response times fall in each latency bucket
+pub fn latency_histogram(latencies: impl IntoIterator<Item = i32>) -> BTreeMap<i32, i32> {
+    let mut histogram = BTreeMap::new();
+    for ms in latencies {
+        *histogram.entry(latency_bucket(ms)).or_insert(0) += 1;
+    }
+    histogram
+}
+
+/// A percentile of a latency histogram, using the nearest rank. This is the upper bound of the
+/// bucket the sample falls in
+pub fn histogram_percentile(histogram: &BTreeMap<i32, i32>, percentile: usize) -> Option<i32> {
+    let total = histogram
+        .values()
+        .map(|count| usize::try_from(*count).unwrap_or(0))
+        .sum::<usize>();
+    let rank = (total * percentile).div_ceil(100).max(1);
+
+    let mut seen = 0;
+    histogram.iter().find_map(|(bucket, count)| {
+        seen += usize::try_from(*count).unwrap_or(0);
+        (seen >= rank).then_some(*bucket)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::checks::CheckResult;
+    use crate::checks::latency::{
+        LatencyThresholds, histogram_percentile, latency_bucket, latency_histogram, p95, percentile,
+    };
+    use std::time::Duration;
+
+    fn thresholds() -> LatencyThresholds {
+        LatencyThresholds {
+            warn_ms: Some(500),
+            critical_ms: Some(2000),
+            use_p95: false,
+        }
+    }
+
+    fn apply(latency_ms: u64) -> CheckResult {
+        let mut result = CheckResult::success(Duration::from_millis(latency_ms), Some(200));
+        thresholds().apply(&mut result, latency_ms);
+        result
+    }
+
+    #[test]
+    fn fast_responses_are_unchanged() {
+        let result = apply(100);
+        assert!(result.success);
+        assert!(!result.degraded);
+        assert!(result.message.is_none());
+    }
+
+    #[test]
+    fn slow_responses_are_degraded() {
+        let result = apply(900);
+        assert!(result.success);
+        assert!(result.degraded);
+        assert!(
+            result
+                .message
+                .unwrap()
+                .contains("warning threshold of 500ms")
+        );
+    }
+
+    #[test]
+    fn very_slow_responses_fail() {
+        let result = apply(9000);
+        assert!(!result.success);
+        assert!(!result.degraded);
+        assert!(
+            result
+                .message
+                .unwrap()
+                .contains("critical threshold of 2000ms")
+        );
+    }
+
+    #[test]
+    fn failures_are_unchanged() {
+        let mut result = CheckResult::failure(Duration::from_secs(9), None, "Request failed");
+        thresholds().apply(&mut result, 9000);
+        assert_eq!(result.message.unwrap(), "Request failed");
+    }
+
+    #[test]
+    fn invalid_thresholds() {
+        assert!(thresholds().validate().is_ok());
+        assert!(LatencyThresholds::default().validate().is_ok());
+        assert!(
+            LatencyThresholds {
+                warn_ms: Some(2000),
+                critical_ms: Some(500),
+                use_p95: false
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            LatencyThresholds {
+                warn_ms: Some(0),
+                critical_ms: None,
+                use_p95: false
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn nearest_rank_p95() {
+        assert_eq!(p95(&[]), None);
+        assert_eq!(p95(&[42]), Some(42));
+
+        let samples = (1..=20).collect::<Vec<_>>();
+        assert_eq!(p95(&samples), Some(19));
+
+        let samples = (1..=100).rev().collect::<Vec<_>>();
+        assert_eq!(p95(&samples), Some(95));
+        assert_eq!(percentile(&samples, 50), Some(50));
+    }
+
+    #[test]
+    fn latency_buckets() {
+        assert_eq!(latency_bucket(0), 0);
+        assert_eq!(latency_bucket(1), 10);
+        assert_eq!(latency_bucket(500), 500);
+        assert_eq!(latency_bucket(501), 510);
+        assert_eq!(latency_bucket(1001), 1100);
+        assert_eq!(latency_bucket(12_345), 13_000);
+
+        let histogram = latency_histogram([95, 100, 101, 2050]);
+        assert_eq!(
+            histogram.into_iter().collect::<Vec<_>>(),
+            vec![(100, 2), (110, 1), (2100, 1)]
+        );
+    }
+
+    #[test]
+    fn histogram_percentiles() {
+        assert_eq!(histogram_percentile(&latency_histogram([]), 50), None);
+
+        let histogram = latency_histogram((1..=100).map(|i| i * 10));
+        assert_eq!(histogram_percentile(&histogram, 50), Some(500));
+        assert_eq!(histogram_percentile(&histogram, 95), Some(950));
+
+        let histogram = latency_histogram([100, 100, 100, 2050]);
+        assert_eq!(histogram_percentile(&histogram, 50), Some(100));
+        assert_eq!(histogram_percentile(&histogram, 95), Some(2100));
+    }
+}
@@ -0,0 +1,242 @@
+pub mod assertions;
+pub mod certificate;
+pub mod dns;
+pub mod grpc;
+pub mod heartbeat;
+pub mod http;
+pub mod journey;
+pub mod latency;
+pub mod script;
+pub mod tcp;
+pub mod websocket;
+
+use crate::checks::certificate::CertificateInfo;
+use crate::data::heartbeat_repository::HeartbeatRepositoryBox;
+use crate::models::Project;
+use anyhow::Context;
+use reqwest::Client;
+use reqwest::redirect::Policy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+/// All of the check types that can be assigned to a project
+pub const CHECK_TYPES: &[&str] = &[
+    http::CHECK_TYPE,
+    journey::CHECK_TYPE,
+    tcp::CHECK_TYPE,
+    websocket::CHECK_TYPE,
+    grpc::CHECK_TYPE,
+    dns::CHECK_TYPE,
+    script::CHECK_TYPE,
+    heartbeat::CHECK_TYPE,
+];
+
+/// The probe neutral outcome of checking a project once
+#[derive(Clone, Debug)]
+pub struct CheckResult {
+    pub duration: Duration,
+    pub success: bool,
+    /// Only set by probes that have a protocol level status code, e.g. HTTP
+    pub status_code: Option<u16>,
+    /// Human readable explanation, mostly used to say why a check failed
+    pub message: Option<String>,
+    /// The project is up, but something needs attention
+    pub degraded: bool,
+    /// The leaf certificate presented by the server, for probes that use TLS
+    pub certificate: Option<CertificateInfo>,
+    /// Time taken by each step of probes that make several requests
+    pub steps: Vec<StepTiming>,
+}
+
+/// How long a single step of a check took
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StepTiming {
+    pub name: String,
+    pub duration_ms: u64,
+}
+
+impl CheckResult {
+    pub fn success(duration: Duration, status_code: Option<u16>) -> Self {
+        Self {
+            duration,
+            success: true,
+            status_code,
+            message: None,
+            degraded: false,
+            certificate: None,
+            steps: vec![],
+        }
+    }
+
+    pub fn failure(
+        duration: Duration,
+        status_code: Option<u16>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            duration,
+            success: false,
+            status_code,
+            message: Some(message.into()),
+            degraded: false,
+            certificate: None,
+            steps: vec![],
+        }
+    }
+
+    pub fn mark_degraded(&mut self, message: impl Into<String>) {
+        self.degraded = true;
+        self.message = Some(message.into());
+    }
+
+    /// A short description of the result, suitable for notifications
+    pub fn describe(&self) -> String {
+        match (&self.message, self.status_code) {
+            (Some(message), _) => message.clone(),
+            (None, Some(status_code)) => format!("status code {status_code}"),
+            (None, None) if self.success => "OK".to_string(),
+            (None, None) => "Unknown failure".to_string(),
+        }
+    }
+}
+
+pub type CheckFuture<'a> = Pin<Box<dyn Future<Output = CheckResult> + 'a>>;
+
+/// A probe that can determine if a project is up
+pub trait Check {
+    fn run(&self) -> CheckFuture<'_>;
+
+    /// Whether a failure might be transient, making it worth checking again before reporting it
+    fn retryable(&self) -> bool {
+        true
+    }
+}
+
+/// Shared resources used by probes
+pub struct CheckContext {
+    pub http: Client,
+    pub http_no_redirect: Client,
+    /// Only available when running alongside the database
+    pub heartbeat_repo: Option<HeartbeatRepositoryBox>,
+    /// Whether script checks may run local commands
+    pub allow_scripts: bool,
+}
+
+impl CheckContext {
+    pub fn new(
+        heartbeat_repo: Option<HeartbeatRepositoryBox>,
+        allow_scripts: bool,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            http: Client::builder()
+                .tls_info(true)
+                .build()
+                .context("Unable to create http client")?,
+            http_no_redirect: Client::builder()
+                .tls_info(true)
+                .redirect(Policy::none())
+                .build()
+                .context("Unable to create http client")?,
+            heartbeat_repo,
+            allow_scripts,
+        })
+    }
+}
+
+/// Create the probe configured for the given project
+pub fn check_for_project(
+    context: &CheckContext,
+    project: &Project,
+) -> anyhow::Result<Box<dyn Check>> {
+    match project.check_type.as_str() {
+        http::CHECK_TYPE => Ok(Box::new(http::HttpCheck::new(
+            context,
+            project,
+            parse_config(project.check_config.as_deref())?,
+        )?)),
+        journey::CHECK_TYPE => Ok(Box::new(journey::JourneyCheck::new(
+            project,
+            parse_config(project.check_config.as_deref())?,
+        )?)),
+        tcp::CHECK_TYPE => Ok(Box::new(tcp::TcpCheck::new(parse_config(
+            project.check_config.as_deref(),
+        )?))),
+        websocket::CHECK_TYPE => Ok(Box::new(websocket::WebSocketCheck::new(parse_config(
+            project.check_config.as_deref(),
+        )?)?)),
+        grpc::CHECK_TYPE => Ok(Box::new(grpc::GrpcCheck::new(parse_config(
+            project.check_config.as_deref(),
+        )?)?)),
+        dns::CHECK_TYPE => Ok(Box::new(dns::DnsCheck::new(parse_config(
+            project.check_config.as_deref(),
+        )?)?)),
+        script::CHECK_TYPE => {
+            anyhow::ensure!(
+                context.allow_scripts,
+                "Script checks are not allowed to run here"
+            );
+            Ok(Box::new(script::ScriptCheck::new(parse_config(
+                project.check_config.as_deref(),
+            )?)))
+        }
+        heartbeat::CHECK_TYPE => {
+            let heartbeat = context
+                .heartbeat_repo
+                .as_ref()
+                .context("Heartbeat checks need access to the database")?
+                .get_heartbeat_by_project(project.id)?
+                .context("No heartbeat token for project")?;
+
+            Ok(Box::new(heartbeat::HeartbeatCheck::new(
+                parse_config(project.check_config.as_deref())?,
+                heartbeat,
+            )))
+        }
+        other => anyhow::bail!("Unknown check type '{other}'"),
+    }
+}
+
+/// Make sure that a check type / config pair can be used to build a probe
+pub fn validate_check_config(check_type: &str, check_config: &str) -> anyhow::Result<()> {
+    match check_type {
+        http::CHECK_TYPE => parse_config::<http::HttpCheckConfig>(Some(check_config))?
+            .header_map()
+            .map(|_| ()),
+        journey::CHECK_TYPE => {
+            parse_config::<journey::JourneyCheckConfig>(Some(check_config))?.validate()
+        }
+        tcp::CHECK_TYPE => parse_config::<tcp::TcpCheckConfig>(Some(check_config)).map(|_| ()),
+        websocket::CHECK_TYPE => {
+            parse_config::<websocket::WebSocketCheckConfig>(Some(check_config))?.validate()
+        }
+        grpc::CHECK_TYPE => parse_config::<grpc::GrpcCheckConfig>(Some(check_config))?
+            .endpoint()
+            .map(|_| ()),
+        dns::CHECK_TYPE => parse_config::<dns::DnsCheckConfig>(Some(check_config))?
+            .resolver_address()
+            .map(|_| ()),
+        script::CHECK_TYPE => {
+            parse_config::<script::ScriptCheckConfig>(Some(check_config)).map(|_| ())
+        }
+        heartbeat::CHECK_TYPE => {
+            parse_config::<heartbeat::HeartbeatCheckConfig>(Some(check_config)).map(|_| ())
+        }
+        other => anyhow::bail!("Unknown check type '{other}'"),
+    }
+}
+
+/// Parse the JSON config of a check, a missing config is treated as `{}`
+fn parse_config<T: DeserializeOwned>(check_config: Option<&str>) -> anyhow::Result<T> {
+    let check_config = check_config
+        .filter(|c| !c.trim().is_empty())
+        .unwrap_or("{}");
+    serde_json::from_str(check_config).context("Invalid check config")
+}
+
+/// How long a probe waits before giving up, if not configured
+pub(crate) fn default_timeout_ms() -> u64 {
+    10_000
+}
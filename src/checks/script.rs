@@ -0,0 +1,198 @@
+use crate::checks::{Check, CheckFuture, CheckResult, default_timeout_ms};
+use actix_rt::time::timeout;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+pub const CHECK_TYPE: &str = "script";
+
+/// How many lines of output are kept as the status message
+const MESSAGE_LINES: usize = 3;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ScriptCheckConfig {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Added to the environment of this process
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// Build the status message from the output of a plugin, dropping any performance data
+fn output_message(stdout: &[u8]) -> Option<String> {
+    let stdout = String::from_utf8_lossy(stdout);
+    let message = stdout
+        .lines()
+        .map(|line| line.split('|').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .take(MESSAGE_LINES)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Some(message).filter(|m| !m.is_empty())
+}
+
+/// Map the exit code of a Nagios style plugin to a result,
+/// 0 is up, 1 is degraded and anything else (2 critical, 3 unknown) is down
+pub fn plugin_result(duration: Duration, exit_code: Option<i32>, stdout: &[u8]) -> CheckResult {
+    let message = output_message(stdout);
+
+    match exit_code {
+        Some(0) => {
+            let mut result = CheckResult::success(duration, None);
+            result.message = message;
+            result
+        }
+        Some(1) => {
+            let mut result = CheckResult::success(duration, None);
+            result.mark_degraded(message.unwrap_or_else(|| "Exited with warning".to_string()));
+            result
+        }
+        Some(code) => CheckResult::failure(
+            duration,
+            None,
+            message.unwrap_or_else(|| format!("Exited with code {code}")),
+        ),
+        None => CheckResult::failure(duration, None, "Killed by a signal"),
+    }
+}
+
+/// Runs a local command, e.g. a Nagios plugin
+pub struct ScriptCheck {
+    config: ScriptCheckConfig,
+}
+
+impl ScriptCheck {
+    pub fn new(config: ScriptCheckConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Check for ScriptCheck {
+    fn run(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            let ScriptCheckConfig {
+                command,
+                args,
+                env,
+                working_dir,
+                timeout_ms,
+            } = &self.config;
+
+            let mut process = Command::new(command);
+            process
+                .args(args)
+                .envs(env)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                // Make sure the process doesn't outlive the timeout
+                .kill_on_drop(true);
+            if let Some(working_dir) = working_dir {
+                process.current_dir(working_dir);
+            }
+
+            let run_start_time = Instant::now();
+            let output = timeout(Duration::from_millis(*timeout_ms), process.output()).await;
+            let run_duration = run_start_time.elapsed();
+
+            match output {
+                Ok(Ok(output)) => plugin_result(run_duration, output.status.code(), &output.stdout),
+                Ok(Err(e)) => CheckResult::failure(
+                    run_duration,
+                    None,
+                    format!("Unable to run {command}: {e}"),
+                ),
+                Err(_) => CheckResult::failure(
+                    run_duration,
+                    None,
+                    format!("{command} timed out after {timeout_ms}ms"),
+                ),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::checks::Check;
+    use crate::checks::script::{ScriptCheck, ScriptCheckConfig, plugin_result};
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    fn shell(script: &str, timeout_ms: u64) -> ScriptCheck {
+        ScriptCheck::new(ScriptCheckConfig {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            env: BTreeMap::from([("GREETING".to_string(), "hello".to_string())]),
+            working_dir: Some("/".to_string()),
+            timeout_ms,
+        })
+    }
+
+    #[test]
+    fn exit_codes() {
+        let ok = plugin_result(Duration::ZERO, Some(0), b"OK - all good | load=0.1\n");
+        assert!(ok.success);
+        assert!(!ok.degraded);
+        assert_eq!(ok.message.unwrap(), "OK - all good");
+
+        let warning = plugin_result(Duration::ZERO, Some(1), b"WARNING - disk 85%\n");
+        assert!(warning.success);
+        assert!(warning.degraded);
+
+        let critical = plugin_result(Duration::ZERO, Some(2), b"");
+        assert!(!critical.success);
+        assert_eq!(critical.message.unwrap(), "Exited with code 2");
+
+        assert!(!plugin_result(Duration::ZERO, Some(3), b"UNKNOWN").success);
+        assert!(!plugin_result(Duration::ZERO, None, b"").success);
+    }
+
+    #[test]
+    fn only_first_lines_are_kept() {
+        let result = plugin_result(Duration::ZERO, Some(2), b"one\n\ntwo\nthree\nfour\n");
+        assert_eq!(result.message.unwrap(), "one\ntwo\nthree");
+    }
+
+    #[actix_rt::test]
+    async fn run_command() {
+        let result = shell("echo \"WARNING - $GREETING from $(pwd)\"; exit 1", 5000)
+            .run()
+            .await;
+
+        assert!(result.success);
+        assert!(result.degraded);
+        assert_eq!(result.message.unwrap(), "WARNING - hello from /");
+    }
+
+    #[actix_rt::test]
+    async fn command_times_out() {
+        let result = shell("sleep 5", 100).run().await;
+
+        assert!(!result.success);
+        assert!(result.message.unwrap().contains("timed out"));
+    }
+
+    #[actix_rt::test]
+    async fn missing_command() {
+        let result = ScriptCheck::new(ScriptCheckConfig {
+            command: "/does/not/exist".to_string(),
+            args: vec![],
+            env: BTreeMap::new(),
+            working_dir: None,
+            timeout_ms: 1000,
+        })
+        .run()
+        .await;
+
+        assert!(!result.success);
+    }
+}
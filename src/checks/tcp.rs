@@ -0,0 +1,106 @@
+use crate::checks::{Check, CheckFuture, CheckResult, default_timeout_ms};
+use actix_rt::net::TcpStream;
+use actix_rt::time::timeout;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+pub const CHECK_TYPE: &str = "tcp";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TcpCheckConfig {
+    pub host: String,
+    pub port: u16,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// Checks that a TCP connection can be opened to `host:port`
+pub struct TcpCheck {
+    config: TcpCheckConfig,
+}
+
+impl TcpCheck {
+    pub fn new(config: TcpCheckConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Check for TcpCheck {
+    fn run(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            let TcpCheckConfig {
+                host,
+                port,
+                timeout_ms,
+            } = &self.config;
+
+            let connect_start_time = Instant::now();
+            let connection = timeout(
+                Duration::from_millis(*timeout_ms),
+                TcpStream::connect((host.as_str(), *port)),
+            )
+            .await;
+            let connect_duration = connect_start_time.elapsed();
+
+            match connection {
+                Ok(Ok(_stream)) => CheckResult::success(connect_duration, None),
+                Ok(Err(e)) => CheckResult::failure(
+                    connect_duration,
+                    None,
+                    format!("Unable to connect to {host}:{port}: {e}"),
+                ),
+                Err(_) => CheckResult::failure(
+                    connect_duration,
+                    None,
+                    format!("Timed out connecting to {host}:{port} after {timeout_ms}ms"),
+                ),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::checks::Check;
+    use crate::checks::tcp::{TcpCheck, TcpCheckConfig};
+    use actix_rt::net::TcpListener;
+
+    #[actix_rt::test]
+    async fn connect_to_open_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let result = TcpCheck::new(TcpCheckConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            timeout_ms: 1000,
+        })
+        .run()
+        .await;
+
+        assert!(result.success);
+        assert!(result.status_code.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn connect_to_closed_port() {
+        // Bind then drop the listener so we know the port is free
+        let port = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let result = TcpCheck::new(TcpCheckConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            timeout_ms: 1000,
+        })
+        .run()
+        .await;
+
+        assert!(!result.success);
+        assert!(result.message.is_some());
+    }
+}
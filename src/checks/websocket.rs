@@ -0,0 +1,265 @@
+use crate::checks::{Check, CheckFuture, CheckResult, default_timeout_ms};
+use actix_rt::time::timeout;
+use futures_util::{SinkExt, StreamExt};
+use reqwest::Url;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::{Error, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+
+pub const CHECK_TYPE: &str = "websocket";
+
+type Socket = WebSocketStream<MaybeTlsStream<actix_rt::net::TcpStream>>;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WebSocketCheckConfig {
+    /// Must be a `ws://` or `wss://` url
+    pub url: String,
+    /// Sent as a text message once connected
+    #[serde(default)]
+    pub send: Option<String>,
+    /// A message containing this must be received before the timeout
+    #[serde(default)]
+    pub expect: Option<String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl WebSocketCheckConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let url = Url::parse(&self.url)?;
+        anyhow::ensure!(
+            matches!(url.scheme(), "ws" | "wss"),
+            "WebSocket url must start with ws:// or wss://"
+        );
+        Ok(())
+    }
+}
+
+/// Wait for a message containing `expected`, ignoring any other messages
+async fn wait_for_reply(socket: &mut Socket, expected: &str) -> Result<(), String> {
+    while let Some(message) = socket.next().await {
+        let matches = match message.map_err(|e| format!("Connection failed: {e}"))? {
+            Message::Text(text) => text.as_str().contains(expected),
+            Message::Binary(data) => String::from_utf8_lossy(&data).contains(expected),
+            Message::Close(frame) => {
+                return Err(match frame {
+                    Some(frame) => format!("Connection closed: {} {}", frame.code, frame.reason),
+                    None => "Connection closed".to_string(),
+                });
+            }
+            _ => continue,
+        };
+
+        if matches {
+            return Ok(());
+        }
+    }
+
+    Err("Connection closed".to_string())
+}
+
+/// Checks that a WebSocket connection can be upgraded, and optionally that it replies to a message
+pub struct WebSocketCheck {
+    config: WebSocketCheckConfig,
+}
+
+impl WebSocketCheck {
+    pub fn new(config: WebSocketCheckConfig) -> anyhow::Result<Self> {
+        config.validate()?;
+        Ok(Self { config })
+    }
+}
+
+impl Check for WebSocketCheck {
+    fn run(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            let WebSocketCheckConfig {
+                url,
+                send,
+                expect,
+                timeout_ms,
+            } = &self.config;
+            let time_limit = Duration::from_millis(*timeout_ms);
+
+            let connect_start_time = Instant::now();
+            let connection = timeout(time_limit, connect_async(url.as_str())).await;
+
+            let (mut socket, response) = match connection {
+                Ok(Ok(connection)) => connection,
+                Ok(Err(Error::Http(response))) => {
+                    return CheckResult::failure(
+                        connect_start_time.elapsed(),
+                        Some(response.status().as_u16()),
+                        format!("Upgrade refused with status {}", response.status()),
+                    );
+                }
+                Ok(Err(e)) => {
+                    return CheckResult::failure(
+                        connect_start_time.elapsed(),
+                        None,
+                        format!("Unable to connect to {url}: {e}"),
+                    );
+                }
+                Err(_) => {
+                    return CheckResult::failure(
+                        connect_start_time.elapsed(),
+                        None,
+                        format!("Timed out connecting to {url} after {timeout_ms}ms"),
+                    );
+                }
+            };
+            let status_code = Some(response.status().as_u16());
+
+            if let Some(send) = send
+                && let Err(e) = socket.send(Message::text(send.as_str())).await
+            {
+                return CheckResult::failure(
+                    connect_start_time.elapsed(),
+                    status_code,
+                    format!("Unable to send message: {e}"),
+                );
+            }
+
+            if let Some(expect) = expect {
+                let remaining = time_limit.saturating_sub(connect_start_time.elapsed());
+                let reply = match timeout(remaining, wait_for_reply(&mut socket, expect)).await {
+                    Ok(reply) => reply,
+                    Err(_) => Err(format!(
+                        "No reply containing '{expect}' within {timeout_ms}ms"
+                    )),
+                };
+
+                if let Err(message) = reply {
+                    return CheckResult::failure(
+                        connect_start_time.elapsed(),
+                        status_code,
+                        message,
+                    );
+                }
+            }
+
+            let duration = connect_start_time.elapsed();
+            // The check has already passed, the server doesn't have to acknowledge the close
+            let _ = timeout(Duration::from_secs(1), socket.close(None)).await;
+
+            CheckResult::success(duration, status_code)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::checks::Check;
+    use crate::checks::websocket::{WebSocketCheck, WebSocketCheckConfig};
+    use actix_rt::net::TcpListener;
+    use futures_util::StreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Accept a single WebSocket connection and echo everything sent to it
+    async fn echo_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        actix_rt::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let (write, read) = socket.split();
+            let _ = read.forward(write).await;
+        });
+
+        format!("ws://{address}")
+    }
+
+    fn config(url: String, send: Option<&str>, expect: Option<&str>) -> WebSocketCheckConfig {
+        WebSocketCheckConfig {
+            url,
+            send: send.map(str::to_string),
+            expect: expect.map(str::to_string),
+            timeout_ms: 1000,
+        }
+    }
+
+    #[test]
+    fn only_websocket_urls() {
+        assert!(
+            config("wss://example.com/socket".to_string(), None, None)
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            config("https://example.com".to_string(), None, None)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            config("nonsense".to_string(), None, None)
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn handshake() {
+        let url = echo_server().await;
+
+        let result = WebSocketCheck::new(config(url, None, None))
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(result.success);
+        assert_eq!(result.status_code, Some(101));
+    }
+
+    #[actix_rt::test]
+    async fn expected_reply() {
+        let url = echo_server().await;
+
+        let result = WebSocketCheck::new(config(url, Some("ping from you_up"), Some("you_up")))
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(result.success);
+    }
+
+    #[actix_rt::test]
+    async fn missing_reply() {
+        let url = echo_server().await;
+
+        let result = WebSocketCheck::new(config(url, Some("ping"), Some("pong")))
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(!result.success);
+        assert!(
+            result
+                .message
+                .unwrap()
+                .contains("No reply containing 'pong'")
+        );
+    }
+
+    #[actix_rt::test]
+    async fn upgrade_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        actix_rt::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = stream.read(&mut [0; 1024]).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\n\r\n")
+                .await;
+        });
+
+        let result = WebSocketCheck::new(config(format!("ws://{address}"), None, None))
+            .unwrap()
+            .run()
+            .await;
+
+        assert!(!result.success);
+        assert_eq!(result.status_code, Some(502));
+    }
+}
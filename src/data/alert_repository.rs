@@ -0,0 +1,52 @@
+use crate::db::Database;
+use crate::diesel::Insertable;
+use crate::models::{NewProjectAlert, ProjectAlert};
+use crate::schema::project_alerts;
+use actix_web::web::Data;
+use anyhow::Context;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+pub type AlertRepositoryBox = Box<dyn AlertRepository>;
+pub type AlertRepositoryData = Data<AlertRepositoryBox>;
+
+pub trait AlertRepository {
+    fn get_all_alerts(&self) -> anyhow::Result<Vec<ProjectAlert>>;
+    fn get_alert_by_project(&self, project: i32) -> anyhow::Result<Option<ProjectAlert>>;
+    /// Store the alert state of a project, replacing any previous one
+    fn save_alert(&self, alert: NewProjectAlert) -> anyhow::Result<()>;
+}
+
+impl AlertRepository for Database {
+    fn get_all_alerts(&self) -> anyhow::Result<Vec<ProjectAlert>> {
+        project_alerts::table
+            .load::<ProjectAlert>(&mut self.get()?)
+            .context("Unable to load alerts")
+    }
+
+    fn get_alert_by_project(&self, project: i32) -> anyhow::Result<Option<ProjectAlert>> {
+        Ok(project_alerts::table
+            .filter(project_alerts::project.eq(project))
+            .load::<ProjectAlert>(&mut self.get()?)
+            .context("Unable to load alert by project")?
+            .first()
+            .cloned())
+    }
+
+    fn save_alert(&self, alert: NewProjectAlert) -> anyhow::Result<()> {
+        let mut conn = self.get()?;
+
+        let updated = diesel::update(project_alerts::table)
+            .filter(project_alerts::project.eq(alert.project))
+            .set(&alert)
+            .execute(&mut conn)
+            .context("Unable to update alert")?;
+
+        if updated == 0 {
+            alert
+                .insert_into(project_alerts::table)
+                .execute(&mut conn)
+                .context("Unable to insert alert")?;
+        }
+        Ok(())
+    }
+}
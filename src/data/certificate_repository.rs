@@ -0,0 +1,68 @@
+use crate::db::Database;
+use crate::diesel::Insertable;
+use crate::models::{NewProjectCertificate, ProjectCertificate};
+use crate::schema::project_certificates;
+use actix_web::web::Data;
+use anyhow::Context;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+pub type CertificateRepositoryBox = Box<dyn CertificateRepository>;
+pub type CertificateRepositoryData = Data<CertificateRepositoryBox>;
+
+pub trait CertificateRepository {
+    fn get_all_certificates(&self) -> anyhow::Result<Vec<ProjectCertificate>>;
+    fn get_certificate_by_project(
+        &self,
+        project: i32,
+    ) -> anyhow::Result<Option<ProjectCertificate>>;
+    /// Store the latest certificate seen for a project, replacing any previous one
+    fn save_certificate(&self, certificate: NewProjectCertificate) -> anyhow::Result<()>;
+    fn set_notified_threshold(&self, project: i32, threshold: Option<i32>) -> anyhow::Result<()>;
+}
+
+impl CertificateRepository for Database {
+    fn get_all_certificates(&self) -> anyhow::Result<Vec<ProjectCertificate>> {
+        project_certificates::table
+            .load::<ProjectCertificate>(&mut self.get()?)
+            .context("Unable to load certificates")
+    }
+
+    fn get_certificate_by_project(
+        &self,
+        project: i32,
+    ) -> anyhow::Result<Option<ProjectCertificate>> {
+        Ok(project_certificates::table
+            .filter(project_certificates::project.eq(project))
+            .load::<ProjectCertificate>(&mut self.get()?)
+            .context("Unable to load certificate by project")?
+            .first()
+            .cloned())
+    }
+
+    fn save_certificate(&self, certificate: NewProjectCertificate) -> anyhow::Result<()> {
+        let mut conn = self.get()?;
+
+        let updated = diesel::update(project_certificates::table)
+            .filter(project_certificates::project.eq(certificate.project))
+            .set(&certificate)
+            .execute(&mut conn)
+            .context("Unable to update certificate")?;
+
+        if updated == 0 {
+            certificate
+                .insert_into(project_certificates::table)
+                .execute(&mut conn)
+                .context("Unable to insert certificate")?;
+        }
+        Ok(())
+    }
+
+    fn set_notified_threshold(&self, project: i32, threshold: Option<i32>) -> anyhow::Result<()> {
+        diesel::update(project_certificates::table)
+            .filter(project_certificates::project.eq(project))
+            .set(project_certificates::notified_threshold.eq(threshold))
+            .execute(&mut self.get()?)
+            .context("Unable to update certificate notification threshold")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use crate::db::Database;
+use crate::models::{EmailSubscription, NewEmailSubscription};
+use crate::schema::email_subscriptions;
+use actix_web::web::Data;
+use anyhow::Context;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+pub type EmailSubscriberRepository = Box<dyn EmailSubscriptionRepository>;
+pub type EmailSubscriberRepositoryData = Data<EmailSubscriberRepository>;
+
+pub trait EmailSubscriptionRepository {
+    fn get_all_confirmed_subscribers(&self) -> anyhow::Result<Vec<EmailSubscription>>;
+    fn get_all(&self) -> anyhow::Result<Vec<EmailSubscription>>;
+    /// Add an unconfirmed subscription for an address, returning it
+    fn subscribe(&self, email: &str) -> anyhow::Result<EmailSubscription>;
+    fn confirm(&self, id: i32) -> anyhow::Result<()>;
+}
+
+impl EmailSubscriptionRepository for Database {
+    fn get_all_confirmed_subscribers(&self) -> anyhow::Result<Vec<EmailSubscription>> {
+        email_subscriptions::table
+            .filter(email_subscriptions::confirmed.eq(true))
+            .load::<EmailSubscription>(&mut self.get()?)
+            .context("Unable to load email subscribers")
+    }
+
+    fn get_all(&self) -> anyhow::Result<Vec<EmailSubscription>> {
+        email_subscriptions::table
+            .load::<EmailSubscription>(&mut self.get()?)
+            .context("Unable to load email subscribers")
+    }
+
+    fn subscribe(&self, email: &str) -> anyhow::Result<EmailSubscription> {
+        let mut conn = self.get()?;
+
+        diesel::insert_into(email_subscriptions::table)
+            .values(NewEmailSubscription {
+                email: email.to_string(),
+            })
+            .execute(&mut conn)
+            .context("Unable to insert email subscription")?;
+
+        email_subscriptions::table
+            .filter(email_subscriptions::email.eq(email))
+            .order(email_subscriptions::id.desc())
+            .first::<EmailSubscription>(&mut conn)
+            .context("Unable to load email subscription")
+    }
+
+    fn confirm(&self, id: i32) -> anyhow::Result<()> {
+        diesel::update(email_subscriptions::table.filter(email_subscriptions::id.eq(id)))
+            .set(email_subscriptions::confirmed.eq(true))
+            .execute(&mut self.get()?)
+            .context("Unable to confirm email subscription")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use crate::db::Database;
+use crate::diesel::Insertable;
+use crate::models::{Heartbeat, NewHeartbeat};
+use crate::schema::heartbeats;
+use actix_web::web::Data;
+use anyhow::Context;
+use chrono::Utc;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+pub type HeartbeatRepositoryBox = Box<dyn HeartbeatRepository>;
+pub type HeartbeatRepositoryData = Data<HeartbeatRepositoryBox>;
+
+pub trait HeartbeatRepository {
+    fn get_all_heartbeats(&self) -> anyhow::Result<Vec<Heartbeat>>;
+    fn get_heartbeat_by_project(&self, project: i32) -> anyhow::Result<Option<Heartbeat>>;
+    /// Create a heartbeat with a new random token, if the project doesn't have one already
+    fn ensure_heartbeat(&self, project: i32) -> anyhow::Result<()>;
+    /// Record a ping for the heartbeat with the given token, returns false if there is no such heartbeat
+    fn record_ping(&self, token: &str) -> anyhow::Result<bool>;
+}
+
+impl HeartbeatRepository for Database {
+    fn get_all_heartbeats(&self) -> anyhow::Result<Vec<Heartbeat>> {
+        heartbeats::table
+            .load::<Heartbeat>(&mut self.get()?)
+            .context("Unable to load heartbeats")
+    }
+
+    fn get_heartbeat_by_project(&self, project: i32) -> anyhow::Result<Option<Heartbeat>> {
+        Ok(heartbeats::table
+            .filter(heartbeats::project.eq(project))
+            .load::<Heartbeat>(&mut self.get()?)
+            .context("Unable to load heartbeat by project")?
+            .first()
+            .cloned())
+    }
+
+    fn ensure_heartbeat(&self, project: i32) -> anyhow::Result<()> {
+        if self.get_heartbeat_by_project(project)?.is_none() {
+            NewHeartbeat {
+                project,
+                token: Uuid::new_v4().simple().to_string(),
+            }
+            .insert_into(heartbeats::table)
+            .execute(&mut self.get()?)
+            .context("Unable to insert heartbeat")?;
+        }
+        Ok(())
+    }
+
+    fn record_ping(&self, token: &str) -> anyhow::Result<bool> {
+        let updated = diesel::update(heartbeats::table)
+            .filter(heartbeats::token.eq(token))
+            .set(heartbeats::last_ping.eq(Utc::now().naive_utc()))
+            .execute(&mut self.get()?)
+            .context("Unable to record heartbeat ping")?;
+        Ok(updated > 0)
+    }
+}
@@ -0,0 +1,880 @@
+use crate::data::alert_repository::AlertRepository;
+use crate::data::certificate_repository::CertificateRepository;
+use crate::data::email_subscription_repository::EmailSubscriptionRepository;
+use crate::data::heartbeat_repository::HeartbeatRepository;
+use crate::data::incident_repository::{IncidentRepository, IncidentWithUpdates};
+use crate::data::outage_repository::OutageRepository;
+use crate::data::project_repository::ProjectRepository;
+use crate::data::rollup_repository::RollupRepository;
+use crate::data::settings_repository::SettingsRepository;
+use crate::data::slo_repository::SloRepository;
+use crate::data::sms_subscription_repository::SmsSubscriptionRepository;
+use crate::data::status_repository::{StatusRepository, project_timeline, project_timelines};
+use crate::data::webhook_subscription_repository::WebhookSubscriptionRepository;
+use crate::models::fixtures;
+use crate::models::{
+    DailyStatusCounts, EmailSubscription, Heartbeat, IncidentStatusType, IncidentStatusUpdate,
+    Incidents, NewIncident, NewIncidentStatusUpdate, NewOutage, NewProjectAlert,
+    NewProjectCertificate, NewSlo, NewStatus, NewStatusRollup, Outage, Project, ProjectAlert,
+    ProjectCertificate, ProjectSettings, RollupTotals, Slo, SmsSubscription, Status, StatusRollup,
+    WebhookSubscription,
+};
+use crate::rollup::RollupPeriod;
+use crate::settings;
+use chrono::{NaiveDateTime, Utc};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+
+/// Every repository kept in memory, for exercising the routes without a database.
+/// Clones share the same tables
+#[derive(Clone, Default)]
+pub struct InMemoryDatabase {
+    tables: Arc<Mutex<Tables>>,
+}
+
+#[derive(Default)]
+pub struct Tables {
+    next_id: i32,
+    pub projects: Vec<Project>,
+    pub status: Vec<Status>,
+    pub rollups: Vec<StatusRollup>,
+    pub incidents: Vec<Incidents>,
+    pub incident_status_types: Vec<IncidentStatusType>,
+    pub incident_status_updates: Vec<IncidentStatusUpdate>,
+    pub email_subscriptions: Vec<EmailSubscription>,
+    pub sms_subscriptions: Vec<SmsSubscription>,
+    pub webhook_subscriptions: Vec<WebhookSubscription>,
+    pub certificates: Vec<ProjectCertificate>,
+    pub heartbeats: Vec<Heartbeat>,
+    pub alerts: Vec<ProjectAlert>,
+    pub outages: Vec<Outage>,
+    pub slos: Vec<Slo>,
+    pub settings: BTreeMap<String, String>,
+}
+
+impl Tables {
+    /// Ids are unique across every table, like they would be if each had its own sequence
+    fn next_id(&mut self) -> i32 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+fn newest_first(mut status_list: Vec<Status>) -> Vec<Status> {
+    status_list.sort_by_key(|s| Reverse(s.created));
+    status_list
+}
+
+impl InMemoryDatabase {
+    pub fn tables(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().expect("In memory database poisoned")
+    }
+
+    /// Add an enabled project with the defaults from the migrations
+    pub fn add_project(&self, name: &str, url: &str) -> Project {
+        self.insert_project(name, url, true)
+    }
+
+    fn insert_project(&self, name: &str, url: &str, enabled: bool) -> Project {
+        let mut tables = self.tables();
+        let project = Project {
+            id: tables.next_id(),
+            url: url.to_string(),
+            name: name.to_string(),
+            created: now(),
+            enabled,
+            ..fixtures::project(0)
+        };
+        tables.projects.push(project.clone());
+        project
+    }
+
+    pub fn add_incident_status_type(&self, title: &str, colour: &str) -> IncidentStatusType {
+        let mut tables = self.tables();
+        let status_type = IncidentStatusType {
+            id: tables.next_id(),
+            created: now(),
+            colour: colour.to_string(),
+            title: title.to_string(),
+        };
+        tables.incident_status_types.push(status_type.clone());
+        status_type
+    }
+
+    fn modify_project(&self, id: i32, update: impl FnOnce(&mut Project)) -> anyhow::Result<()> {
+        if let Some(project) = self.tables().projects.iter_mut().find(|p| p.id == id) {
+            update(project);
+        }
+        Ok(())
+    }
+}
+
+impl ProjectRepository for InMemoryDatabase {
+    fn get_project_by_name(&self, name: &str) -> anyhow::Result<Vec<Project>> {
+        Ok(self
+            .tables()
+            .projects
+            .iter()
+            .filter(|p| p.name == name)
+            .cloned()
+            .collect())
+    }
+
+    fn get_all_projects(&self) -> anyhow::Result<Vec<Project>> {
+        Ok(self.tables().projects.clone())
+    }
+
+    fn get_all_enabled_projects(&self) -> anyhow::Result<Vec<Project>> {
+        Ok(self
+            .tables()
+            .projects
+            .iter()
+            .filter(|p| p.enabled)
+            .cloned()
+            .collect())
+    }
+
+    fn get_project_by_id(&self, id: i32) -> Option<Project> {
+        self.tables().projects.iter().find(|p| p.id == id).cloned()
+    }
+
+    fn create(&self, name: &str) -> anyhow::Result<()> {
+        self.insert_project(name, "", false);
+        Ok(())
+    }
+
+    fn update_project(
+        &self,
+        id: i32,
+        name: &str,
+        enable: bool,
+        url: &str,
+        description: &str,
+    ) -> anyhow::Result<()> {
+        self.modify_project(id, |p| {
+            p.name = name.to_string();
+            p.enabled = enable;
+            p.url = url.to_string();
+            p.description = Some(description.to_string());
+        })
+    }
+
+    fn update_project_settings(&self, id: i32, settings: &ProjectSettings) -> anyhow::Result<()> {
+        self.modify_project(id, |p| {
+            p.check_type = settings.check_type.clone();
+            p.check_config = settings.check_config.clone();
+            p.check_interval = settings.check_interval;
+            p.latency_warn_ms = settings.latency_warn_ms;
+            p.latency_critical_ms = settings.latency_critical_ms;
+            p.latency_use_p95 = settings.latency_use_p95;
+            p.notify_degraded = settings.notify_degraded;
+            p.down_after_failures = settings.down_after_failures;
+            p.up_after_successes = settings.up_after_successes;
+            p.flap_threshold = settings.flap_threshold;
+            p.flap_window_minutes = settings.flap_window_minutes;
+            p.sla_target = settings.sla_target;
+        })
+    }
+}
+
+impl StatusRepository for InMemoryDatabase {
+    fn add_statuses(&self, statuses: &[NewStatus]) -> anyhow::Result<()> {
+        let mut tables = self.tables();
+        for status in statuses {
+            let id = tables.next_id();
+            tables.status.push(Status {
+                id,
+                project: status.project,
+                time: status.time,
+                status_code: status.status_code,
+                created: status.created,
+                success: status.success,
+                message: status.message.clone(),
+                degraded: status.degraded,
+                steps: status.steps.clone(),
+                location: status.location.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    fn get_daily_status_counts(
+        &self,
+        projects: &[i32],
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<DailyStatusCounts>> {
+        let mut grouped = BTreeMap::new();
+        for status in self
+            .tables()
+            .status
+            .iter()
+            .filter(|s| projects.contains(&s.project) && s.created >= from && s.created < to)
+        {
+            let (samples, total_ms) = grouped
+                .entry((status.project, status.created.date(), status.success))
+                .or_insert((0, Some(0)));
+            *samples += 1;
+            *total_ms = total_ms.map(|total_ms| total_ms + i64::from(status.time));
+        }
+
+        Ok(DailyStatusCounts::from_grouped(
+            grouped
+                .into_iter()
+                .map(|((project, day, success), (samples, total_ms))| {
+                    (project, day, success, samples, total_ms)
+                })
+                .collect(),
+        ))
+    }
+
+    fn get_oldest_status_time(&self, project: i32) -> anyhow::Result<Option<NaiveDateTime>> {
+        Ok(self
+            .tables()
+            .status
+            .iter()
+            .filter(|s| s.project == project)
+            .map(|s| s.created)
+            .min())
+    }
+
+    fn delete_status_before(&self, before: NaiveDateTime) -> anyhow::Result<usize> {
+        let mut tables = self.tables();
+        let count = tables.status.len();
+        tables.status.retain(|s| s.created >= before);
+        Ok(count - tables.status.len())
+    }
+
+    fn get_recent_status_for_project(
+        &self,
+        project: i32,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Status>> {
+        let status_list = self
+            .tables()
+            .status
+            .iter()
+            .filter(|s| s.project == project && s.created > since)
+            .cloned()
+            .collect();
+        Ok(newest_first(status_list))
+    }
+
+    fn get_project_timeline(
+        &self,
+        project: i32,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Status>> {
+        self.get_recent_status_for_project(project, since)
+            .map(project_timeline)
+    }
+
+    fn get_project_timelines(
+        &self,
+        projects: &[i32],
+        since: NaiveDateTime,
+    ) -> anyhow::Result<BTreeMap<i32, Vec<Status>>> {
+        let status_list = self
+            .tables()
+            .status
+            .iter()
+            .filter(|s| projects.contains(&s.project) && s.created > since)
+            .cloned()
+            .collect();
+        Ok(project_timelines(newest_first(status_list)))
+    }
+
+    fn get_project_timeline_between(
+        &self,
+        project: i32,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Status>> {
+        let status_list = self
+            .tables()
+            .status
+            .iter()
+            .filter(|s| s.project == project && s.created >= from && s.created < to)
+            .cloned()
+            .collect();
+        Ok(project_timeline(newest_first(status_list)))
+    }
+
+    fn get_latest_status_by_location(&self) -> anyhow::Result<Vec<Status>> {
+        let since = now() - settings::location_stale_after();
+        let status_list = self
+            .tables()
+            .status
+            .iter()
+            .filter(|s| s.created > since)
+            .cloned()
+            .collect();
+
+        let mut seen = HashSet::new();
+        Ok(newest_first(status_list)
+            .into_iter()
+            .filter(|s| seen.insert((s.project, s.location.clone())))
+            .collect())
+    }
+
+    fn get_recent_latencies(
+        &self,
+        project: i32,
+        location: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<i32>> {
+        let status_list = self
+            .tables()
+            .status
+            .iter()
+            .filter(|s| s.project == project && s.location == location)
+            .cloned()
+            .collect();
+        Ok(newest_first(status_list)
+            .into_iter()
+            .take(usize::try_from(limit).unwrap_or(0))
+            .map(|s| s.time)
+            .collect())
+    }
+}
+
+impl RollupRepository for InMemoryDatabase {
+    fn get_rollups_since(
+        &self,
+        period: RollupPeriod,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<StatusRollup>> {
+        let mut rollups = self
+            .tables()
+            .rollups
+            .iter()
+            .filter(|r| r.period_seconds == period.seconds() && r.period_start >= since)
+            .cloned()
+            .collect::<Vec<_>>();
+        rollups.sort_by_key(|r| Reverse(r.period_start));
+        Ok(rollups)
+    }
+
+    fn get_project_rollups_since(
+        &self,
+        project: i32,
+        period: RollupPeriod,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<StatusRollup>> {
+        Ok(self
+            .get_rollups_since(period, since)?
+            .into_iter()
+            .filter(|r| r.project == project)
+            .collect())
+    }
+
+    fn get_rollups_between(
+        &self,
+        period: RollupPeriod,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<StatusRollup>> {
+        Ok(self
+            .get_rollups_since(period, from)?
+            .into_iter()
+            .filter(|r| r.period_start < to)
+            .collect())
+    }
+
+    fn get_hourly_totals(&self, since: NaiveDateTime) -> anyhow::Result<Vec<RollupTotals>> {
+        let mut totals = BTreeMap::<i32, RollupTotals>::new();
+        for rollup in self.get_rollups_since(RollupPeriod::Hour, since)? {
+            let total = totals.entry(rollup.project).or_insert(RollupTotals {
+                project: rollup.project,
+                up_seconds: Some(0),
+                down_seconds: Some(0),
+                latest_start: None,
+            });
+            total.up_seconds = total.up_seconds.map(|s| s + i64::from(rollup.up_seconds));
+            total.down_seconds = total
+                .down_seconds
+                .map(|s| s + i64::from(rollup.down_seconds));
+            total.latest_start = total.latest_start.max(Some(rollup.period_start));
+        }
+        Ok(totals.into_values().collect())
+    }
+
+    fn get_checked_days(&self, since: NaiveDateTime) -> anyhow::Result<Vec<NaiveDateTime>> {
+        Ok(self
+            .get_rollups_since(RollupPeriod::Day, since)?
+            .into_iter()
+            .filter(|r| r.samples > 0)
+            .map(|r| r.period_start)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect())
+    }
+
+    fn get_latest_rollup(
+        &self,
+        project: i32,
+        period: RollupPeriod,
+    ) -> anyhow::Result<Option<StatusRollup>> {
+        Ok(self
+            .tables()
+            .rollups
+            .iter()
+            .filter(|r| r.project == project && r.period_seconds == period.seconds())
+            .max_by_key(|r| r.period_start)
+            .cloned())
+    }
+
+    fn save_rollups(&self, rollups: &[NewStatusRollup]) -> anyhow::Result<()> {
+        let mut tables = self.tables();
+        for rollup in rollups {
+            anyhow::ensure!(
+                !tables.rollups.iter().any(|r| r.project == rollup.project
+                    && r.period_seconds == rollup.period_seconds
+                    && r.period_start == rollup.period_start),
+                "Duplicate rollup"
+            );
+            let id = tables.next_id();
+            tables.rollups.push(StatusRollup {
+                id,
+                created: now(),
+                project: rollup.project,
+                period_seconds: rollup.period_seconds,
+                period_start: rollup.period_start,
+                samples: rollup.samples,
+                failures: rollup.failures,
+                degraded: rollup.degraded,
+                up_seconds: rollup.up_seconds,
+                down_seconds: rollup.down_seconds,
+                min_latency_ms: rollup.min_latency_ms,
+                avg_latency_ms: rollup.avg_latency_ms,
+                p95_latency_ms: rollup.p95_latency_ms,
+                status_codes: rollup.status_codes.clone(),
+                latency_histogram: Some(rollup.latency_histogram.clone()),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl IncidentRepository for InMemoryDatabase {
+    fn get_all_incidents(&self) -> Vec<Incidents> {
+        self.tables().incidents.clone()
+    }
+
+    fn get_incidents_between(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Incidents>> {
+        Ok(self
+            .get_all_incidents()
+            .into_iter()
+            .filter(|i| i.created >= from && i.created < to)
+            .collect())
+    }
+
+    fn get_incidents_since(
+        &self,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<IncidentWithUpdates>> {
+        let incidents = self
+            .get_all_incidents()
+            .into_iter()
+            .filter(|i| i.created > since)
+            .collect::<Vec<_>>();
+
+        Ok(incidents
+            .into_iter()
+            .map(|incident| {
+                let updates = self.get_status_updates_by_incident(&incident);
+                (incident, updates)
+            })
+            .collect())
+    }
+
+    fn add_incident(&self, incident: NewIncident) {
+        let mut tables = self.tables();
+        let id = tables.next_id();
+        tables.incidents.push(Incidents {
+            id,
+            created: now(),
+            project: incident.project,
+        });
+    }
+
+    fn get_incident_by_id(&self, id: i32) -> Incidents {
+        self.tables()
+            .incidents
+            .iter()
+            .find(|i| i.id == id)
+            .cloned()
+            .unwrap()
+    }
+
+    fn get_status_updates_by_incident(
+        &self,
+        incident: &Incidents,
+    ) -> Vec<(IncidentStatusUpdate, IncidentStatusType)> {
+        let tables = self.tables();
+        let mut updates = tables
+            .incident_status_updates
+            .iter()
+            .filter(|u| u.incident == incident.id)
+            .filter_map(|u| {
+                let status_type = tables
+                    .incident_status_types
+                    .iter()
+                    .find(|t| t.id == u.status_type)?;
+                Some((u.clone(), status_type.clone()))
+            })
+            .collect::<Vec<_>>();
+        updates.sort_by_key(|(u, _)| Reverse(u.created));
+        updates
+    }
+
+    fn add_status_update(&self, status_update: NewIncidentStatusUpdate) {
+        let mut tables = self.tables();
+        let id = tables.next_id();
+        tables.incident_status_updates.push(IncidentStatusUpdate {
+            id,
+            created: now(),
+            status_type: status_update.status_type,
+            message: status_update.message,
+            incident: status_update.incident,
+        });
+    }
+
+    fn get_incident_status_types(&self) -> anyhow::Result<Vec<IncidentStatusType>> {
+        Ok(self.tables().incident_status_types.clone())
+    }
+
+    fn get_incident_status_type_by_title(&self, name: &str) -> Option<IncidentStatusType> {
+        self.tables()
+            .incident_status_types
+            .iter()
+            .find(|t| t.title == name)
+            .cloned()
+    }
+}
+
+impl EmailSubscriptionRepository for InMemoryDatabase {
+    fn get_all_confirmed_subscribers(&self) -> anyhow::Result<Vec<EmailSubscription>> {
+        Ok(self
+            .tables()
+            .email_subscriptions
+            .iter()
+            .filter(|s| s.confirmed)
+            .cloned()
+            .collect())
+    }
+
+    fn get_all(&self) -> anyhow::Result<Vec<EmailSubscription>> {
+        Ok(self.tables().email_subscriptions.clone())
+    }
+
+    fn subscribe(&self, email: &str) -> anyhow::Result<EmailSubscription> {
+        let mut tables = self.tables();
+        let subscription = EmailSubscription {
+            id: tables.next_id(),
+            created: now(),
+            email: email.to_string(),
+            confirmed: false,
+        };
+        tables.email_subscriptions.push(subscription.clone());
+        Ok(subscription)
+    }
+
+    fn confirm(&self, id: i32) -> anyhow::Result<()> {
+        if let Some(subscription) = self
+            .tables()
+            .email_subscriptions
+            .iter_mut()
+            .find(|s| s.id == id)
+        {
+            subscription.confirmed = true;
+        }
+        Ok(())
+    }
+}
+
+impl SmsSubscriptionRepository for InMemoryDatabase {
+    fn get_all_confirmed_subscribers(&self) -> Vec<SmsSubscription> {
+        self.tables()
+            .sms_subscriptions
+            .iter()
+            .filter(|s| s.confirmed)
+            .cloned()
+            .collect()
+    }
+
+    fn get_all(&self) -> Vec<SmsSubscription> {
+        self.tables().sms_subscriptions.clone()
+    }
+}
+
+impl WebhookSubscriptionRepository for InMemoryDatabase {
+    fn get_all_enabled_subscribers(&self) -> Vec<WebhookSubscription> {
+        self.tables()
+            .webhook_subscriptions
+            .iter()
+            .filter(|s| s.enabled)
+            .cloned()
+            .collect()
+    }
+
+    fn get_all(&self) -> Vec<WebhookSubscription> {
+        self.tables().webhook_subscriptions.clone()
+    }
+}
+
+impl CertificateRepository for InMemoryDatabase {
+    fn get_all_certificates(&self) -> anyhow::Result<Vec<ProjectCertificate>> {
+        Ok(self.tables().certificates.clone())
+    }
+
+    fn get_certificate_by_project(
+        &self,
+        project: i32,
+    ) -> anyhow::Result<Option<ProjectCertificate>> {
+        Ok(self
+            .tables()
+            .certificates
+            .iter()
+            .find(|c| c.project == project)
+            .cloned())
+    }
+
+    fn save_certificate(&self, certificate: NewProjectCertificate) -> anyhow::Result<()> {
+        let mut tables = self.tables();
+        match tables
+            .certificates
+            .iter_mut()
+            .find(|c| c.project == certificate.project)
+        {
+            Some(existing) => {
+                existing.not_after = certificate.not_after;
+                existing.issuer = certificate.issuer;
+                existing.sans = certificate.sans;
+            }
+            None => {
+                let id = tables.next_id();
+                tables.certificates.push(ProjectCertificate {
+                    id,
+                    created: now(),
+                    project: certificate.project,
+                    not_after: certificate.not_after,
+                    issuer: certificate.issuer,
+                    sans: certificate.sans,
+                    notified_threshold: None,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn set_notified_threshold(&self, project: i32, threshold: Option<i32>) -> anyhow::Result<()> {
+        if let Some(certificate) = self
+            .tables()
+            .certificates
+            .iter_mut()
+            .find(|c| c.project == project)
+        {
+            certificate.notified_threshold = threshold;
+        }
+        Ok(())
+    }
+}
+
+impl HeartbeatRepository for InMemoryDatabase {
+    fn get_all_heartbeats(&self) -> anyhow::Result<Vec<Heartbeat>> {
+        Ok(self.tables().heartbeats.clone())
+    }
+
+    fn get_heartbeat_by_project(&self, project: i32) -> anyhow::Result<Option<Heartbeat>> {
+        Ok(self
+            .tables()
+            .heartbeats
+            .iter()
+            .find(|h| h.project == project)
+            .cloned())
+    }
+
+    fn ensure_heartbeat(&self, project: i32) -> anyhow::Result<()> {
+        if self.get_heartbeat_by_project(project)?.is_none() {
+            let mut tables = self.tables();
+            let id = tables.next_id();
+            tables.heartbeats.push(Heartbeat {
+                id,
+                created: now(),
+                project,
+                token: Uuid::new_v4().simple().to_string(),
+                last_ping: None,
+            });
+        }
+        Ok(())
+    }
+
+    fn record_ping(&self, token: &str) -> anyhow::Result<bool> {
+        let mut tables = self.tables();
+        let heartbeat = tables.heartbeats.iter_mut().find(|h| h.token == token);
+        Ok(match heartbeat {
+            Some(heartbeat) => {
+                heartbeat.last_ping = Some(now());
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+impl AlertRepository for InMemoryDatabase {
+    fn get_all_alerts(&self) -> anyhow::Result<Vec<ProjectAlert>> {
+        Ok(self.tables().alerts.clone())
+    }
+
+    fn get_alert_by_project(&self, project: i32) -> anyhow::Result<Option<ProjectAlert>> {
+        Ok(self
+            .tables()
+            .alerts
+            .iter()
+            .find(|a| a.project == project)
+            .cloned())
+    }
+
+    fn save_alert(&self, alert: NewProjectAlert) -> anyhow::Result<()> {
+        let mut tables = self.tables();
+        match tables
+            .alerts
+            .iter_mut()
+            .find(|a| a.project == alert.project)
+        {
+            Some(existing) => {
+                existing.down = alert.down;
+                existing.streak = alert.streak;
+                existing.flapping = alert.flapping;
+                existing.changed = alert.changed;
+            }
+            None => {
+                let id = tables.next_id();
+                tables.alerts.push(ProjectAlert {
+                    id,
+                    created: now(),
+                    project: alert.project,
+                    down: alert.down,
+                    streak: alert.streak,
+                    flapping: alert.flapping,
+                    changed: alert.changed,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OutageRepository for InMemoryDatabase {
+    fn get_outages_since(&self, since: NaiveDateTime) -> anyhow::Result<Vec<Outage>> {
+        let mut outages = self
+            .tables()
+            .outages
+            .iter()
+            .filter(|o| o.ended.is_none_or(|ended| ended >= since))
+            .cloned()
+            .collect::<Vec<_>>();
+        outages.sort_by_key(|o| Reverse(o.started));
+        Ok(outages)
+    }
+
+    fn get_open_outage(&self, project: i32) -> anyhow::Result<Option<Outage>> {
+        Ok(self
+            .tables()
+            .outages
+            .iter()
+            .filter(|o| o.project == project && o.ended.is_none())
+            .max_by_key(|o| o.started)
+            .cloned())
+    }
+
+    fn open_outage(&self, outage: NewOutage) -> anyhow::Result<()> {
+        let mut tables = self.tables();
+        let id = tables.next_id();
+        tables.outages.push(Outage {
+            id,
+            created: now(),
+            project: outage.project,
+            started: outage.started,
+            ended: None,
+            cause: outage.cause,
+            first_status_code: outage.first_status_code,
+            last_status_code: outage.last_status_code,
+        });
+        Ok(())
+    }
+
+    fn set_last_status_code(&self, id: i32, status_code: Option<i32>) -> anyhow::Result<()> {
+        if let Some(outage) = self.tables().outages.iter_mut().find(|o| o.id == id) {
+            outage.last_status_code = status_code;
+        }
+        Ok(())
+    }
+
+    fn close_outage(&self, id: i32, ended: NaiveDateTime) -> anyhow::Result<()> {
+        if let Some(outage) = self.tables().outages.iter_mut().find(|o| o.id == id) {
+            outage.ended = Some(ended);
+        }
+        Ok(())
+    }
+}
+
+impl SloRepository for InMemoryDatabase {
+    fn get_all_slos(&self) -> anyhow::Result<Vec<Slo>> {
+        let mut slos = self.tables().slos.clone();
+        slos.sort_by_key(|s| s.project);
+        Ok(slos)
+    }
+
+    fn create_slo(&self, slo: NewSlo) -> anyhow::Result<()> {
+        let mut tables = self.tables();
+        let id = tables.next_id();
+        tables.slos.push(Slo {
+            id,
+            created: now(),
+            project: slo.project,
+            objective: slo.objective,
+            window_days: slo.window_days,
+            latency_threshold_ms: slo.latency_threshold_ms,
+            burning: false,
+        });
+        Ok(())
+    }
+
+    fn delete_slo(&self, id: i32) -> anyhow::Result<()> {
+        self.tables().slos.retain(|s| s.id != id);
+        Ok(())
+    }
+
+    fn set_burning(&self, id: i32, burning: bool) -> anyhow::Result<()> {
+        if let Some(slo) = self.tables().slos.iter_mut().find(|s| s.id == id) {
+            slo.burning = burning;
+        }
+        Ok(())
+    }
+}
+
+impl SettingsRepository for InMemoryDatabase {
+    fn get_setting(&self, name: &str) -> anyhow::Result<Option<String>> {
+        Ok(self.tables().settings.get(name).cloned())
+    }
+
+    fn set_setting(&self, name: &str, value: &str) -> anyhow::Result<()> {
+        self.tables()
+            .settings
+            .insert(name.to_string(), value.to_string());
+        Ok(())
+    }
+}
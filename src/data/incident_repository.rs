@@ -5,13 +5,26 @@ use crate::models::{
 };
 use crate::schema::{incident_status_type, incident_status_update, incidents};
 use actix_web::web::Data;
-use diesel::{BelongingToDsl, ExpressionMethods, QueryDsl, RunQueryDsl};
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use diesel::{BelongingToDsl, ExpressionMethods, GroupedBy, QueryDsl, RunQueryDsl};
 
 pub type IncidentRepositoryData = Data<Box<dyn IncidentRepository>>;
 
+/// An incident along with its status updates, newest first
+pub type IncidentWithUpdates = (Incidents, Vec<(IncidentStatusUpdate, IncidentStatusType)>);
+
 pub trait IncidentRepository {
-    fn get_incident_by_name(&self, name: &str) -> Vec<Incidents>;
     fn get_all_incidents(&self) -> Vec<Incidents>;
+    /// Incidents created at or after `from` and before `to`
+    fn get_incidents_between(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Incidents>>;
+    /// Incidents created since the given time, along with their status updates
+    fn get_incidents_since(&self, since: NaiveDateTime)
+    -> anyhow::Result<Vec<IncidentWithUpdates>>;
 
     fn add_incident(&self, incident: NewIncident);
     fn get_incident_by_id(&self, id: i32) -> Incidents;
@@ -22,20 +35,50 @@ pub trait IncidentRepository {
     ) -> Vec<(IncidentStatusUpdate, IncidentStatusType)>;
     fn add_status_update(&self, status_update: NewIncidentStatusUpdate);
 
+    fn get_incident_status_types(&self) -> anyhow::Result<Vec<IncidentStatusType>>;
     fn get_incident_status_type_by_title(&self, name: &str) -> Option<IncidentStatusType>;
 }
 
 impl IncidentRepository for Database {
-    fn get_incident_by_name(&self, _name: &str) -> Vec<Incidents> {
-        unimplemented!("get_incident_by_name not impl")
-    }
-
     fn get_all_incidents(&self) -> Vec<Incidents> {
         incidents::table
             .load(&mut self.get().unwrap())
             .expect("Unable to get all incidents")
     }
 
+    fn get_incidents_between(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Incidents>> {
+        incidents::table
+            .filter(incidents::created.ge(from))
+            .filter(incidents::created.lt(to))
+            .load::<Incidents>(&mut self.get()?)
+            .context("Unable to load incidents")
+    }
+
+    fn get_incidents_since(
+        &self,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<IncidentWithUpdates>> {
+        let mut conn = self.get()?;
+
+        let all_incidents = incidents::table
+            .filter(incidents::created.gt(since))
+            .load::<Incidents>(&mut conn)
+            .context("Unable to load incidents")?;
+
+        let status_updates = IncidentStatusUpdate::belonging_to(&all_incidents)
+            .order(incident_status_update::dsl::created.desc())
+            .inner_join(incident_status_type::table)
+            .load(&mut conn)
+            .context("Unable to load status updates")?
+            .grouped_by(&all_incidents);
+
+        Ok(all_incidents.into_iter().zip(status_updates).collect())
+    }
+
     fn add_incident(&self, incident: NewIncident) {
         incident
             .insert_into(incidents::table)
@@ -73,6 +116,12 @@ impl IncidentRepository for Database {
             .expect("Unable to insert incident status update");
     }
 
+    fn get_incident_status_types(&self) -> anyhow::Result<Vec<IncidentStatusType>> {
+        incident_status_type::table
+            .load::<IncidentStatusType>(&mut self.get()?)
+            .context("Unable to load incident status types")
+    }
+
     fn get_incident_status_type_by_title(&self, name: &str) -> Option<IncidentStatusType> {
         incident_status_type::table
             .filter(incident_status_type::dsl::title.eq(name))
@@ -1,5 +1,15 @@
+pub mod alert_repository;
+pub mod certificate_repository;
+pub mod email_subscription_repository;
+pub mod heartbeat_repository;
+#[cfg(test)]
+pub mod in_memory;
 pub mod incident_repository;
+pub mod outage_repository;
 pub mod project_repository;
+pub mod rollup_repository;
+pub mod settings_repository;
+pub mod slo_repository;
 pub mod sms_subscription_repository;
 pub mod status_repository;
 pub mod webhook_subscription_repository;
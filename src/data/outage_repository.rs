@@ -0,0 +1,68 @@
+use crate::db::Database;
+use crate::diesel::Insertable;
+use crate::models::{NewOutage, Outage};
+use crate::schema::outages;
+use actix_web::web::Data;
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use diesel::{BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+pub type OutageRepositoryBox = Box<dyn OutageRepository>;
+pub type OutageRepositoryData = Data<OutageRepositoryBox>;
+
+pub trait OutageRepository {
+    /// Outages of every project that were ongoing at any point since the given time, newest first
+    fn get_outages_since(&self, since: NaiveDateTime) -> anyhow::Result<Vec<Outage>>;
+    /// The outage a project is currently in, if it is down
+    fn get_open_outage(&self, project: i32) -> anyhow::Result<Option<Outage>>;
+    fn open_outage(&self, outage: NewOutage) -> anyhow::Result<()>;
+    /// Record another failure during an ongoing outage
+    fn set_last_status_code(&self, id: i32, status_code: Option<i32>) -> anyhow::Result<()>;
+    fn close_outage(&self, id: i32, ended: NaiveDateTime) -> anyhow::Result<()>;
+}
+
+impl OutageRepository for Database {
+    fn get_outages_since(&self, since: NaiveDateTime) -> anyhow::Result<Vec<Outage>> {
+        outages::table
+            .filter(outages::ended.is_null().or(outages::ended.ge(since)))
+            .order(outages::started.desc())
+            .load::<Outage>(&mut self.get()?)
+            .context("Unable to load outages")
+    }
+
+    fn get_open_outage(&self, project: i32) -> anyhow::Result<Option<Outage>> {
+        outages::table
+            .filter(outages::project.eq(project))
+            .filter(outages::ended.is_null())
+            .order(outages::started.desc())
+            .first::<Outage>(&mut self.get()?)
+            .optional()
+            .context("Unable to load open outage")
+    }
+
+    fn open_outage(&self, outage: NewOutage) -> anyhow::Result<()> {
+        outage
+            .insert_into(outages::table)
+            .execute(&mut self.get()?)
+            .context("Unable to insert outage")?;
+        Ok(())
+    }
+
+    fn set_last_status_code(&self, id: i32, status_code: Option<i32>) -> anyhow::Result<()> {
+        diesel::update(outages::table)
+            .filter(outages::id.eq(id))
+            .set(outages::last_status_code.eq(status_code))
+            .execute(&mut self.get()?)
+            .context("Unable to update outage")?;
+        Ok(())
+    }
+
+    fn close_outage(&self, id: i32, ended: NaiveDateTime) -> anyhow::Result<()> {
+        diesel::update(outages::table)
+            .filter(outages::id.eq(id))
+            .set(outages::ended.eq(Some(ended)))
+            .execute(&mut self.get()?)
+            .context("Unable to close outage")?;
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use crate::db::Database;
 use crate::diesel::Insertable;
-use crate::models::{NewProject, Project};
+use crate::models::{NewProject, Project, ProjectSettings};
 use crate::schema::projects;
 use actix_web::web::Data;
 use anyhow::Context;
@@ -10,6 +10,7 @@ pub type ProjectRepositoryData = Data<Box<dyn ProjectRepository>>;
 
 pub trait ProjectRepository {
     fn get_project_by_name(&self, name: &str) -> anyhow::Result<Vec<Project>>;
+    fn get_all_projects(&self) -> anyhow::Result<Vec<Project>>;
     fn get_all_enabled_projects(&self) -> anyhow::Result<Vec<Project>>;
     fn get_project_by_id(&self, id: i32) -> Option<Project>;
     fn create(&self, name: &str) -> anyhow::Result<()>;
@@ -21,6 +22,7 @@ pub trait ProjectRepository {
         url: &str,
         description: &str,
     ) -> anyhow::Result<()>;
+    fn update_project_settings(&self, id: i32, settings: &ProjectSettings) -> anyhow::Result<()>;
 }
 
 impl ProjectRepository for Database {
@@ -33,6 +35,12 @@ impl ProjectRepository for Database {
             .expect("Unable to load projects"))
     }
 
+    fn get_all_projects(&self) -> anyhow::Result<Vec<Project>> {
+        projects::table
+            .load::<Project>(&mut self.get()?)
+            .context("Unable to load projects")
+    }
+
     fn get_all_enabled_projects(&self) -> anyhow::Result<Vec<Project>> {
         let mut pool = self.get()?;
 
@@ -84,4 +92,13 @@ impl ProjectRepository for Database {
             .context("Failed to update project")?;
         Ok(())
     }
+
+    fn update_project_settings(&self, id: i32, settings: &ProjectSettings) -> anyhow::Result<()> {
+        diesel::update(projects::table)
+            .filter(projects::id.eq(id))
+            .set(settings)
+            .execute(&mut self.get()?)
+            .context("Failed to update project settings")?;
+        Ok(())
+    }
 }
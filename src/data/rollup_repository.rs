@@ -0,0 +1,138 @@
+use crate::db::Database;
+use crate::models::{NewStatusRollup, RollupTotals, StatusRollup};
+use crate::rollup::RollupPeriod;
+use crate::schema::status_rollups;
+use actix_web::web::Data;
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use diesel::dsl::{max, sum};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+pub type RollupRepositoryBox = Box<dyn RollupRepository>;
+pub type RollupRepositoryData = Data<RollupRepositoryBox>;
+
+pub trait RollupRepository {
+    /// Rollups of every project for periods starting at or after `since`, newest first
+    fn get_rollups_since(
+        &self,
+        period: RollupPeriod,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<StatusRollup>>;
+    /// Rollups of one project for periods starting at or after `since`, newest first
+    fn get_project_rollups_since(
+        &self,
+        project: i32,
+        period: RollupPeriod,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<StatusRollup>>;
+    /// Rollups of every project for periods starting between `from` and `to`, newest first
+    fn get_rollups_between(
+        &self,
+        period: RollupPeriod,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<StatusRollup>>;
+    /// The hourly rollups of each project added up since the given time, computed by the database
+    fn get_hourly_totals(&self, since: NaiveDateTime) -> anyhow::Result<Vec<RollupTotals>>;
+    /// The start of each day since the given time that any project was checked on
+    fn get_checked_days(&self, since: NaiveDateTime) -> anyhow::Result<Vec<NaiveDateTime>>;
+    fn get_latest_rollup(
+        &self,
+        project: i32,
+        period: RollupPeriod,
+    ) -> anyhow::Result<Option<StatusRollup>>;
+    fn save_rollups(&self, rollups: &[NewStatusRollup]) -> anyhow::Result<()>;
+}
+
+impl RollupRepository for Database {
+    fn get_rollups_since(
+        &self,
+        period: RollupPeriod,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<StatusRollup>> {
+        status_rollups::table
+            .filter(status_rollups::period_seconds.eq(period.seconds()))
+            .filter(status_rollups::period_start.ge(since))
+            .order(status_rollups::period_start.desc())
+            .load::<StatusRollup>(&mut self.get()?)
+            .context("Unable to load rollups")
+    }
+
+    fn get_project_rollups_since(
+        &self,
+        project: i32,
+        period: RollupPeriod,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<StatusRollup>> {
+        status_rollups::table
+            .filter(status_rollups::project.eq(project))
+            .filter(status_rollups::period_seconds.eq(period.seconds()))
+            .filter(status_rollups::period_start.ge(since))
+            .order(status_rollups::period_start.desc())
+            .load::<StatusRollup>(&mut self.get()?)
+            .context("Unable to load project rollups")
+    }
+
+    fn get_rollups_between(
+        &self,
+        period: RollupPeriod,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<StatusRollup>> {
+        status_rollups::table
+            .filter(status_rollups::period_seconds.eq(period.seconds()))
+            .filter(status_rollups::period_start.ge(from))
+            .filter(status_rollups::period_start.lt(to))
+            .order(status_rollups::period_start.desc())
+            .load::<StatusRollup>(&mut self.get()?)
+            .context("Unable to load rollups")
+    }
+
+    fn get_hourly_totals(&self, since: NaiveDateTime) -> anyhow::Result<Vec<RollupTotals>> {
+        status_rollups::table
+            .filter(status_rollups::period_seconds.eq(RollupPeriod::Hour.seconds()))
+            .filter(status_rollups::period_start.ge(since))
+            .group_by(status_rollups::project)
+            .select((
+                status_rollups::project,
+                sum(status_rollups::up_seconds),
+                sum(status_rollups::down_seconds),
+                max(status_rollups::period_start),
+            ))
+            .load::<RollupTotals>(&mut self.get()?)
+            .context("Unable to load rollup totals")
+    }
+
+    fn get_checked_days(&self, since: NaiveDateTime) -> anyhow::Result<Vec<NaiveDateTime>> {
+        status_rollups::table
+            .filter(status_rollups::period_seconds.eq(RollupPeriod::Day.seconds()))
+            .filter(status_rollups::period_start.ge(since))
+            .filter(status_rollups::samples.gt(0))
+            .select(status_rollups::period_start)
+            .distinct()
+            .load::<NaiveDateTime>(&mut self.get()?)
+            .context("Unable to load checked days")
+    }
+
+    fn get_latest_rollup(
+        &self,
+        project: i32,
+        period: RollupPeriod,
+    ) -> anyhow::Result<Option<StatusRollup>> {
+        status_rollups::table
+            .filter(status_rollups::project.eq(project))
+            .filter(status_rollups::period_seconds.eq(period.seconds()))
+            .order(status_rollups::period_start.desc())
+            .first::<StatusRollup>(&mut self.get()?)
+            .optional()
+            .context("Unable to load latest rollup")
+    }
+
+    fn save_rollups(&self, rollups: &[NewStatusRollup]) -> anyhow::Result<()> {
+        diesel::insert_into(status_rollups::table)
+            .values(rollups)
+            .execute(&mut self.get()?)
+            .context("Unable to insert rollups")?;
+        Ok(())
+    }
+}
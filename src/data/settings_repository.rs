@@ -0,0 +1,46 @@
+use crate::db::Database;
+use crate::diesel::Insertable;
+use crate::models::{NewSetting, Setting};
+use crate::schema::settings;
+use anyhow::Context;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+pub type SettingsRepositoryBox = Box<dyn SettingsRepository>;
+
+pub trait SettingsRepository {
+    /// The value of a setting that has been saved, if there is one
+    fn get_setting(&self, name: &str) -> anyhow::Result<Option<String>>;
+    /// Save a setting, replacing its value if it has already been saved
+    fn set_setting(&self, name: &str, value: &str) -> anyhow::Result<()>;
+}
+
+impl SettingsRepository for Database {
+    fn get_setting(&self, name: &str) -> anyhow::Result<Option<String>> {
+        Ok(settings::table
+            .filter(settings::name.eq(name))
+            .load::<Setting>(&mut self.get()?)
+            .context("Unable to load setting")?
+            .first()
+            .map(|setting| setting.value.clone()))
+    }
+
+    fn set_setting(&self, name: &str, value: &str) -> anyhow::Result<()> {
+        let mut conn = self.get()?;
+
+        let updated = diesel::update(settings::table)
+            .filter(settings::name.eq(name))
+            .set(settings::value.eq(value))
+            .execute(&mut conn)
+            .context("Unable to update setting")?;
+        if updated == 0 {
+            NewSetting {
+                name: name.to_string(),
+                value: value.to_string(),
+            }
+            .insert_into(settings::table)
+            .execute(&mut conn)
+            .context("Unable to insert setting")?;
+        }
+        Ok(())
+    }
+}
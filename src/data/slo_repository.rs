@@ -0,0 +1,49 @@
+use crate::db::Database;
+use crate::diesel::Insertable;
+use crate::models::{NewSlo, Slo};
+use crate::schema::slos;
+use actix_web::web::Data;
+use anyhow::Context;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+pub type SloRepositoryBox = Box<dyn SloRepository>;
+pub type SloRepositoryData = Data<SloRepositoryBox>;
+
+pub trait SloRepository {
+    fn get_all_slos(&self) -> anyhow::Result<Vec<Slo>>;
+    fn create_slo(&self, slo: NewSlo) -> anyhow::Result<()>;
+    fn delete_slo(&self, id: i32) -> anyhow::Result<()>;
+    fn set_burning(&self, id: i32, burning: bool) -> anyhow::Result<()>;
+}
+
+impl SloRepository for Database {
+    fn get_all_slos(&self) -> anyhow::Result<Vec<Slo>> {
+        slos::table
+            .order(slos::project.asc())
+            .load::<Slo>(&mut self.get()?)
+            .context("Unable to load SLOs")
+    }
+
+    fn create_slo(&self, slo: NewSlo) -> anyhow::Result<()> {
+        slo.insert_into(slos::table)
+            .execute(&mut self.get()?)
+            .context("Unable to insert SLO")?;
+        Ok(())
+    }
+
+    fn delete_slo(&self, id: i32) -> anyhow::Result<()> {
+        diesel::delete(slos::table.filter(slos::id.eq(id)))
+            .execute(&mut self.get()?)
+            .context("Unable to delete SLO")?;
+        Ok(())
+    }
+
+    fn set_burning(&self, id: i32, burning: bool) -> anyhow::Result<()> {
+        diesel::update(slos::table)
+            .filter(slos::id.eq(id))
+            .set(slos::burning.eq(burning))
+            .execute(&mut self.get()?)
+            .context("Unable to update SLO")?;
+        Ok(())
+    }
+}
@@ -1,38 +1,248 @@
 use crate::db::Database;
 use crate::diesel::RunQueryDsl;
-use crate::models::Status;
+use crate::models::{DailyStatusCounts, NewStatus, Status};
+use crate::{quorum, settings};
 use actix_web::web::Data;
-use diesel::dsl::sql;
-use diesel::sql_types::Bool;
+use anyhow::Context;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use diesel::dsl::{count_star, exists, not, sql, sum};
+use diesel::sql_types::{Bool, Date, Integer};
 use diesel::{ExpressionMethods, QueryDsl};
+use std::collections::{BTreeMap, HashSet};
 
 pub type StatusRepositoryData = Data<Box<dyn StatusRepository>>;
 
 pub trait StatusRepository {
-    fn get_status_last_30_days(&self) -> Vec<Status>;
+    /// Store samples, in a single insert
+    fn add_statuses(&self, statuses: &[NewStatus]) -> anyhow::Result<()>;
 
-    fn get_status_last_90_days(&self) -> Vec<Status>;
+    /// How many samples of each of the projects were taken on each day between two times,
+    /// counted by the database from every location in one query, newest first
+    fn get_daily_status_counts(
+        &self,
+        projects: &[i32],
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<DailyStatusCounts>>;
+
+    /// When the oldest sample of a project that hasn't been pruned was recorded
+    fn get_oldest_status_time(&self, project: i32) -> anyhow::Result<Option<NaiveDateTime>>;
+
+    /// Remove every sample recorded before the given time, returning how many were removed
+    fn delete_status_before(&self, before: NaiveDateTime) -> anyhow::Result<usize>;
+
+    /// Samples from every location for a project since the given time, newest first
+    fn get_recent_status_for_project(
+        &self,
+        project: i32,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Status>>;
+
+    /// The timeline of a project since the given time, combined from every location, newest first
+    fn get_project_timeline(
+        &self,
+        project: i32,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Status>>;
+
+    /// The timeline of each of the projects since the given time, loaded in one query
+    fn get_project_timelines(
+        &self,
+        projects: &[i32],
+        since: NaiveDateTime,
+    ) -> anyhow::Result<BTreeMap<i32, Vec<Status>>>;
+
+    /// The timeline of a project between two times, combined from every location, newest first
+    fn get_project_timeline_between(
+        &self,
+        project: i32,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Status>>;
+
+    /// The most recent sample of each project from each location that has reported recently,
+    /// picked by the database
+    fn get_latest_status_by_location(&self) -> anyhow::Result<Vec<Status>>;
+
+    /// Response times in milliseconds of the most recent samples of a project from a location
+    fn get_recent_latencies(
+        &self,
+        project: i32,
+        location: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<i32>>;
+}
+
+/// Combine the samples from each location into the timeline of each project
+pub(crate) fn project_timeline(status_list: Vec<Status>) -> Vec<Status> {
+    quorum::consensus(
+        status_list,
+        settings::check_quorum(),
+        settings::location_stale_after(),
+    )
+}
+
+/// Split samples from every project, newest first, into the timeline of each project
+pub(crate) fn project_timelines(status_list: Vec<Status>) -> BTreeMap<i32, Vec<Status>> {
+    let mut by_project = BTreeMap::<i32, Vec<Status>>::new();
+    for status in status_list {
+        by_project.entry(status.project).or_default().push(status);
+    }
+    by_project
+        .into_iter()
+        .map(|(project, status_list)| (project, project_timeline(status_list)))
+        .collect()
 }
 
 impl StatusRepository for Database {
-    //TODO: history size?
-    fn get_status_last_30_days(&self) -> Vec<Status> {
-        let status_list: Vec<_> = crate::schema::status::dsl::status
-            .filter(sql::<Bool>("created > DATE_SUB(NOW(), INTERVAL 30 day)"))
+    fn add_statuses(&self, statuses: &[NewStatus]) -> anyhow::Result<()> {
+        diesel::insert_into(crate::schema::status::table)
+            .values(statuses)
+            .execute(&mut self.get()?)
+            .context("Unable to insert status")?;
+        Ok(())
+    }
+
+    fn get_daily_status_counts(
+        &self,
+        projects: &[i32],
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<DailyStatusCounts>> {
+        // Diesel can't group by a function of a column, so the day is selected in plain SQL
+        crate::schema::status::dsl::status
+            .filter(crate::schema::status::dsl::project.eq_any(projects))
+            .filter(crate::schema::status::dsl::created.ge(from))
+            .filter(crate::schema::status::dsl::created.lt(to))
+            .group_by(sql::<(Integer, Date, Bool)>(
+                "project, DATE(created), success",
+            ))
+            .select((
+                sql::<(Integer, Date, Bool)>("project, DATE(created), success"),
+                count_star(),
+                sum(crate::schema::status::dsl::time),
+            ))
+            .load::<((i32, NaiveDate, bool), i64, Option<i64>)>(&mut self.get()?)
+            .map(|grouped| {
+                DailyStatusCounts::from_grouped(
+                    grouped
+                        .into_iter()
+                        .map(|((project, day, success), samples, total_ms)| {
+                            (project, day, success, samples, total_ms)
+                        })
+                        .collect(),
+                )
+            })
+            .context("Unable to load daily status counts")
+    }
+
+    fn get_oldest_status_time(&self, project: i32) -> anyhow::Result<Option<NaiveDateTime>> {
+        crate::schema::status::dsl::status
+            .filter(crate::schema::status::dsl::project.eq(project))
+            .select(diesel::dsl::min(crate::schema::status::dsl::created))
+            .first::<Option<NaiveDateTime>>(&mut self.get()?)
+            .context("Unable to load oldest status")
+    }
+
+    fn delete_status_before(&self, before: NaiveDateTime) -> anyhow::Result<usize> {
+        diesel::delete(
+            crate::schema::status::dsl::status
+                .filter(crate::schema::status::dsl::created.lt(before)),
+        )
+        .execute(&mut self.get()?)
+        .context("Unable to delete old status")
+    }
+
+    fn get_recent_status_for_project(
+        &self,
+        project: i32,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Status>> {
+        crate::schema::status::dsl::status
+            .filter(crate::schema::status::dsl::project.eq(project))
+            .filter(crate::schema::status::dsl::created.gt(since))
             .order(crate::schema::status::dsl::created.desc())
-            .load::<Status>(&mut self.get().unwrap())
-            .expect("Unable to load status");
+            .load::<Status>(&mut self.get()?)
+            .context("Unable to load recent status")
+    }
 
-        status_list
+    fn get_project_timeline(
+        &self,
+        project: i32,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Status>> {
+        self.get_recent_status_for_project(project, since)
+            .map(project_timeline)
     }
 
-    fn get_status_last_90_days(&self) -> Vec<Status> {
-        let status_list: Vec<_> = crate::schema::status::dsl::status
-            .filter(sql::<Bool>("created > DATE_SUB(NOW(), INTERVAL 90 day)"))
+    fn get_project_timelines(
+        &self,
+        projects: &[i32],
+        since: NaiveDateTime,
+    ) -> anyhow::Result<BTreeMap<i32, Vec<Status>>> {
+        crate::schema::status::dsl::status
+            .filter(crate::schema::status::dsl::project.eq_any(projects))
+            .filter(crate::schema::status::dsl::created.gt(since))
             .order(crate::schema::status::dsl::created.desc())
-            .load::<Status>(&mut self.get().unwrap())
-            .expect("Unable to load status");
+            .load::<Status>(&mut self.get()?)
+            .map(project_timelines)
+            .context("Unable to load project timelines")
+    }
 
-        status_list
+    fn get_project_timeline_between(
+        &self,
+        project: i32,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<Status>> {
+        crate::schema::status::dsl::status
+            .filter(crate::schema::status::dsl::project.eq(project))
+            .filter(crate::schema::status::dsl::created.ge(from))
+            .filter(crate::schema::status::dsl::created.lt(to))
+            .order(crate::schema::status::dsl::created.desc())
+            .load::<Status>(&mut self.get()?)
+            .map(project_timeline)
+            .context("Unable to load project timeline")
+    }
+
+    fn get_latest_status_by_location(&self) -> anyhow::Result<Vec<Status>> {
+        use crate::schema::status::dsl::{created, location, project, status};
+
+        let since = Utc::now().naive_utc() - settings::location_stale_after();
+        let newer = diesel::alias!(crate::schema::status as newer);
+        let status_list = status
+            .filter(created.gt(since))
+            .filter(not(exists(
+                newer
+                    .filter(newer.field(project).eq(project))
+                    .filter(newer.field(location).eq(location))
+                    .filter(newer.field(created).gt(created)),
+            )))
+            .order(created.desc())
+            .load::<Status>(&mut self.get()?)
+            .context("Unable to load latest status")?;
+
+        // Samples from a location recorded at the same time would both be the latest
+        let mut seen = HashSet::new();
+        Ok(status_list
+            .into_iter()
+            .filter(|s| seen.insert((s.project, s.location.clone())))
+            .collect())
+    }
+
+    fn get_recent_latencies(
+        &self,
+        project: i32,
+        location: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<i32>> {
+        crate::schema::status::dsl::status
+            .select(crate::schema::status::dsl::time)
+            .filter(crate::schema::status::dsl::project.eq(project))
+            .filter(crate::schema::status::dsl::location.eq(location))
+            .order(crate::schema::status::dsl::created.desc())
+            .limit(limit)
+            .load::<i32>(&mut self.get()?)
+            .context("Unable to load recent latencies")
     }
 }
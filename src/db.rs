@@ -1,16 +1,59 @@
 use anyhow::Context;
+use diesel::Connection;
 use diesel::r2d2::ConnectionManager;
 use diesel::r2d2::Pool;
-use diesel::{Connection, MysqlConnection};
 use diesel_migrations::{EmbeddedMigrations, MigrationHarness};
 use std::env;
 use std::time::Duration;
 use tracing::{error, warn};
 
-pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
+#[cfg(not(any(feature = "mysql", feature = "postgres", feature = "sqlite")))]
+compile_error!("One of the mysql, postgres or sqlite features must be enabled");
 
-pub type Database = Pool<ConnectionManager<MysqlConnection>>;
+#[cfg(any(
+    all(feature = "mysql", feature = "postgres"),
+    all(feature = "mysql", feature = "sqlite"),
+    all(feature = "postgres", feature = "sqlite")
+))]
+compile_error!(
+    "Only one of the mysql, postgres or sqlite features can be enabled, use --no-default-features to turn off mysql"
+);
 
+#[cfg(feature = "mysql")]
+pub type DbConnection = diesel::MysqlConnection;
+#[cfg(feature = "postgres")]
+pub type DbConnection = diesel::PgConnection;
+#[cfg(feature = "sqlite")]
+pub type DbConnection = diesel::SqliteConnection;
+
+#[cfg(feature = "mysql")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/mysql");
+#[cfg(feature = "postgres")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/postgres");
+#[cfg(feature = "sqlite")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/sqlite");
+
+pub type Database = Pool<ConnectionManager<DbConnection>>;
+
+/// SQLite only allows one writer at a time, wait for the other connections in the pool rather than
+/// failing straight away
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+struct SqliteOptions;
+
+#[cfg(feature = "sqlite")]
+impl diesel::r2d2::CustomizeConnection<DbConnection, diesel::r2d2::Error> for SqliteOptions {
+    fn on_acquire(&self, conn: &mut DbConnection) -> Result<(), diesel::r2d2::Error> {
+        use diesel::connection::SimpleConnection;
+
+        // Switching to WAL needs a lock too, so the timeout has to be set first
+        conn.batch_execute("PRAGMA busy_timeout = 5000; PRAGMA journal_mode = WAL;")
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// Connect to the database and run any pending migrations. This is done once at startup, the
+/// pool is shared by the web server and every job
 #[tracing::instrument]
 pub fn get_db_connection() -> anyhow::Result<Database> {
     let span = tracing::info_span!("Connecting to the database");
@@ -20,7 +63,7 @@ pub fn get_db_connection() -> anyhow::Result<Database> {
 
     let mut conn;
     loop {
-        match MysqlConnection::establish(&database_url) {
+        match DbConnection::establish(&database_url) {
             Ok(x) => {
                 conn = x;
                 break;
@@ -36,24 +79,11 @@ pub fn get_db_connection() -> anyhow::Result<Database> {
     conn.run_pending_migrations(MIGRATIONS)
         .expect("Migrate fail");
 
-    let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+    let manager = ConnectionManager::<DbConnection>::new(database_url);
 
-    Pool::builder()
-        .max_size(4)
-        .test_on_check_out(true)
-        .build(manager)
-        .context("Cant create db pool")
-}
+    let builder = Pool::builder().max_size(10).test_on_check_out(true);
+    #[cfg(feature = "sqlite")]
+    let builder = builder.connection_customizer(Box::new(SqliteOptions));
 
-#[macro_export]
-macro_rules! get_db {
-    () => {{
-        match $crate::db::get_db_connection() {
-            Ok(db) => db,
-            Err(e) => {
-                tracing::error!("Failed to get database, can't run update job: {e:?}");
-                return HttpResponse::InternalServerError().finish();
-            }
-        }
-    }};
+    builder.build(manager).context("Cant create db pool")
 }
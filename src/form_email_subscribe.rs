@@ -1,12 +1,10 @@
-use crate::get_db;
-use crate::models::{EmailSubscription, NewEmailSubscription};
+use crate::data::email_subscription_repository::EmailSubscriberRepositoryData;
 use crate::notifications::mailer::Mailer;
 use actix_web::get;
 use actix_web::post;
 use actix_web::web::{Data, Query};
 use actix_web::{HttpResponse, web::Form};
 use askama::Template;
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
 use lettre::Message;
 use lettre::message::Mailbox;
 use serde::Deserialize;
@@ -28,34 +26,27 @@ pub struct EmailSubscribeRequest {
 #[post("/subscribe/email")]
 pub async fn post_email_subscribe(
     mailer: Data<Arc<Mailer>>,
+    email_subscription_repo: EmailSubscriberRepositoryData,
     form: Form<EmailSubscribeRequest>,
 ) -> HttpResponse {
-    use crate::schema::email_subscriptions;
-
     let request_id = Uuid::new_v4();
     let span = tracing::info_span!("Email subscribe", request_id = %request_id);
     let _span_guard = span.enter();
 
-    let db = get_db!();
-
     let parsed_email = form.email.clone().parse::<Mailbox>();
 
     if let Ok(email) = parsed_email {
-        diesel::insert_into(email_subscriptions::table)
-            .values(NewEmailSubscription {
-                email: form.email.clone(),
-            })
-            .execute(&mut db.get().unwrap())
-            .unwrap();
-
-        let entry = email_subscriptions::table
-            .filter(email_subscriptions::dsl::email.eq(form.email.clone()))
-            .load::<EmailSubscription>(&mut db.get().unwrap())
-            .unwrap();
+        let entry = match email_subscription_repo.subscribe(&form.email) {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::warn!("Failed to add email subscription: {e:?}");
+                return HttpResponse::InternalServerError().finish();
+            }
+        };
 
         let message_body = EmailSubscriptionTemplate {
             site_url: "localhost:8102".to_string(),
-            subscription_id: format!("{}", entry.first().unwrap().id),
+            subscription_id: format!("{}", entry.id),
         }
         .render()
         .unwrap();
@@ -83,19 +74,18 @@ pub struct ConfirmSubscription {
 }
 
 #[get("/subscribe/email/confirm")]
-pub async fn get_email_confirm(form: Query<ConfirmSubscription>) -> HttpResponse {
-    use crate::schema::email_subscriptions;
-
+pub async fn get_email_confirm(
+    form: Query<ConfirmSubscription>,
+    email_subscription_repo: EmailSubscriberRepositoryData,
+) -> HttpResponse {
     //TODO: add a tracking id (uuid?)
     let span = tracing::info_span!("Email subscribe confirm, id = {}", form.id);
     let _span_guard = span.enter();
 
-    let db = get_db!();
-
-    diesel::update(email_subscriptions::table.filter(email_subscriptions::dsl::id.eq(form.id)))
-        .set(email_subscriptions::dsl::confirmed.eq(true))
-        .execute(&mut db.get().unwrap())
-        .unwrap();
+    if let Err(e) = email_subscription_repo.confirm(form.id) {
+        tracing::warn!("Failed to confirm subscription: {e:?}");
+        return HttpResponse::InternalServerError().finish();
+    }
 
     tracing::info!("Confirmed subscription id={}", form.id);
 
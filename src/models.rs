@@ -1,14 +1,24 @@
 use super::schema::email_subscriptions;
+use super::schema::heartbeats;
 use super::schema::incident_status_type;
 use super::schema::incident_status_update;
 use super::schema::incidents;
+use super::schema::outages;
+use super::schema::project_alerts;
+use super::schema::project_certificates;
 use super::schema::projects;
 use super::schema::settings;
+use super::schema::slos;
 use super::schema::sms_subscriptions;
 use super::schema::status;
+use super::schema::status_rollups;
 use super::schema::webhook_subscriptions;
+use crate::alerting::{AlertPolicy, AlertState};
+use crate::checks::StepTiming;
+use crate::checks::latency::LatencyThresholds;
 use chrono::{SecondsFormat, TimeZone, Utc};
-use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Queryable, Clone)]
 pub struct Project {
@@ -18,12 +28,74 @@ pub struct Project {
     pub description: Option<String>,
     pub created: chrono::NaiveDateTime,
     pub enabled: bool,
+    pub check_type: String,
+    pub check_config: Option<String>,
+    /// Seconds between each check of this project
+    pub check_interval: i32,
+    pub latency_warn_ms: Option<i32>,
+    pub latency_critical_ms: Option<i32>,
+    pub latency_use_p95: bool,
+    /// Tell subscribers when the project becomes degraded, and when it recovers
+    pub notify_degraded: bool,
+    pub down_after_failures: i32,
+    pub up_after_successes: i32,
+    pub flap_threshold: Option<i32>,
+    pub flap_window_minutes: i32,
+    /// Uptime percentage expected over 30 days
+    pub sla_target: Option<f64>,
 }
 
 impl Project {
     pub fn formatted_description(&self) -> String {
         self.description.clone().unwrap_or_default()
     }
+
+    pub fn formatted_check_config(&self) -> String {
+        self.check_config.clone().unwrap_or_default()
+    }
+
+    pub fn uses_check_type(&self, check_type: &str) -> bool {
+        self.check_type == check_type
+    }
+
+    pub fn latency_thresholds(&self) -> LatencyThresholds {
+        LatencyThresholds {
+            warn_ms: self.latency_warn_ms,
+            critical_ms: self.latency_critical_ms,
+            use_p95: self.latency_use_p95,
+        }
+    }
+
+    pub fn formatted_latency_warn_ms(&self) -> String {
+        self.latency_warn_ms
+            .map(|l| l.to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn formatted_latency_critical_ms(&self) -> String {
+        self.latency_critical_ms
+            .map(|l| l.to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn alert_policy(&self) -> AlertPolicy {
+        AlertPolicy {
+            down_after: self.down_after_failures,
+            up_after: self.up_after_successes,
+            flap_threshold: self.flap_threshold,
+            flap_window_minutes: self.flap_window_minutes,
+        }
+    }
+
+    pub fn formatted_flap_threshold(&self) -> String {
+        self.flap_threshold
+            .map(|t| t.to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn formatted_sla_target(&self) -> String {
+        self.sla_target.map(|t| t.to_string()).unwrap_or_default()
+    }
 }
 
 #[derive(Insertable)]
@@ -35,20 +107,54 @@ pub struct NewProject {
     pub enabled: bool,
 }
 
+/// How a project is checked and alerted on, saved together from the admin dashboard
+#[derive(AsChangeset, Clone, Debug, PartialEq)]
+#[diesel(table_name = projects, treat_none_as_null = true)]
+pub struct ProjectSettings {
+    pub check_type: String,
+    pub check_config: Option<String>,
+    pub check_interval: i32,
+    pub latency_warn_ms: Option<i32>,
+    pub latency_critical_ms: Option<i32>,
+    pub latency_use_p95: bool,
+    pub notify_degraded: bool,
+    pub down_after_failures: i32,
+    pub up_after_successes: i32,
+    pub flap_threshold: Option<i32>,
+    pub flap_window_minutes: i32,
+    pub sla_target: Option<f64>,
+}
+
 #[derive(Queryable, Clone, Debug)]
 pub struct Status {
     pub id: i32,
     pub project: i32,
     pub time: i32,
-    pub status_code: i32,
+    pub status_code: Option<i32>,
     pub created: chrono::NaiveDateTime,
+    pub success: bool,
+    pub message: Option<String>,
+    pub degraded: bool,
+    /// JSON list of [StepTiming] for multi step checks
+    pub steps: Option<String>,
+    /// Where the probe that recorded this sample ran
+    pub location: String,
 }
 
 impl Status {
     pub(crate) fn is_success(&self) -> bool {
-        StatusCode::from_u16(self.status_code as u16)
-            .map(|s| s.is_success())
-            .unwrap_or(false)
+        self.success
+    }
+
+    pub(crate) fn is_degraded(&self) -> bool {
+        self.success && self.degraded
+    }
+
+    pub(crate) fn step_timings(&self) -> Vec<StepTiming> {
+        self.steps
+            .as_deref()
+            .and_then(|steps| serde_json::from_str(steps).ok())
+            .unwrap_or_default()
     }
 
     pub(crate) fn formatted_creation_time(&self) -> String {
@@ -57,15 +163,22 @@ impl Status {
     }
 }
 
-#[derive(Insertable, Clone, Debug)]
+#[derive(Insertable, Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[diesel(table_name = status)]
 pub struct NewStatus {
     pub project: i32,
     pub time: i32,
-    pub status_code: i32,
+    pub status_code: Option<i32>,
+    /// When the check ran, samples can be stored well after this if the database was unavailable
+    pub created: chrono::NaiveDateTime,
+    pub success: bool,
+    pub message: Option<String>,
+    pub degraded: bool,
+    pub steps: Option<String>,
+    pub location: String,
 }
 
-#[derive(Queryable, Identifiable, Clone)]
+#[derive(Queryable, Identifiable, Clone, Debug)]
 #[diesel(table_name = incidents)]
 pub struct Incidents {
     pub id: i32,
@@ -129,6 +242,13 @@ pub struct Setting {
     pub value: String,
 }
 
+#[derive(Insertable)]
+#[diesel(table_name = settings)]
+pub struct NewSetting {
+    pub name: String,
+    pub value: String,
+}
+
 #[derive(Identifiable, Queryable, Clone)]
 pub struct EmailSubscription {
     pub id: i32,
@@ -158,3 +278,416 @@ pub struct WebhookSubscription {
     pub url: String,
     pub enabled: bool,
 }
+
+#[derive(Identifiable, Queryable, Clone, Debug)]
+pub struct ProjectCertificate {
+    pub id: i32,
+    pub created: chrono::NaiveDateTime,
+    pub project: i32,
+    pub not_after: chrono::NaiveDateTime,
+    pub issuer: String,
+    pub sans: String,
+    pub notified_threshold: Option<i32>,
+}
+
+impl ProjectCertificate {
+    pub fn days_remaining(&self) -> i64 {
+        self.not_after
+            .signed_duration_since(Utc::now().naive_utc())
+            .num_days()
+    }
+}
+
+#[derive(Insertable, AsChangeset, Clone, Debug)]
+#[diesel(table_name = project_certificates)]
+pub struct NewProjectCertificate {
+    pub project: i32,
+    pub not_after: chrono::NaiveDateTime,
+    pub issuer: String,
+    pub sans: String,
+}
+
+#[derive(Identifiable, Queryable, Clone, Debug)]
+pub struct Heartbeat {
+    pub id: i32,
+    pub created: chrono::NaiveDateTime,
+    pub project: i32,
+    pub token: String,
+    pub last_ping: Option<chrono::NaiveDateTime>,
+}
+
+impl Heartbeat {
+    pub fn url(&self) -> String {
+        format!(
+            "{}/heartbeat/{}",
+            crate::settings::get_host_url(),
+            self.token
+        )
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = heartbeats)]
+pub struct NewHeartbeat {
+    pub project: i32,
+    pub token: String,
+}
+
+#[derive(Identifiable, Queryable, Clone, Debug)]
+pub struct ProjectAlert {
+    pub id: i32,
+    pub created: chrono::NaiveDateTime,
+    pub project: i32,
+    pub down: bool,
+    pub streak: i32,
+    pub flapping: bool,
+    /// When `down` last changed
+    pub changed: chrono::NaiveDateTime,
+}
+
+impl ProjectAlert {
+    pub fn state(&self) -> AlertState {
+        AlertState {
+            down: self.down,
+            streak: self.streak,
+            flapping: self.flapping,
+        }
+    }
+}
+
+#[derive(Insertable, AsChangeset, Clone, Debug)]
+#[diesel(table_name = project_alerts)]
+pub struct NewProjectAlert {
+    pub project: i32,
+    pub down: bool,
+    pub streak: i32,
+    pub flapping: bool,
+    pub changed: chrono::NaiveDateTime,
+}
+
+/// A period that a project was down for
+#[derive(Identifiable, Queryable, Clone, Debug)]
+pub struct Outage {
+    pub id: i32,
+    pub created: chrono::NaiveDateTime,
+    pub project: i32,
+    pub started: chrono::NaiveDateTime,
+    /// Not set while the project is still down
+    pub ended: Option<chrono::NaiveDateTime>,
+    pub cause: Option<String>,
+    pub first_status_code: Option<i32>,
+    pub last_status_code: Option<i32>,
+}
+
+impl Outage {
+    /// When the outage ended, or `now` if it is still ongoing
+    pub fn ended_or(&self, now: chrono::NaiveDateTime) -> chrono::NaiveDateTime {
+        self.ended.unwrap_or(now)
+    }
+
+    pub fn duration(&self, now: chrono::NaiveDateTime) -> chrono::Duration {
+        self.ended_or(now).signed_duration_since(self.started)
+    }
+
+    /// How much of the outage falls between `from` and `to`
+    pub fn overlap(
+        &self,
+        from: chrono::NaiveDateTime,
+        to: chrono::NaiveDateTime,
+        now: chrono::NaiveDateTime,
+    ) -> chrono::Duration {
+        let start = self.started.max(from);
+        let end = self.ended_or(now).min(to);
+        (end - start).max(chrono::Duration::zero())
+    }
+
+    pub fn formatted_started(&self) -> String {
+        Utc.from_utc_datetime(&self.started)
+            .to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+
+    /// A short description of the outage for feeds
+    pub fn summary(&self, now: chrono::NaiveDateTime) -> String {
+        let cause = self.cause.as_deref().unwrap_or("Unknown failure");
+        match self.ended {
+            Some(_) => format!(
+                "{cause}, resolved after {}",
+                Some(crate::time_formatter::format_duration(&self.duration(now)))
+                    .filter(|d| !d.is_empty())
+                    .unwrap_or_else(|| "less than a minute".to_string())
+            ),
+            None => format!("{cause}, ongoing"),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[diesel(table_name = outages)]
+pub struct NewOutage {
+    pub project: i32,
+    pub started: chrono::NaiveDateTime,
+    pub cause: Option<String>,
+    pub first_status_code: Option<i32>,
+    pub last_status_code: Option<i32>,
+}
+
+#[derive(Queryable, Clone, Debug)]
+pub struct Slo {
+    pub id: i32,
+    pub created: chrono::NaiveDateTime,
+    pub project: i32,
+    /// The percentage of good time, or checks for latency objectives, e.g. 99.9
+    pub objective: f64,
+    pub window_days: i32,
+    /// Latency objectives only count checks faster than this as good
+    pub latency_threshold_ms: Option<i32>,
+    /// Whether subscribers have been told that the error budget is burning too fast
+    pub burning: bool,
+}
+
+impl Slo {
+    pub fn window(&self) -> chrono::Duration {
+        chrono::Duration::days(i64::from(self.window_days))
+    }
+
+    pub fn describe(&self) -> String {
+        match self.latency_threshold_ms {
+            Some(threshold) => format!(
+                "{}% of checks faster than {threshold}ms over {} days",
+                self.objective, self.window_days
+            ),
+            None => format!("{}% uptime over {} days", self.objective, self.window_days),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[diesel(table_name = slos)]
+pub struct NewSlo {
+    pub project: i32,
+    pub objective: f64,
+    pub window_days: i32,
+    pub latency_threshold_ms: Option<i32>,
+}
+
+/// A summary of the samples of a project over an hour or a day
+#[derive(Queryable, Clone, Debug, PartialEq)]
+pub struct StatusRollup {
+    pub id: i32,
+    pub created: chrono::NaiveDateTime,
+    pub project: i32,
+    /// 3600 for hourly rollups, 86400 for daily ones
+    pub period_seconds: i32,
+    pub period_start: chrono::NaiveDateTime,
+    pub samples: i32,
+    pub failures: i32,
+    pub degraded: i32,
+    /// Time weighted, like [crate::uptime::uptime]
+    pub up_seconds: i32,
+    pub down_seconds: i32,
+    /// Response times of the successful samples
+    pub min_latency_ms: Option<i32>,
+    pub avg_latency_ms: Option<i32>,
+    pub p95_latency_ms: Option<i32>,
+    /// JSON object of how many samples had each status code
+    pub status_codes: String,
+    /// JSON object of how many successful samples fell in each
+    /// [latency bucket](crate::checks::latency::latency_bucket), `None` for older rollups
+    pub latency_histogram: Option<String>,
+}
+
+impl StatusRollup {
+    pub fn period_end(&self) -> chrono::NaiveDateTime {
+        self.period_start + chrono::Duration::seconds(i64::from(self.period_seconds))
+    }
+
+    /// How many successful samples fell in each latency bucket
+    pub fn histogram(&self) -> Option<BTreeMap<i32, i32>> {
+        serde_json::from_str(self.latency_histogram.as_deref()?).ok()
+    }
+}
+
+/// How many samples of a project were taken on a day that hasn't been rolled up yet, counted by
+/// the database, see [crate::data::status_repository::StatusRepository::get_daily_status_counts]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DailyStatusCounts {
+    pub project: i32,
+    pub day: chrono::NaiveDate,
+    pub samples: i64,
+    pub failures: i64,
+    /// Response times of the successful samples
+    pub avg_latency_ms: Option<i64>,
+}
+
+impl DailyStatusCounts {
+    /// Combine the number of samples and their total response time of each project on each day,
+    /// grouped by whether they were successful, newest day first
+    pub fn from_grouped(
+        grouped: Vec<(i32, chrono::NaiveDate, bool, i64, Option<i64>)>,
+    ) -> Vec<Self> {
+        let mut days = std::collections::BTreeMap::new();
+        for (project, day, success, samples, total_ms) in grouped {
+            let counts = days.entry((day, project)).or_insert(Self {
+                project,
+                day,
+                samples: 0,
+                failures: 0,
+                avg_latency_ms: None,
+            });
+            counts.samples += samples;
+            if success {
+                counts.avg_latency_ms = total_ms
+                    .filter(|_| samples > 0)
+                    .map(|total_ms| total_ms / samples);
+            } else {
+                counts.failures += samples;
+            }
+        }
+        days.into_values().rev().collect()
+    }
+}
+
+impl From<&StatusRollup> for DailyStatusCounts {
+    fn from(rollup: &StatusRollup) -> Self {
+        Self {
+            project: rollup.project,
+            day: rollup.period_start.date(),
+            samples: i64::from(rollup.samples),
+            failures: i64::from(rollup.failures),
+            avg_latency_ms: rollup.avg_latency_ms.map(i64::from),
+        }
+    }
+}
+
+/// The hourly rollups of a project added up since some time, see
+/// [crate::data::rollup_repository::RollupRepository::get_hourly_totals]
+#[derive(Queryable, Clone, Debug, PartialEq)]
+pub struct RollupTotals {
+    pub project: i32,
+    pub up_seconds: Option<i64>,
+    pub down_seconds: Option<i64>,
+    /// When the latest hourly rollup started
+    pub latest_start: Option<chrono::NaiveDateTime>,
+}
+
+impl RollupTotals {
+    pub fn up(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.up_seconds.unwrap_or(0))
+    }
+
+    pub fn down(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.down_seconds.unwrap_or(0))
+    }
+
+    /// The end of the latest hourly rollup, samples after this haven't been rolled up yet
+    pub fn rolled_up_until(&self) -> Option<chrono::NaiveDateTime> {
+        self.latest_start
+            .map(|start| start + chrono::Duration::hours(1))
+    }
+}
+
+#[derive(Insertable, Clone, Debug, PartialEq)]
+#[diesel(table_name = status_rollups)]
+pub struct NewStatusRollup {
+    pub project: i32,
+    pub period_seconds: i32,
+    pub period_start: chrono::NaiveDateTime,
+    pub samples: i32,
+    pub failures: i32,
+    pub degraded: i32,
+    pub up_seconds: i32,
+    pub down_seconds: i32,
+    pub min_latency_ms: Option<i32>,
+    pub avg_latency_ms: Option<i32>,
+    pub p95_latency_ms: Option<i32>,
+    pub status_codes: String,
+    pub latency_histogram: String,
+}
+
+/// Models with the defaults from the migrations, shared by the tests so that a new column only
+/// needs adding here
+#[cfg(test)]
+pub mod fixtures {
+    use crate::models::{Outage, Project, Status, StatusRollup};
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    pub fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    pub fn project(id: i32) -> Project {
+        Project {
+            id,
+            url: "".to_string(),
+            name: "".to_string(),
+            description: None,
+            created: at(2020, 1, 1, 0, 0),
+            enabled: true,
+            check_type: "http".to_string(),
+            check_config: None,
+            check_interval: 90,
+            latency_warn_ms: None,
+            latency_critical_ms: None,
+            latency_use_p95: false,
+            notify_degraded: false,
+            down_after_failures: 1,
+            up_after_successes: 1,
+            flap_threshold: None,
+            flap_window_minutes: 60,
+            sla_target: None,
+        }
+    }
+
+    /// A sample from the default location that took 100ms
+    pub fn status(project: i32, created: NaiveDateTime, success: bool) -> Status {
+        Status {
+            id: 0,
+            project,
+            time: 100,
+            status_code: None,
+            created,
+            success,
+            message: None,
+            degraded: false,
+            steps: None,
+            location: "local".to_string(),
+        }
+    }
+
+    pub fn outage(project: i32, started: NaiveDateTime, ended: Option<NaiveDateTime>) -> Outage {
+        Outage {
+            id: 0,
+            created: started,
+            project,
+            started,
+            ended,
+            cause: None,
+            first_status_code: Some(503),
+            last_status_code: Some(503),
+        }
+    }
+
+    /// An hourly rollup with no samples
+    pub fn rollup(project: i32, period_start: NaiveDateTime) -> StatusRollup {
+        StatusRollup {
+            id: 0,
+            created: period_start,
+            project,
+            period_seconds: 3600,
+            period_start,
+            samples: 0,
+            failures: 0,
+            degraded: 0,
+            up_seconds: 0,
+            down_seconds: 0,
+            min_latency_ms: None,
+            avg_latency_ms: None,
+            p95_latency_ms: None,
+            status_codes: "{}".to_string(),
+            latency_histogram: Some("{}".to_string()),
+        }
+    }
+}
@@ -1,8 +1,5 @@
-use crate::db::Database;
-use crate::diesel::RunQueryDsl;
-use crate::models::EmailSubscription;
+use crate::data::email_subscription_repository::EmailSubscriberRepository;
 use crate::settings;
-use diesel::{ExpressionMethods, QueryDsl};
 use lettre::message::header::ContentType;
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{Message, SmtpTransport, Transport};
@@ -41,17 +38,18 @@ impl Mailer {
 
     pub fn send_to_subscribers(
         &self,
-        db: &Database,
+        email_subscription_repo: &EmailSubscriberRepository,
         from: &str,
         title: String,
         message_body: String,
     ) {
-        use crate::schema::email_subscriptions;
-
-        let subscribed_users = email_subscriptions::table
-            .filter(email_subscriptions::dsl::confirmed.eq(true))
-            .load::<EmailSubscription>(&mut db.get().unwrap())
-            .unwrap();
+        let subscribed_users = match email_subscription_repo.get_all_confirmed_subscribers() {
+            Ok(subscribed_users) => subscribed_users,
+            Err(e) => {
+                warn!("Unable to load email subscribers: {e:?}");
+                return;
+            }
+        };
 
         for user in &subscribed_users {
             let email = Message::builder()
@@ -65,4 +63,24 @@ impl Mailer {
             self.send_message(email);
         }
     }
+
+    /// Send an HTML email to a fixed list of addresses, invalid addresses are skipped
+    pub fn send_to(&self, recipients: &[String], from: &str, title: &str, message_body: &str) {
+        for recipient in recipients {
+            let Ok(to) = recipient.parse() else {
+                warn!("Not sending email to invalid address {recipient:?}");
+                continue;
+            };
+
+            let email = Message::builder()
+                .to(to)
+                .from(from.parse().unwrap())
+                .header(ContentType::TEXT_HTML)
+                .subject(title)
+                .body(message_body.to_string())
+                .unwrap();
+
+            self.send_message(email);
+        }
+    }
 }
@@ -1,3 +1,49 @@
+use crate::data::email_subscription_repository::EmailSubscriberRepository;
+use crate::data::sms_subscription_repository::SmsSubscriberRepository;
+use crate::data::webhook_subscription_repository::WebhookSubscriberRepository;
+use crate::notifications::mailer::Mailer;
+use crate::notifications::sms::SMSNotifier;
+use crate::notifications::webhook::{WebhookNotifier, WebhookPayload};
+use std::sync::Arc;
+
 pub mod mailer;
 pub mod sms;
 pub mod webhook;
+
+/// A single alert, formatted for each of the notification channels
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub title: String,
+    pub email_body: String,
+    pub sms_body: String,
+    pub payload: WebhookPayload,
+}
+
+/// All of the notification channels, along with their subscribers
+pub struct Notifiers {
+    pub mailer: Arc<Mailer>,
+    pub sms: Arc<SMSNotifier>,
+    pub webhook: Arc<WebhookNotifier>,
+    pub email_subscription_repo: EmailSubscriberRepository,
+    pub sms_subscription_repo: SmsSubscriberRepository,
+    pub webhook_subscription_repo: WebhookSubscriberRepository,
+}
+
+impl Notifiers {
+    pub async fn notify_all_subscribers(&self, notification: Notification) {
+        self.mailer.send_to_subscribers(
+            &self.email_subscription_repo,
+            "YouUp <alerts@you-up.net>",
+            notification.title,
+            notification.email_body,
+        );
+
+        self.sms
+            .notify_all_subscribers(&self.sms_subscription_repo, &notification.sms_body)
+            .await;
+
+        self.webhook
+            .notify_all_subscribers(&self.webhook_subscription_repo, notification.payload)
+            .await;
+    }
+}
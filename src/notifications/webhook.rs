@@ -1,6 +1,7 @@
 use crate::data::webhook_subscription_repository::WebhookSubscriberRepository;
 use reqwest::Client;
-use serde::Serialize;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
 
 #[derive(Debug)]
 pub struct WebhookNotifier {
@@ -32,10 +33,69 @@ impl WebhookNotifier {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone)]
 pub struct WebhookPayload {
     pub project_id: i32,
     pub project_name: String,
-    pub status_code: u16,
+    /// The status code of the response, `None` when no response was received
+    pub status_code: Option<u16>,
+    pub message: Option<String>,
     pub time: String,
+    /// When the outage that just ended started, only set for recovery notifications
+    pub outage_started: Option<String>,
+    /// How long the outage that just ended lasted
+    pub outage_seconds: Option<i64>,
+}
+
+/// Webhooks have always been sent a numeric `status_code`, so a missing status code is sent as
+/// `0` with `no_response` set rather than as `null`
+impl Serialize for WebhookPayload {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut payload = serializer.serialize_struct("WebhookPayload", 8)?;
+        payload.serialize_field("project_id", &self.project_id)?;
+        payload.serialize_field("project_name", &self.project_name)?;
+        payload.serialize_field("status_code", &self.status_code.unwrap_or(0))?;
+        payload.serialize_field("no_response", &self.status_code.is_none())?;
+        payload.serialize_field("message", &self.message)?;
+        payload.serialize_field("time", &self.time)?;
+        match &self.outage_started {
+            Some(outage_started) => payload.serialize_field("outage_started", outage_started)?,
+            None => payload.skip_field("outage_started")?,
+        }
+        match &self.outage_seconds {
+            Some(outage_seconds) => payload.serialize_field("outage_seconds", outage_seconds)?,
+            None => payload.skip_field("outage_seconds")?,
+        }
+        payload.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::notifications::webhook::WebhookPayload;
+    use serde_json::json;
+
+    fn payload(status_code: Option<u16>) -> WebhookPayload {
+        WebhookPayload {
+            project_id: 1,
+            project_name: "Test".to_string(),
+            status_code,
+            message: None,
+            time: "2024-01-01 00:00:00".to_string(),
+            outage_started: None,
+            outage_seconds: None,
+        }
+    }
+
+    #[test]
+    fn status_code_is_always_a_number() {
+        let with_response = serde_json::to_value(payload(Some(503))).unwrap();
+        assert_eq!(with_response["status_code"], json!(503));
+        assert_eq!(with_response["no_response"], json!(false));
+        assert!(with_response.get("outage_started").is_none());
+
+        let without_response = serde_json::to_value(payload(None)).unwrap();
+        assert_eq!(without_response["status_code"], json!(0));
+        assert_eq!(without_response["no_response"], json!(true));
+    }
 }
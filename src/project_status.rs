@@ -4,8 +4,11 @@ use std::fmt::{Display, Formatter};
 #[derive(Eq, PartialEq)]
 pub enum ProjectStatusTypes {
     Operational,
+    Degraded,
     Recovering,
     Failing,
+    /// Changing between up and down too often to be sure of either
+    Flapping,
     Failed,
     Unknown,
 }
@@ -14,8 +17,10 @@ impl ProjectStatusTypes {
     pub fn get_colour(&self) -> String {
         match self {
             ProjectStatusTypes::Operational => "#00FF00".into(),
+            ProjectStatusTypes::Degraded => "#AADD00".into(),
             ProjectStatusTypes::Recovering => "#FFFF00".into(),
             ProjectStatusTypes::Failing => "#FFAA00".into(),
+            ProjectStatusTypes::Flapping => "#CC66FF".into(),
             ProjectStatusTypes::Failed => "#FF0000".into(),
             ProjectStatusTypes::Unknown => "#808080".into(),
         }
@@ -26,8 +31,10 @@ impl Display for ProjectStatusTypes {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.write_str(match self {
             ProjectStatusTypes::Operational => "Operational",
+            ProjectStatusTypes::Degraded => "Degraded",
             ProjectStatusTypes::Recovering => "Recovering",
             ProjectStatusTypes::Failing => "Failing",
+            ProjectStatusTypes::Flapping => "Flapping",
             ProjectStatusTypes::Failed => "Failed",
             ProjectStatusTypes::Unknown => "Unknown",
         })
@@ -0,0 +1,209 @@
+use crate::models::Status;
+use chrono::Duration;
+use std::collections::{BTreeMap, HashMap};
+
+/// Decide if enough locations agree that a project is down.
+/// Without an explicit quorum a majority of locations must be failing, the quorum can never be
+/// more than the number of locations, so a single location can still report an outage on its own
+pub fn is_down(failures: usize, locations: usize, quorum: Option<usize>) -> bool {
+    if locations == 0 {
+        return false;
+    }
+
+    let required = quorum.unwrap_or(locations / 2 + 1).clamp(1, locations);
+    failures >= required
+}
+
+/// The latest sample from each location, given samples ordered newest first
+pub fn latest_by_location(statuses: &[Status]) -> BTreeMap<&str, &Status> {
+    let mut latest = BTreeMap::new();
+    for status in statuses {
+        latest.entry(status.location.as_str()).or_insert(status);
+    }
+    latest
+}
+
+/// The state of a single location, as seen while walking through a project's history
+struct LocationResult {
+    created: chrono::NaiveDateTime,
+    success: bool,
+    message: Option<String>,
+}
+
+/// Turn per location samples, ordered newest first, into a project timeline.
+/// Each sample is replaced by the quorum verdict of the latest results from every location at
+/// that point in time, so a failure seen by a single location doesn't count as downtime
+pub fn consensus(
+    mut statuses: Vec<Status>,
+    quorum: Option<usize>,
+    stale_after: Duration,
+) -> Vec<Status> {
+    let mut latest: HashMap<i32, BTreeMap<String, LocationResult>> = HashMap::new();
+
+    for status in statuses.iter_mut().rev() {
+        let locations = latest.entry(status.project).or_default();
+        locations.insert(
+            status.location.clone(),
+            LocationResult {
+                created: status.created,
+                success: status.success,
+                message: status.message.clone(),
+            },
+        );
+
+        let fresh = locations
+            .iter()
+            .filter(|(_, r)| status.created.signed_duration_since(r.created) <= stale_after)
+            .collect::<Vec<_>>();
+        let failing = fresh.iter().filter(|(_, r)| !r.success).collect::<Vec<_>>();
+        let down = is_down(failing.len(), fresh.len(), quorum);
+
+        if down == status.success {
+            status.success = !down;
+            status.degraded = false;
+            status.message = if down {
+                failing.iter().find_map(|(location, r)| {
+                    r.message
+                        .as_ref()
+                        .map(|message| format!("{location}: {message}"))
+                })
+            } else {
+                None
+            };
+        }
+    }
+
+    statuses
+}
+
+/// When the current run of failures in a timeline started, given samples ordered newest first.
+/// None if the latest sample was successful
+pub fn failing_since(timeline: &[Status]) -> Option<chrono::NaiveDateTime> {
+    timeline
+        .iter()
+        .take_while(|s| !s.success)
+        .last()
+        .map(|s| s.created)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::Status;
+    use crate::quorum::{consensus, failing_since, is_down, latest_by_location};
+    use chrono::{Duration, Utc};
+
+    fn status(location: &str, minutes_ago: i64, success: bool) -> Status {
+        Status {
+            id: 0,
+            project: 1,
+            time: 10,
+            status_code: None,
+            created: Utc::now().naive_utc() - Duration::minutes(minutes_ago),
+            success,
+            message: (!success).then(|| "Request failed".to_string()),
+            degraded: false,
+            steps: None,
+            location: location.to_string(),
+        }
+    }
+
+    #[test]
+    fn majority_must_agree_by_default() {
+        assert!(!is_down(0, 0, None));
+        assert!(is_down(1, 1, None));
+        assert!(!is_down(1, 2, None));
+        assert!(is_down(2, 2, None));
+        assert!(!is_down(1, 3, None));
+        assert!(is_down(2, 3, None));
+    }
+
+    #[test]
+    fn quorum_is_clamped_to_locations() {
+        assert!(is_down(1, 3, Some(1)));
+        assert!(!is_down(2, 3, Some(3)));
+        // Only one location is reporting, so it has to be trusted
+        assert!(is_down(1, 1, Some(2)));
+        assert!(is_down(1, 2, Some(0)));
+    }
+
+    #[test]
+    fn latest_sample_per_location() {
+        let statuses = vec![
+            status("london", 1, false),
+            status("paris", 2, true),
+            status("london", 3, true),
+        ];
+
+        let latest = latest_by_location(&statuses);
+        assert_eq!(latest.len(), 2);
+        assert!(!latest["london"].success);
+        assert!(latest["paris"].success);
+    }
+
+    #[test]
+    fn single_location_failure_is_not_downtime() {
+        let timeline = consensus(
+            vec![
+                status("london", 1, true),
+                status("paris", 2, false),
+                status("london", 3, true),
+                status("paris", 4, true),
+            ],
+            None,
+            Duration::minutes(10),
+        );
+
+        assert!(timeline.iter().all(|s| s.success));
+        assert!(timeline.iter().all(|s| s.message.is_none()));
+    }
+
+    #[test]
+    fn agreeing_locations_are_downtime() {
+        let timeline = consensus(
+            vec![
+                status("london", 1, true),
+                status("paris", 2, false),
+                status("london", 3, false),
+                status("paris", 4, true),
+            ],
+            None,
+            Duration::minutes(10),
+        );
+
+        let success = timeline.iter().map(|s| s.success).collect::<Vec<_>>();
+        assert_eq!(success, vec![true, false, true, true]);
+        assert!(
+            timeline[1]
+                .message
+                .as_ref()
+                .unwrap()
+                .contains("Request failed")
+        );
+    }
+
+    #[test]
+    fn stale_locations_are_ignored() {
+        let timeline = consensus(
+            vec![status("london", 1, false), status("paris", 60, true)],
+            None,
+            Duration::minutes(10),
+        );
+
+        assert!(!timeline[0].success);
+    }
+
+    #[test]
+    fn failures_are_counted_from_the_first_in_a_run() {
+        let timeline = vec![
+            status("london", 1, false),
+            status("london", 2, false),
+            status("london", 3, false),
+            status("london", 4, true),
+            status("london", 5, false),
+        ];
+
+        assert_eq!(failing_since(&timeline), Some(timeline[2].created));
+        assert_eq!(failing_since(&timeline[3..]), None);
+        assert_eq!(failing_since(&[]), None);
+    }
+}
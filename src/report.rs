@@ -0,0 +1,542 @@
+use crate::checks::latency::{histogram_percentile, latency_histogram};
+use crate::data::incident_repository::IncidentRepository;
+use crate::data::outage_repository::OutageRepository;
+use crate::data::project_repository::ProjectRepository;
+use crate::data::rollup_repository::RollupRepository;
+use crate::data::status_repository::StatusRepository;
+use crate::models::{Incidents, Outage, Project, Status, StatusRollup};
+use crate::rollup::RollupPeriod;
+use crate::uptime::{max_sample_gap, up_and_down_since};
+use crate::{settings, time_formatter};
+use anyhow::Context;
+use askama::Template;
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A calendar month that a report covers, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ReportMonth(NaiveDate);
+
+impl ReportMonth {
+    pub fn containing(time: NaiveDateTime) -> Self {
+        Self(time.date().with_day(1).unwrap())
+    }
+
+    /// The last full month before `now`
+    pub fn previous(now: NaiveDateTime) -> Self {
+        Self::containing(now).minus(1)
+    }
+
+    pub fn minus(&self, months: u32) -> Self {
+        Self(self.0 - Months::new(months))
+    }
+
+    pub fn next(&self) -> Self {
+        Self(self.0 + Months::new(1))
+    }
+
+    pub fn start(&self) -> NaiveDateTime {
+        self.0.and_hms_opt(0, 0, 0).unwrap()
+    }
+
+    pub fn end(&self) -> NaiveDateTime {
+        (self.0 + Months::new(1)).and_hms_opt(0, 0, 0).unwrap()
+    }
+
+    pub fn name(&self) -> String {
+        self.0.format("%B %Y").to_string()
+    }
+}
+
+impl Display for ReportMonth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.format("%Y-%m"))
+    }
+}
+
+impl FromStr for ReportMonth {
+    type Err = anyhow::Error;
+
+    /// Parse a month such as `2026-09`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NaiveDate::parse_from_str(&format!("{s}-01"), "%Y-%m-%d")
+            .map(Self)
+            .context("Month must be in the form YYYY-MM")
+    }
+}
+
+/// The availability of a single project over a month
+#[derive(Debug, Clone)]
+pub struct ProjectReport {
+    pub project: String,
+    /// Time weighted, `None` if the project wasn't checked during the month
+    pub uptime: Option<f64>,
+    pub outages: usize,
+    /// Mean time to recovery
+    pub mttr: Option<Duration>,
+    /// Mean time between failures, the time the project was up for each outage
+    pub mtbf: Option<Duration>,
+    pub longest_outage: Option<Duration>,
+    pub p50_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub incidents: Vec<Incidents>,
+}
+
+impl ProjectReport {
+    /// Measured from the hourly `rollups` of the month and the samples in `timeline`, ordered
+    /// newest first, since the latest of them. `rollups`, `outages` and `incidents` only need to
+    /// belong to the project, anything outside of the month is ignored
+    pub fn new(
+        project: &Project,
+        month: ReportMonth,
+        rollups: &[StatusRollup],
+        timeline: &[Status],
+        outages: &[Outage],
+        incidents: &[Incidents],
+        now: NaiveDateTime,
+    ) -> Self {
+        let (start, end) = (month.start(), month.end().min(now));
+        let rollups = rollups_in_month(rollups, month).collect::<Vec<_>>();
+        let rolled_up_until = rolled_up_until(&rollups, month);
+
+        let (recent_up, recent_down) =
+            up_and_down_since(timeline, rolled_up_until, max_sample_gap(project), end);
+        let seconds = |seconds: i32| Duration::seconds(i64::from(seconds));
+        let up = rollups
+            .iter()
+            .fold(recent_up, |up, r| up + seconds(r.up_seconds));
+        let down = rollups
+            .iter()
+            .fold(recent_down, |down, r| down + seconds(r.down_seconds));
+        let known = up + down;
+
+        let outage_durations = outages
+            .iter()
+            .map(|o| o.overlap(start, end, now))
+            .filter(|d| *d > Duration::zero())
+            .collect::<Vec<_>>();
+        let outage_count = i32::try_from(outage_durations.len()).unwrap_or(i32::MAX);
+        let total_outage = outage_durations
+            .iter()
+            .fold(Duration::zero(), |total, d| total + *d);
+
+        // Hours rolled up before the latency histogram was kept are left out
+        let mut latencies = latency_histogram(
+            timeline
+                .iter()
+                .filter(|s| s.created >= rolled_up_until && s.created < end && s.is_success())
+                .map(|s| s.time),
+        );
+        for histogram in rollups.iter().filter_map(|r| r.histogram()) {
+            for (bucket, count) in histogram {
+                *latencies.entry(bucket).or_insert(0) += count;
+            }
+        }
+        let latency_percentile = |percentile: usize| {
+            histogram_percentile(&latencies, percentile).and_then(|ms| u64::try_from(ms).ok())
+        };
+
+        Self {
+            project: project.name.clone(),
+            uptime: if known.is_zero() {
+                None
+            } else {
+                Some(up.num_milliseconds() as f64 * 100.0 / known.num_milliseconds() as f64)
+            },
+            outages: outage_durations.len(),
+            mttr: (outage_count > 0).then(|| total_outage / outage_count),
+            mtbf: (outage_count > 0).then(|| up / outage_count),
+            longest_outage: outage_durations.iter().max().copied(),
+            p50_ms: latency_percentile(50),
+            p95_ms: latency_percentile(95),
+            incidents: incidents
+                .iter()
+                .filter(|i| i.created >= start && i.created < end)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn formatted_uptime(&self) -> String {
+        self.uptime
+            .map(|u| format!("{u:.3}%"))
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    pub fn formatted_mttr(&self) -> String {
+        format_optional_duration(self.mttr)
+    }
+
+    pub fn formatted_mtbf(&self) -> String {
+        format_optional_duration(self.mtbf)
+    }
+
+    pub fn formatted_longest_outage(&self) -> String {
+        format_optional_duration(self.longest_outage)
+    }
+
+    pub fn formatted_p50(&self) -> String {
+        format_optional_ms(self.p50_ms)
+    }
+
+    pub fn formatted_p95(&self) -> String {
+        format_optional_ms(self.p95_ms)
+    }
+
+    pub fn incident_urls(&self) -> Vec<String> {
+        self.incidents
+            .iter()
+            .map(|i| format!("{}/incident/{}", settings::get_host_url(), i.id))
+            .collect()
+    }
+}
+
+/// The hourly rollups that cover the month
+fn rollups_in_month(
+    rollups: &[StatusRollup],
+    month: ReportMonth,
+) -> impl Iterator<Item = &StatusRollup> {
+    rollups.iter().filter(move |r| {
+        r.period_seconds == RollupPeriod::Hour.seconds()
+            && r.period_start >= month.start()
+            && r.period_end() <= month.end()
+    })
+}
+
+/// When the raw samples take over from the hourly rollups within the month
+fn rolled_up_until(rollups: &[&StatusRollup], month: ReportMonth) -> NaiveDateTime {
+    rollups
+        .iter()
+        .map(|r| r.period_end())
+        .max()
+        .unwrap_or(month.start())
+}
+
+fn format_optional_duration(duration: Option<Duration>) -> String {
+    match duration {
+        Some(duration) if duration < Duration::minutes(1) => "less than a minute".to_string(),
+        Some(duration) => time_formatter::format_duration(&duration),
+        None => "-".to_string(),
+    }
+}
+
+fn format_optional_ms(ms: Option<u64>) -> String {
+    ms.map(|ms| format!("{ms}ms"))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Availability of every project over a month
+#[derive(Template, Debug, Clone)]
+#[template(path = "report.html")]
+pub struct MonthlyReport {
+    pub month: ReportMonth,
+    pub projects: Vec<ProjectReport>,
+}
+
+impl MonthlyReport {
+    /// Build the report for every enabled project from the hourly rollups, the samples that
+    /// haven't been rolled up yet, outages and incidents. Rollups are kept after the samples are
+    /// pruned, so any month can be reported on
+    pub fn generate(
+        month: ReportMonth,
+        project_repo: &dyn ProjectRepository,
+        rollup_repo: &dyn RollupRepository,
+        status_repo: &dyn StatusRepository,
+        outage_repo: &dyn OutageRepository,
+        incident_repo: &dyn IncidentRepository,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<Self> {
+        let projects = project_repo.get_all_enabled_projects()?;
+        let outages = outage_repo
+            .get_outages_since(month.start())?
+            .into_iter()
+            .filter(|o| o.started < month.end())
+            .collect::<Vec<_>>();
+        let incidents = incident_repo.get_incidents_between(month.start(), month.end())?;
+        let rollups =
+            rollup_repo.get_rollups_between(RollupPeriod::Hour, month.start(), month.end())?;
+
+        let mut reports = Vec::with_capacity(projects.len());
+        for project in &projects {
+            let project_rollups = rollups
+                .iter()
+                .filter(|r| r.project == project.id)
+                .cloned()
+                .collect::<Vec<_>>();
+            let from = rolled_up_until(
+                &rollups_in_month(&project_rollups, month).collect::<Vec<_>>(),
+                month,
+            );
+            let timeline = if from < month.end().min(now) {
+                status_repo.get_project_timeline_between(
+                    project.id,
+                    from - max_sample_gap(project),
+                    month.end(),
+                )?
+            } else {
+                vec![]
+            };
+            let project_outages = outages
+                .iter()
+                .filter(|o| o.project == project.id)
+                .cloned()
+                .collect::<Vec<_>>();
+            let project_incidents = incidents
+                .iter()
+                .filter(|i| i.project == project.id)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            reports.push(ProjectReport::new(
+                project,
+                month,
+                &project_rollups,
+                &timeline,
+                &project_outages,
+                &project_incidents,
+                now,
+            ));
+        }
+
+        Ok(Self {
+            month,
+            projects: reports,
+        })
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = "project,uptime_percent,outages,mttr_seconds,mtbf_seconds,longest_outage_seconds,p50_ms,p95_ms,incidents\n".to_string();
+
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let seconds =
+            |duration: Option<Duration>| optional(duration.map(|d| d.num_seconds().to_string()));
+
+        for report in &self.projects {
+            let fields = [
+                report.project.clone(),
+                optional(report.uptime.map(|u| format!("{u:.3}"))),
+                report.outages.to_string(),
+                seconds(report.mttr),
+                seconds(report.mtbf),
+                seconds(report.longest_outage),
+                optional(report.p50_ms.map(|ms| ms.to_string())),
+                optional(report.p95_ms.map(|ms| ms.to_string())),
+                report.incident_urls().join(" "),
+            ];
+
+            csv += &fields
+                .iter()
+                .map(|f| csv_field(f))
+                .collect::<Vec<_>>()
+                .join(",");
+            csv += "\n";
+        }
+
+        csv
+    }
+}
+
+/// Quote a CSV field if needed
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::fixtures;
+    use crate::models::{Incidents, Outage, Project, Status, StatusRollup};
+    use crate::report::{MonthlyReport, ProjectReport, ReportMonth};
+    use crate::rollup::{RollupPeriod, rollup};
+    use crate::uptime::max_sample_gap;
+    use chrono::{Datelike, Duration, NaiveDateTime};
+
+    fn at(month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        fixtures::at(2026, month, day, hour, 0)
+    }
+
+    fn project() -> Project {
+        Project {
+            name: "Website, public".to_string(),
+            check_interval: 3600,
+            ..fixtures::project(1)
+        }
+    }
+
+    /// An hourly sample for all of September, down on the 10th from 10:00 until 12:00
+    fn timeline() -> Vec<Status> {
+        let mut time = at(10, 1, 0) - Duration::hours(1);
+        let mut timeline = vec![];
+        while time >= at(9, 1, 0) {
+            let success = !(time >= at(9, 10, 10) && time < at(9, 10, 12));
+            timeline.push(Status {
+                time: if time.date().day0().is_multiple_of(2) {
+                    100
+                } else {
+                    300
+                },
+                status_code: Some(200),
+                ..fixtures::status(1, time, success)
+            });
+            time -= Duration::hours(1);
+        }
+        timeline
+    }
+
+    fn outage(started: NaiveDateTime, ended: NaiveDateTime) -> Outage {
+        fixtures::outage(1, started, Some(ended))
+    }
+
+    #[test]
+    fn months() {
+        let month: ReportMonth = "2026-09".parse().unwrap();
+        assert_eq!(month.start(), at(9, 1, 0));
+        assert_eq!(month.end(), at(10, 1, 0));
+        assert_eq!(month.name(), "September 2026");
+        assert_eq!(month.to_string(), "2026-09");
+
+        assert_eq!(ReportMonth::previous(at(10, 18, 12)), month);
+        assert_eq!(ReportMonth::previous(at(1, 1, 0)).to_string(), "2025-12");
+        assert!("September".parse::<ReportMonth>().is_err());
+    }
+
+    /// The hourly rollups of the timeline before `until`
+    fn rollups(until: NaiveDateTime) -> Vec<StatusRollup> {
+        let timeline = timeline();
+        let mut start = at(9, 1, 0);
+        let mut rollups = vec![];
+        while start < until {
+            let rollup = rollup(
+                1,
+                RollupPeriod::Hour,
+                start,
+                &timeline,
+                max_sample_gap(&project()),
+            );
+            rollups.push(StatusRollup {
+                latency_histogram: Some(rollup.latency_histogram),
+                up_seconds: rollup.up_seconds,
+                down_seconds: rollup.down_seconds,
+                samples: rollup.samples,
+                ..fixtures::rollup(1, start)
+            });
+            start += RollupPeriod::Hour.duration();
+        }
+        rollups
+    }
+
+    fn outages() -> [Outage; 3] {
+        [
+            outage(at(9, 10, 10), at(9, 10, 12)),
+            // Only the part of this outage within September counts
+            outage(at(8, 31, 23), at(9, 1, 1)),
+            outage(at(8, 1, 0), at(8, 2, 0)),
+        ]
+    }
+
+    fn incidents() -> [Incidents; 2] {
+        [
+            Incidents {
+                id: 7,
+                created: at(9, 10, 11),
+                project: 1,
+            },
+            Incidents {
+                id: 8,
+                created: at(10, 2, 0),
+                project: 1,
+            },
+        ]
+    }
+
+    fn september_report(rollups: &[StatusRollup], timeline: &[Status]) -> ProjectReport {
+        ProjectReport::new(
+            &project(),
+            "2026-09".parse().unwrap(),
+            rollups,
+            timeline,
+            &outages(),
+            &incidents(),
+            at(10, 18, 0),
+        )
+    }
+
+    fn assert_september(report: &ProjectReport) {
+        assert_eq!(report.outages, 2);
+        assert_eq!(report.mttr, Some(Duration::minutes(90)));
+        assert_eq!(report.longest_outage, Some(Duration::hours(2)));
+        assert_eq!(report.mtbf, Some(Duration::hours(359)));
+        assert_eq!(report.formatted_uptime(), "99.722%");
+        assert_eq!(report.p50_ms, Some(100));
+        assert_eq!(report.p95_ms, Some(300));
+        assert_eq!(
+            report.incidents.iter().map(|i| i.id).collect::<Vec<_>>(),
+            vec![7]
+        );
+    }
+
+    #[test]
+    fn project_report() {
+        assert_september(&september_report(&[], &timeline()));
+    }
+
+    #[test]
+    fn project_report_from_rollups() {
+        // Once the samples have been pruned
+        assert_september(&september_report(&rollups(at(10, 1, 0)), &[]));
+        // Part way through rolling up, samples before the latest rollup aren't counted twice
+        assert_september(&september_report(&rollups(at(9, 20, 0)), &timeline()));
+    }
+
+    #[test]
+    fn unchecked_project() {
+        let report = ProjectReport::new(
+            &project(),
+            "2026-09".parse().unwrap(),
+            &[],
+            &[],
+            &[],
+            &[],
+            at(10, 18, 0),
+        );
+
+        assert_eq!(report.formatted_uptime(), "-");
+        assert_eq!(report.formatted_mttr(), "-");
+        assert_eq!(report.outages, 0);
+    }
+
+    #[test]
+    fn csv() {
+        let month: ReportMonth = "2026-09".parse().unwrap();
+        let report = MonthlyReport {
+            month,
+            projects: vec![ProjectReport::new(
+                &project(),
+                month,
+                &[],
+                &timeline(),
+                &[outage(at(9, 10, 10), at(9, 10, 12))],
+                &[],
+                at(10, 18, 0),
+            )],
+        };
+
+        let csv = report.to_csv();
+        let mut lines = csv.lines();
+        assert!(
+            lines
+                .next()
+                .unwrap()
+                .starts_with("project,uptime_percent,outages")
+        );
+        assert_eq!(
+            lines.next().unwrap(),
+            "\"Website, public\",99.722,1,7200,2584800,7200,100,300,"
+        );
+        assert_eq!(lines.next(), None);
+    }
+}
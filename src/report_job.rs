@@ -0,0 +1,129 @@
+use crate::data::settings_repository::SettingsRepository;
+use crate::db::Database;
+use crate::notifications::mailer::Mailer;
+use crate::report::{MonthlyReport, ReportMonth};
+use crate::settings;
+use anyhow::Context;
+use askama::Template;
+use chrono::{NaiveDateTime, Utc};
+use std::sync::Arc;
+use tracing::error;
+
+/// The setting that records the last month a report was emailed for
+const LAST_REPORTED_MONTH: &str = "LAST_REPORTED_MONTH";
+
+/// How far back reports are caught up on after the job hasn't run for a while
+const MAX_CATCH_UP_MONTHS: u32 = 12;
+
+/// Email the availability report for the previous month to `REPORT_EMAILS` at the start of each
+/// month, catching up on any months that were missed while the server wasn't running
+pub async fn run_report_job(db: Database, mailer: Arc<Mailer>) {
+    let _span = tracing::info_span!("Report Job");
+
+    loop {
+        let now = Utc::now().naive_utc();
+
+        let recipients = settings::report_emails();
+        if !recipients.is_empty()
+            && let Err(e) = send_due_reports(&db, &mailer, &recipients, now)
+        {
+            error!("Failed to send monthly reports: {e:?}");
+        }
+
+        // Check again once the month that is running now is over
+        let wait = (ReportMonth::containing(now).end() - now)
+            .to_std()
+            .unwrap_or_default();
+        actix_rt::time::sleep(wait).await;
+    }
+}
+
+/// The months that have ended since the last one reported on, oldest first. Only the previous
+/// month is due if no report has been sent yet
+fn months_due(last_reported: Option<ReportMonth>, now: NaiveDateTime) -> Vec<ReportMonth> {
+    let previous = ReportMonth::previous(now);
+    let mut month = last_reported
+        .map(|last| last.next())
+        .unwrap_or(previous)
+        .max(previous.minus(MAX_CATCH_UP_MONTHS - 1));
+
+    let mut months = Vec::new();
+    while month <= previous {
+        months.push(month);
+        month = month.next();
+    }
+    months
+}
+
+fn send_due_reports(
+    db: &Database,
+    mailer: &Mailer,
+    recipients: &[String],
+    now: NaiveDateTime,
+) -> anyhow::Result<()> {
+    let last_reported = db
+        .get_setting(LAST_REPORTED_MONTH)?
+        .and_then(|month| month.parse::<ReportMonth>().ok());
+
+    for month in months_due(last_reported, now) {
+        send_report(db, mailer, month, recipients)?;
+        db.set_setting(LAST_REPORTED_MONTH, &month.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn send_report(
+    db: &Database,
+    mailer: &Mailer,
+    month: ReportMonth,
+    recipients: &[String],
+) -> anyhow::Result<()> {
+    let now = Utc::now().naive_utc().max(month.end());
+    let report = MonthlyReport::generate(month, db, db, db, db, db, now)?;
+    let body = report.render().context("Unable to render report")?;
+
+    tracing::info!(
+        "Sending report for {} to {} recipients",
+        report.month,
+        recipients.len()
+    );
+    mailer.send_to(
+        recipients,
+        "YouUp <alerts@you-up.net>",
+        &format!("YouUp availability report for {}", report.month.name()),
+        &body,
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::fixtures;
+    use crate::report::ReportMonth;
+    use crate::report_job::months_due;
+
+    fn month(s: &str) -> ReportMonth {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn previous_month_is_due_first() {
+        let now = fixtures::at(2026, 10, 18, 12, 0);
+
+        assert_eq!(months_due(None, now), vec![month("2026-09")]);
+        assert!(months_due(Some(month("2026-09")), now).is_empty());
+    }
+
+    #[test]
+    fn missed_months_are_caught_up() {
+        let now = fixtures::at(2026, 10, 1, 0, 5);
+
+        assert_eq!(
+            months_due(Some(month("2026-06")), now),
+            vec![month("2026-07"), month("2026-08"), month("2026-09")]
+        );
+        assert_eq!(months_due(Some(month("2020-01")), now).len(), 12);
+    }
+}
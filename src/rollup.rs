@@ -0,0 +1,421 @@
+use crate::checks::latency::{latency_histogram, percentile};
+use crate::data::rollup_repository::RollupRepository;
+use crate::data::status_repository::StatusRepository;
+use crate::models::{
+    DailyStatusCounts, NewStatusRollup, Project, RollupTotals, Status, StatusRollup,
+};
+use crate::uptime::{ProjectUptime, UptimeWindow, max_sample_gap, up_and_down_since};
+use chrono::{Duration, NaiveDate, NaiveDateTime, Timelike};
+use std::collections::BTreeMap;
+
+/// How long the status pages can look back, the longest uptime window
+pub const HISTORY_DAYS: i64 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollupPeriod {
+    Hour,
+    Day,
+}
+
+impl RollupPeriod {
+    pub fn seconds(&self) -> i32 {
+        match self {
+            RollupPeriod::Hour => 3600,
+            RollupPeriod::Day => 86400,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::seconds(i64::from(self.seconds()))
+    }
+
+    /// The start of the period that `time` is in
+    pub fn start_of(&self, time: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            RollupPeriod::Hour => time.date().and_hms_opt(time.hour(), 0, 0).unwrap(),
+            RollupPeriod::Day => time.date().and_hms_opt(0, 0, 0).unwrap(),
+        }
+    }
+}
+
+/// Summarise the samples of a project between `start` and the end of the period.
+/// `timeline` is ordered newest first and should go back `max_gap` before `start`, the sample
+/// before the period still counts towards the up and down time until the next one
+pub fn rollup(
+    project: i32,
+    period: RollupPeriod,
+    start: NaiveDateTime,
+    timeline: &[Status],
+    max_gap: Duration,
+) -> NewStatusRollup {
+    let end = start + period.duration();
+    let samples = timeline
+        .iter()
+        .filter(|s| s.created >= start && s.created < end)
+        .collect::<Vec<_>>();
+
+    let (up, down) = up_and_down_since(timeline, start, max_gap, end);
+
+    let latencies = samples
+        .iter()
+        .filter(|s| s.is_success())
+        .filter_map(|s| u64::try_from(s.time).ok())
+        .collect::<Vec<_>>();
+    let to_ms = |ms: u64| i32::try_from(ms).unwrap_or(i32::MAX);
+
+    let mut status_codes = BTreeMap::new();
+    for code in samples.iter().filter_map(|s| s.status_code) {
+        *status_codes.entry(code).or_insert(0) += 1;
+    }
+
+    let count = |f: fn(&Status) -> bool| {
+        i32::try_from(samples.iter().filter(|s| f(s)).count()).unwrap_or(i32::MAX)
+    };
+
+    NewStatusRollup {
+        project,
+        period_seconds: period.seconds(),
+        period_start: start,
+        samples: count(|_| true),
+        failures: count(|s| !s.is_success()),
+        degraded: count(|s| s.is_degraded()),
+        up_seconds: i32::try_from(up.num_seconds()).unwrap_or(i32::MAX),
+        down_seconds: i32::try_from(down.num_seconds()).unwrap_or(i32::MAX),
+        min_latency_ms: latencies.iter().min().copied().map(to_ms),
+        avg_latency_ms: (!latencies.is_empty())
+            .then(|| to_ms(latencies.iter().sum::<u64>() / latencies.len() as u64)),
+        p95_latency_ms: percentile(&latencies, 95).map(to_ms),
+        status_codes: serde_json::to_string(&status_codes).unwrap_or_else(|_| "{}".to_string()),
+        latency_histogram: serde_json::to_string(&latency_histogram(
+            latencies.iter().map(|ms| to_ms(*ms)),
+        ))
+        .unwrap_or_else(|_| "{}".to_string()),
+    }
+}
+
+/// The history of the projects for the status pages, rollups for the past and the raw samples
+/// that haven't been rolled up yet
+pub struct StatusHistory {
+    /// The hourly rollups of each project added up over each uptime window
+    pub totals: Vec<(UptimeWindow, RollupTotals)>,
+    pub daily: Vec<StatusRollup>,
+    /// Counts for the days before today that haven't been rolled up yet
+    pub pending_days: Vec<DailyStatusCounts>,
+    /// Days that any project was checked on
+    pub checked_days: Vec<NaiveDate>,
+    /// The timeline of each project, combined from every location, newest first
+    pub recent: BTreeMap<i32, Vec<Status>>,
+}
+
+impl StatusHistory {
+    /// Load the last `days` of daily rollups, the rolled up uptime of each window and the raw
+    /// samples of each project since midnight or its last hourly rollup, whichever is earlier.
+    /// Earlier days that haven't been rolled up yet are counted by the database instead
+    pub fn load(
+        rollup_repo: &dyn RollupRepository,
+        status_repo: &dyn StatusRepository,
+        projects: &[Project],
+        days: i64,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<Self> {
+        let history_start = RollupPeriod::Day.start_of(now - Duration::days(HISTORY_DAYS));
+        let days_start = RollupPeriod::Day.start_of(now - Duration::days(days));
+        let today = RollupPeriod::Day.start_of(now);
+
+        let mut totals = Vec::new();
+        for window in UptimeWindow::ALL {
+            totals.extend(
+                rollup_repo
+                    .get_hourly_totals(now - window.duration())?
+                    .into_iter()
+                    .map(|t| (window, t)),
+            );
+        }
+        let daily = rollup_repo.get_rollups_since(RollupPeriod::Day, days_start)?;
+        let checked_days = rollup_repo
+            .get_checked_days(history_start)?
+            .into_iter()
+            .map(|start| start.date())
+            .collect();
+
+        // Days and samples that haven't been rolled up yet are loaded for every project at once,
+        // from the earliest project and then trimmed to each one
+        let project_ids = projects.iter().map(|p| p.id).collect::<Vec<_>>();
+        let rolled_up_days_until = |project: i32| {
+            daily
+                .iter()
+                .filter(|r| r.project == project)
+                .map(|r| r.period_start + RollupPeriod::Day.duration())
+                .max()
+                .unwrap_or(days_start)
+        };
+        let pending_from = projects
+            .iter()
+            .map(|p| rolled_up_days_until(p.id))
+            .min()
+            .unwrap_or(today);
+        let pending_days = if pending_from < today {
+            status_repo
+                .get_daily_status_counts(&project_ids, pending_from, today)?
+                .into_iter()
+                .filter(|c| c.day >= rolled_up_days_until(c.project).date())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let recent_since = |project: &Project| {
+            rolled_up_until(&totals, project.id)
+                .unwrap_or(today)
+                .min(today)
+                - max_sample_gap(project)
+        };
+        let mut recent = status_repo.get_project_timelines(
+            &project_ids,
+            projects.iter().map(recent_since).min().unwrap_or(today),
+        )?;
+        for project in projects {
+            let since = recent_since(project);
+            if let Some(timeline) = recent.get_mut(&project.id) {
+                timeline.retain(|s| s.created > since);
+            }
+        }
+
+        Ok(Self {
+            totals,
+            daily,
+            pending_days,
+            checked_days,
+            recent,
+        })
+    }
+
+    /// The raw samples of a project that are still around, newest first
+    pub fn recent_timeline(&self, project: i32) -> Vec<Status> {
+        self.recent.get(&project).cloned().unwrap_or_default()
+    }
+
+    /// How many samples of a project were taken on a day, from the daily rollup if there is one
+    pub fn daily_counts(&self, project: i32, day: NaiveDate) -> Option<DailyStatusCounts> {
+        self.daily
+            .iter()
+            .find(|r| r.project == project && r.period_start.date() == day)
+            .map(DailyStatusCounts::from)
+            .or_else(|| {
+                self.pending_days
+                    .iter()
+                    .find(|c| c.project == project && c.day == day)
+                    .cloned()
+            })
+    }
+
+    /// Whether any project was checked during a day
+    pub fn checked_on(&self, day: NaiveDate) -> bool {
+        self.checked_days.contains(&day)
+            || self
+                .pending_days
+                .iter()
+                .any(|c| c.day == day && c.samples > 0)
+            || self
+                .recent
+                .values()
+                .flatten()
+                .any(|s| s.created.date() == day)
+    }
+
+    /// Time weighted uptime from the hourly rollups, and the raw samples since the last one
+    pub fn uptime(&self, project: &Project, now: NaiveDateTime) -> ProjectUptime {
+        let totals = self
+            .totals
+            .iter()
+            .filter(|(_, t)| t.project == project.id)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        ProjectUptime::new(project, &totals, &self.recent_timeline(project.id), now)
+    }
+}
+
+/// The end of the latest hourly rollup of a project
+fn rolled_up_until(totals: &[(UptimeWindow, RollupTotals)], project: i32) -> Option<NaiveDateTime> {
+    totals
+        .iter()
+        .filter(|(_, t)| t.project == project)
+        .filter_map(|(_, t)| t.rolled_up_until())
+        .max()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::data::in_memory::InMemoryDatabase;
+    use crate::data::status_repository::StatusRepository;
+    use crate::models::fixtures;
+    use crate::models::{DailyStatusCounts, NewStatus, Project, RollupTotals, Status};
+    use crate::rollup::{RollupPeriod, StatusHistory, rollup};
+    use crate::uptime::UptimeWindow;
+    use chrono::{Duration, NaiveDateTime};
+    use std::collections::BTreeMap;
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        fixtures::at(2020, 9, 25, hour, minute)
+    }
+
+    fn status(created: NaiveDateTime, success: bool, time: i32, status_code: i32) -> Status {
+        Status {
+            time,
+            status_code: Some(status_code),
+            ..fixtures::status(1, created, success)
+        }
+    }
+
+    /// A sample every 15 minutes from 10:00 until 12:00, failing from 11:00 until 11:15
+    fn timeline() -> Vec<Status> {
+        (0..=8)
+            .rev()
+            .map(|i| {
+                let created = at(10, 0) + Duration::minutes(15 * i);
+                let failing = created >= at(11, 0) && created < at(11, 15);
+                if failing {
+                    status(created, false, 5000, 503)
+                } else {
+                    status(created, true, 100 + 10 * i as i32, 200)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn period_start() {
+        assert_eq!(RollupPeriod::Hour.start_of(at(11, 42)), at(11, 0));
+        assert_eq!(RollupPeriod::Day.start_of(at(11, 42)), at(0, 0));
+    }
+
+    #[test]
+    fn hourly_rollup() {
+        let rollup = rollup(
+            1,
+            RollupPeriod::Hour,
+            at(11, 0),
+            &timeline(),
+            Duration::minutes(30),
+        );
+
+        assert_eq!(rollup.period_seconds, 3600);
+        assert_eq!(rollup.samples, 4);
+        assert_eq!(rollup.failures, 1);
+        assert_eq!(rollup.up_seconds, 45 * 60);
+        assert_eq!(rollup.down_seconds, 15 * 60);
+        assert_eq!(rollup.min_latency_ms, Some(150));
+        assert_eq!(rollup.avg_latency_ms, Some(160));
+        assert_eq!(rollup.p95_latency_ms, Some(170));
+        assert_eq!(rollup.status_codes, r#"{"200":3,"503":1}"#);
+        assert_eq!(rollup.latency_histogram, r#"{"150":1,"160":1,"170":1}"#);
+    }
+
+    #[test]
+    fn empty_rollup() {
+        let rollup = rollup(
+            1,
+            RollupPeriod::Hour,
+            at(14, 0),
+            &timeline(),
+            Duration::minutes(30),
+        );
+
+        // The last sample is too old to count towards the period
+        assert_eq!(rollup.samples, 0);
+        assert_eq!(rollup.up_seconds, 0);
+        assert_eq!(rollup.avg_latency_ms, None);
+        assert_eq!(rollup.status_codes, "{}");
+    }
+
+    #[test]
+    fn uptime_from_rollups_and_recent() {
+        let timeline = timeline();
+        let hourly = rollup(
+            1,
+            RollupPeriod::Hour,
+            at(10, 0),
+            &timeline,
+            Duration::minutes(30),
+        );
+        let totals = RollupTotals {
+            project: 1,
+            up_seconds: Some(i64::from(hourly.up_seconds)),
+            down_seconds: Some(i64::from(hourly.down_seconds)),
+            latest_start: Some(hourly.period_start),
+        };
+        // Only the samples since the last rollup are still loaded
+        let history = StatusHistory {
+            totals: UptimeWindow::ALL
+                .into_iter()
+                .map(|window| (window, totals.clone()))
+                .collect(),
+            daily: vec![],
+            pending_days: vec![],
+            checked_days: vec![],
+            recent: BTreeMap::from([(
+                1,
+                timeline
+                    .into_iter()
+                    .filter(|s| s.created >= at(10, 45))
+                    .collect(),
+            )]),
+        };
+
+        let project = Project {
+            check_interval: 900,
+            ..fixtures::project(1)
+        };
+        let uptime = history.uptime(&project, at(12, 0));
+
+        assert_eq!(uptime.windows[0].up, Duration::minutes(105));
+        assert_eq!(uptime.windows[0].down, Duration::minutes(15));
+        assert!(history.checked_on(at(0, 0).date()));
+    }
+
+    #[test]
+    fn days_without_rollups_are_counted() {
+        let repos = InMemoryDatabase::default();
+        let sample = |project, created, success| NewStatus {
+            project,
+            time: 100,
+            status_code: None,
+            success,
+            message: None,
+            degraded: false,
+            steps: None,
+            location: "local".to_string(),
+            created,
+        };
+        let yesterday = at(10, 0) - Duration::days(1);
+        repos
+            .add_statuses(&[
+                sample(1, yesterday, true),
+                sample(1, yesterday + Duration::minutes(15), false),
+                sample(1, yesterday + Duration::minutes(30), true),
+                sample(1, at(10, 0), true),
+                sample(2, at(10, 0), false),
+            ])
+            .unwrap();
+
+        let project = fixtures::project(1);
+        let history = StatusHistory::load(&repos, &repos, &[project], 7, at(12, 0)).unwrap();
+
+        // Only today's samples are loaded, and only for the projects asked for
+        assert_eq!(history.recent.len(), 1);
+        assert_eq!(history.recent_timeline(1).len(), 1);
+        assert_eq!(
+            history.daily_counts(1, yesterday.date()),
+            Some(DailyStatusCounts {
+                project: 1,
+                day: yesterday.date(),
+                samples: 3,
+                failures: 1,
+                avg_latency_ms: Some(100),
+            })
+        );
+        assert!(history.checked_on(yesterday.date()));
+        assert!(!history.checked_on(yesterday.date() - Duration::days(1)));
+    }
+}
@@ -0,0 +1,163 @@
+use crate::data::project_repository::ProjectRepository;
+use crate::data::rollup_repository::RollupRepository;
+use crate::data::status_repository::StatusRepository;
+use crate::db::Database;
+use crate::models::Project;
+use crate::rollup::{RollupPeriod, rollup};
+use crate::settings;
+use crate::update_job;
+use crate::uptime::max_sample_gap;
+use chrono::{NaiveDateTime, Utc};
+use std::time::Duration;
+use tracing::error;
+
+/// How often new samples are rolled up and old ones pruned
+const ROLLUP_JOB_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Everything needed to roll up and prune the samples
+pub trait RollupJobRepository: ProjectRepository + StatusRepository + RollupRepository {}
+
+impl<T: ProjectRepository + StatusRepository + RollupRepository> RollupJobRepository for T {}
+
+/// Periodically summarise the samples of each project into hourly and daily rollups, and remove
+/// samples older than the retention period
+pub async fn run_rollup_job(db: Database) {
+    let _span = tracing::info_span!("Rollup Job");
+
+    loop {
+        let now = Utc::now().naive_utc();
+
+        if let Err(e) = roll_up(&db, now) {
+            error!("Failed to roll up status: {e:?}");
+        } else if let Err(e) = prune(&db, now) {
+            error!("Failed to prune status: {e:?}");
+        }
+
+        actix_rt::time::sleep(ROLLUP_JOB_INTERVAL).await;
+    }
+}
+
+fn roll_up(db: &dyn RollupJobRepository, now: NaiveDateTime) -> anyhow::Result<()> {
+    let projects_list = db.get_all_projects()?;
+
+    // Results from agents can arrive a little after they were recorded, and samples that were
+    // spooled while the database was unavailable have to be stored before their periods are done
+    let settled = now - settings::location_stale_after();
+    let settled = match update_job::spooled_since()? {
+        Some(oldest) => settled.min(oldest),
+        None => settled,
+    };
+
+    for project in &projects_list {
+        roll_up_project(db, project, settled)?;
+    }
+
+    Ok(())
+}
+
+/// Roll up every period of a project that ended before `settled` and hasn't been rolled up yet
+fn roll_up_project(
+    db: &dyn RollupJobRepository,
+    project: &Project,
+    settled: NaiveDateTime,
+) -> anyhow::Result<()> {
+    let next_start = |period: RollupPeriod| -> anyhow::Result<Option<NaiveDateTime>> {
+        Ok(match db.get_latest_rollup(project.id, period)? {
+            Some(latest) => Some(latest.period_end()),
+            None => db
+                .get_oldest_status_time(project.id)?
+                .map(|oldest| period.start_of(oldest)),
+        })
+    };
+    let (Some(mut hour), Some(mut day)) = (
+        next_start(RollupPeriod::Hour)?,
+        next_start(RollupPeriod::Day)?,
+    ) else {
+        return Ok(());
+    };
+    let max_gap = max_sample_gap(project);
+
+    // Work through a day at a time, so that catching up doesn't load every sample at once
+    let mut chunk = RollupPeriod::Day.start_of(hour.min(day));
+    while hour + RollupPeriod::Hour.duration() <= settled {
+        let chunk_end = chunk + RollupPeriod::Day.duration();
+        let timeline =
+            db.get_project_timeline_between(project.id, chunk - max_gap, chunk_end.min(settled))?;
+
+        let mut rollups = Vec::new();
+        while hour < chunk_end && hour + RollupPeriod::Hour.duration() <= settled {
+            rollups.push(rollup(
+                project.id,
+                RollupPeriod::Hour,
+                hour,
+                &timeline,
+                max_gap,
+            ));
+            hour += RollupPeriod::Hour.duration();
+        }
+        if day == chunk && chunk_end <= settled {
+            rollups.push(rollup(
+                project.id,
+                RollupPeriod::Day,
+                day,
+                &timeline,
+                max_gap,
+            ));
+            day = chunk_end;
+        }
+
+        if !rollups.is_empty() {
+            db.save_rollups(&rollups)?;
+        }
+        chunk = chunk_end;
+    }
+
+    Ok(())
+}
+
+fn prune(db: &dyn RollupJobRepository, now: NaiveDateTime) -> anyhow::Result<()> {
+    let before = now - chrono::Duration::days(settings::status_retention_days());
+    let removed = db.delete_status_before(before)?;
+    if removed > 0 {
+        tracing::info!("Pruned {removed} status samples recorded before {before}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::data::in_memory::InMemoryDatabase;
+    use crate::data::rollup_repository::RollupRepository;
+    use crate::models::fixtures;
+    use crate::rollup::RollupPeriod;
+    use crate::rollup_job::roll_up;
+    use chrono::Duration;
+
+    #[test]
+    fn every_project_is_rolled_up() {
+        let repos = InMemoryDatabase::default();
+        let start = fixtures::at(2020, 1, 1, 0, 0);
+        for project in [1, 2] {
+            repos.tables().projects.push(fixtures::project(project));
+            for minute in 0..120 {
+                repos.tables().status.push(fixtures::status(
+                    project,
+                    start + Duration::minutes(minute),
+                    true,
+                ));
+            }
+        }
+
+        roll_up(&repos, fixtures::at(2020, 1, 1, 5, 30)).unwrap();
+
+        // Every hour that ended before the samples settled, but not the day that is still going
+        for project in [1, 2] {
+            let hourly = repos
+                .get_project_rollups_since(project, RollupPeriod::Hour, start)
+                .unwrap();
+            assert_eq!(hourly.len(), 5, "project {project}");
+            assert_eq!(hourly.last().unwrap().samples, 60);
+        }
+        assert!(repos.get_rollups_since(RollupPeriod::Day, start).unwrap().is_empty());
+    }
+}
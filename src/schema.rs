@@ -4,8 +4,20 @@ table! {
         url -> Varchar,
         name -> Varchar,
         description -> Nullable<Text>,
-        created -> Datetime,
+        created -> Timestamp,
         enabled -> Bool,
+        check_type -> Varchar,
+        check_config -> Nullable<Text>,
+        check_interval -> Integer,
+        latency_warn_ms -> Nullable<Integer>,
+        latency_critical_ms -> Nullable<Integer>,
+        latency_use_p95 -> Bool,
+        notify_degraded -> Bool,
+        down_after_failures -> Integer,
+        up_after_successes -> Integer,
+        flap_threshold -> Nullable<Integer>,
+        flap_window_minutes -> Integer,
+        sla_target -> Nullable<Double>,
     }
 }
 table! {
@@ -13,14 +25,19 @@ table! {
         id -> Integer,
         project -> Integer,
         time -> Integer,
-        status_code -> Integer,
-        created -> Datetime,
+        status_code -> Nullable<Integer>,
+        created -> Timestamp,
+        success -> Bool,
+        message -> Nullable<Text>,
+        degraded -> Bool,
+        steps -> Nullable<Text>,
+        location -> Varchar,
     }
 }
 table! {
     incidents (id) {
         id -> Integer,
-        created -> Datetime,
+        created -> Timestamp,
         project -> Integer,
     }
 }
@@ -28,7 +45,7 @@ table! {
 table! {
     incident_status_type (id) {
         id -> Integer,
-        created -> Datetime,
+        created -> Timestamp,
         colour -> Varchar,
         title -> Varchar,
     }
@@ -37,7 +54,7 @@ table! {
 table! {
     incident_status_update (id) {
         id -> Integer,
-        created -> Datetime,
+        created -> Timestamp,
         status_type -> Integer,
         message -> Varchar,
         incident -> Integer,
@@ -47,7 +64,7 @@ table! {
 table! {
     settings (id) {
         id -> Integer,
-        created -> Datetime,
+        created -> Timestamp,
         name -> Varchar,
         value -> Text,
     }
@@ -56,7 +73,7 @@ table! {
 table! {
     email_subscriptions (id) {
         id -> Integer,
-        created -> Datetime,
+        created -> Timestamp,
         email -> Varchar,
         confirmed -> Bool,
     }
@@ -65,7 +82,7 @@ table! {
 table! {
     sms_subscriptions (id) {
         id -> Integer,
-        created -> Datetime,
+        created -> Timestamp,
         phone_number -> Varchar,
         confirmed -> Bool,
     }
@@ -74,12 +91,91 @@ table! {
 table! {
     webhook_subscriptions (id) {
         id -> Integer,
-        created -> Datetime,
+        created -> Timestamp,
         url -> Varchar,
         enabled -> Bool,
     }
 }
 
+table! {
+    project_certificates (id) {
+        id -> Integer,
+        created -> Timestamp,
+        project -> Integer,
+        not_after -> Timestamp,
+        issuer -> Text,
+        sans -> Text,
+        notified_threshold -> Nullable<Integer>,
+    }
+}
+
+table! {
+    heartbeats (id) {
+        id -> Integer,
+        created -> Timestamp,
+        project -> Integer,
+        token -> Varchar,
+        last_ping -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    project_alerts (id) {
+        id -> Integer,
+        created -> Timestamp,
+        project -> Integer,
+        down -> Bool,
+        streak -> Integer,
+        flapping -> Bool,
+        changed -> Timestamp,
+    }
+}
+
+table! {
+    outages (id) {
+        id -> Integer,
+        created -> Timestamp,
+        project -> Integer,
+        started -> Timestamp,
+        ended -> Nullable<Timestamp>,
+        cause -> Nullable<Text>,
+        first_status_code -> Nullable<Integer>,
+        last_status_code -> Nullable<Integer>,
+    }
+}
+
+table! {
+    slos (id) {
+        id -> Integer,
+        created -> Timestamp,
+        project -> Integer,
+        objective -> Double,
+        window_days -> Integer,
+        latency_threshold_ms -> Nullable<Integer>,
+        burning -> Bool,
+    }
+}
+
+table! {
+    status_rollups (id) {
+        id -> Integer,
+        created -> Timestamp,
+        project -> Integer,
+        period_seconds -> Integer,
+        period_start -> Timestamp,
+        samples -> Integer,
+        failures -> Integer,
+        degraded -> Integer,
+        up_seconds -> Integer,
+        down_seconds -> Integer,
+        min_latency_ms -> Nullable<Integer>,
+        avg_latency_ms -> Nullable<Integer>,
+        p95_latency_ms -> Nullable<Integer>,
+        status_codes -> Text,
+        latency_histogram -> Nullable<Text>,
+    }
+}
+
 joinable!(incident_status_update -> incident_status_type(status_type));
 
 allow_tables_to_appear_in_same_query!(incident_status_update, incident_status_type,);
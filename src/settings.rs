@@ -1,7 +1,4 @@
-use crate::db::Database;
-use crate::diesel::RunQueryDsl;
-use crate::models::Setting;
-use diesel::{ExpressionMethods, QueryDsl};
+use crate::data::settings_repository::SettingsRepositoryBox;
 use rand::Rng;
 use rand::distr::Alphanumeric;
 use std::env;
@@ -40,10 +37,110 @@ pub fn get_minimum_downtime_minutes() -> i64 {
     2
 }
 
+/// The maximum number of project checks that can be running at the same time,
+/// at least one so that checks can't stall waiting for a worker
+pub fn max_concurrent_checks() -> usize {
+    env::var("MAX_CONCURRENT_CHECKS")
+        .unwrap_or_else(|_| "".to_string())
+        .parse::<usize>()
+        .unwrap_or(8)
+        .max(1)
+}
+
+/// How long raw status samples are kept for, they are rolled up well before then. At least a
+/// day, burn rate alerts and the rollups still need the recent samples
+pub fn status_retention_days() -> i64 {
+    env::var("STATUS_RETENTION_DAYS")
+        .unwrap_or_else(|_| "".to_string())
+        .parse::<i64>()
+        .unwrap_or(90)
+        .max(1)
+}
+
+/// Where status samples are kept while the database is unavailable
+pub fn status_spool_path() -> String {
+    env::var("STATUS_SPOOL_PATH").unwrap_or_else(|_| "status_spool.jsonl".to_string())
+}
+
+/// How many status samples the spool can hold, new samples are dropped once it is full
+pub fn status_spool_size() -> usize {
+    env::var("STATUS_SPOOL_SIZE")
+        .unwrap_or_else(|_| "".to_string())
+        .parse::<usize>()
+        .unwrap_or(100_000)
+}
+
+/// Projects with a certificate that expires in fewer days than this are shown as degraded
+pub fn certificate_warning_days() -> i64 {
+    env::var("CERT_WARNING_DAYS")
+        .unwrap_or_else(|_| "".to_string())
+        .parse::<i64>()
+        .unwrap_or(14)
+}
+
+/// The name of the location that checks run from, shown next to each result
+pub fn location() -> String {
+    env::var("LOCATION").unwrap_or_else(|_| "local".to_string())
+}
+
+/// The location that an agent reports from, its hostname if not set so that agents don't
+/// report under the same location as the server
+pub fn agent_location() -> Option<String> {
+    env::var("LOCATION")
+        .or_else(|_| env::var("HOSTNAME"))
+        .or_else(|_| std::fs::read_to_string("/etc/hostname"))
+        .ok()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+}
+
+/// The shared secret that agents use to report results, agents are disabled if not set
+pub fn agent_token() -> Option<String> {
+    env::var("AGENT_TOKEN").ok().filter(|t| !t.is_empty())
+}
+
+/// Whether an agent will run script checks, off by default so that the central instance
+/// can't run commands on every agent
+pub fn agent_allow_scripts() -> bool {
+    env::var("AGENT_ALLOW_SCRIPTS").unwrap_or_else(|_| "false".to_string()) == "true"
+}
+
+/// The url of the central instance that an agent reports to
+pub fn agent_server() -> Option<String> {
+    env::var("AGENT_SERVER").ok().filter(|s| !s.is_empty())
+}
+
+/// How many locations must agree that a project is down, a majority if not set
+pub fn check_quorum() -> Option<usize> {
+    env::var("CHECK_QUORUM")
+        .ok()
+        .and_then(|q| q.parse::<usize>().ok())
+}
+
+/// Results from a location older than this are ignored when deciding if a project is down
+pub fn location_stale_after() -> chrono::Duration {
+    chrono::Duration::minutes(
+        env::var("LOCATION_STALE_MINUTES")
+            .unwrap_or_else(|_| "".to_string())
+            .parse::<i64>()
+            .unwrap_or(10),
+    )
+}
+
 pub fn get_email_addr() -> String {
     env::var("ALERT_EMAIL").unwrap_or_else(|_| "".to_string())
 }
 
+/// Addresses that the monthly availability report is emailed to, comma separated
+pub fn report_emails() -> Vec<String> {
+    env::var("REPORT_EMAILS")
+        .unwrap_or_else(|_| "".to_string())
+        .split(',')
+        .map(|email| email.trim().to_string())
+        .filter(|email| !email.is_empty())
+        .collect()
+}
+
 pub fn smtp_username() -> String {
     env::var("SMTP_USERNAME").unwrap_or_else(|_| "".to_string())
 }
@@ -114,7 +211,7 @@ pub fn insecure() -> bool {
 }
 
 pub struct PersistedSettings {
-    db: Database,
+    repo: SettingsRepositoryBox,
 }
 
 pub const CUSTOM_SCRIPT: &str = "CUSTOM_SCRIPT";
@@ -122,16 +219,11 @@ pub const CUSTOM_STYLE: &str = "CUSTOM_STYLE";
 pub const CUSTOM_HTML: &str = "CUSTOM_HTML";
 
 impl PersistedSettings {
-    pub fn new(db: Database) -> Self {
-        Self { db }
+    pub fn new(repo: SettingsRepositoryBox) -> Self {
+        Self { repo }
     }
 
     pub fn get_setting(&self, name: &str) -> String {
-        use crate::schema::settings;
-        let setting: Vec<Setting> = settings::table
-            .filter(settings::dsl::name.eq(name))
-            .load::<Setting>(&mut self.db.get().unwrap())
-            .unwrap();
-        setting.first().map(|f| f.value.clone()).unwrap_or_default()
+        self.repo.get_setting(name).unwrap().unwrap_or_default()
     }
 }
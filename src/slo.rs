@@ -0,0 +1,477 @@
+use crate::checks::latency::latency_bucket;
+use crate::data::rollup_repository::RollupRepository;
+use crate::data::status_repository::StatusRepository;
+use crate::models::{NewSlo, Project, Slo, Status, StatusRollup};
+use crate::rollup::RollupPeriod;
+use crate::uptime;
+use crate::uptime::max_sample_gap;
+use chrono::{Duration, NaiveDateTime};
+use std::ops::Add;
+
+/// The longest window an objective can be measured over
+pub const MAX_WINDOW_DAYS: i32 = 90;
+
+impl NewSlo {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        // A 100% objective has no error budget to burn
+        anyhow::ensure!(
+            self.objective > 0.0 && self.objective < 100.0,
+            "The objective must be between 0 and 100%"
+        );
+        anyhow::ensure!(
+            (1..=MAX_WINDOW_DAYS).contains(&self.window_days),
+            "The window must be between 1 and {MAX_WINDOW_DAYS} days"
+        );
+        anyhow::ensure!(
+            self.latency_threshold_ms.is_none_or(|t| t > 0),
+            "The latency threshold must be a positive number of milliseconds"
+        );
+        Ok(())
+    }
+}
+
+/// Good and total milliseconds for availability objectives, or good and total checks for latency
+/// objectives
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct SliCounts {
+    good: i64,
+    total: i64,
+}
+
+impl SliCounts {
+    fn ratio(self) -> Option<f64> {
+        (self.total > 0).then(|| self.good as f64 / self.total as f64)
+    }
+}
+
+impl Add for SliCounts {
+    type Output = SliCounts;
+
+    fn add(self, other: SliCounts) -> SliCounts {
+        SliCounts {
+            good: self.good + other.good,
+            total: self.total + other.total,
+        }
+    }
+}
+
+/// Response times are compared by their latency bucket, so that raw samples and rollups agree
+fn fast_enough(status: &Status, threshold: i32) -> bool {
+    status.is_success() && latency_bucket(status.time) <= threshold
+}
+
+fn sample_counts(
+    slo: &Slo,
+    timeline: &[Status],
+    from: NaiveDateTime,
+    max_gap: Duration,
+    now: NaiveDateTime,
+) -> SliCounts {
+    match slo.latency_threshold_ms {
+        None => {
+            let (up, down) = uptime::up_and_down_since(timeline, from, max_gap, now);
+            SliCounts {
+                good: up.num_milliseconds(),
+                total: (up + down).num_milliseconds(),
+            }
+        }
+        Some(threshold) => {
+            let checks = timeline
+                .iter()
+                .filter(|s| s.created >= from && s.created <= now)
+                .collect::<Vec<_>>();
+            SliCounts {
+                good: checks.iter().filter(|s| fast_enough(s, threshold)).count() as i64,
+                total: checks.len() as i64,
+            }
+        }
+    }
+}
+
+/// Rollups from before latency histograms were kept can't be used for latency objectives
+fn rollup_counts(slo: &Slo, rollup: &StatusRollup) -> Option<SliCounts> {
+    match slo.latency_threshold_ms {
+        None => Some(SliCounts {
+            good: i64::from(rollup.up_seconds) * 1000,
+            total: (i64::from(rollup.up_seconds) + i64::from(rollup.down_seconds)) * 1000,
+        }),
+        Some(threshold) => Some(SliCounts {
+            good: rollup
+                .histogram()?
+                .range(..=threshold)
+                .map(|(_, count)| i64::from(*count))
+                .sum(),
+            total: i64::from(rollup.samples),
+        }),
+    }
+}
+
+/// The share of good time, or good checks for latency objectives, between `from` and `now`.
+/// `None` if nothing is known about that time
+pub fn sli(
+    slo: &Slo,
+    timeline: &[Status],
+    from: NaiveDateTime,
+    max_gap: Duration,
+    now: NaiveDateTime,
+) -> Option<f64> {
+    sample_counts(slo, timeline, from, max_gap, now).ratio()
+}
+
+/// How fast the error budget is being spent, at 1 it would be spent exactly by the end of the
+/// window
+pub fn burn_rate(slo: &Slo, sli: f64) -> f64 {
+    (1.0 - sli) / (1.0 - slo.objective / 100.0)
+}
+
+/// The hourly rollups that cover the window of an objective
+fn rollups_in_window<'a>(
+    slo: &Slo,
+    rollups: &'a [StatusRollup],
+    now: NaiveDateTime,
+) -> impl Iterator<Item = &'a StatusRollup> {
+    let from = now - slo.window();
+    rollups.iter().filter(move |r| {
+        r.period_seconds == RollupPeriod::Hour.seconds()
+            && r.period_start >= from
+            && r.period_end() <= now
+    })
+}
+
+/// When the raw samples take over from the hourly rollups within the window of an objective
+fn rolled_up_until(slo: &Slo, rollups: &[StatusRollup], now: NaiveDateTime) -> NaiveDateTime {
+    rollups_in_window(slo, rollups, now)
+        .map(StatusRollup::period_end)
+        .max()
+        .unwrap_or(now - slo.window())
+}
+
+/// The share of the error budget left over the whole window, negative once it is overspent.
+/// Measured from the hourly rollups within the window and the raw samples since the latest one,
+/// so the window can be longer than raw samples are kept for
+pub fn remaining_budget(
+    slo: &Slo,
+    rollups: &[StatusRollup],
+    timeline: &[Status],
+    max_gap: Duration,
+    now: NaiveDateTime,
+) -> Option<f64> {
+    let recent = sample_counts(
+        slo,
+        timeline,
+        rolled_up_until(slo, rollups, now),
+        max_gap,
+        now,
+    );
+
+    rollups_in_window(slo, rollups, now)
+        .filter_map(|r| rollup_counts(slo, r))
+        .fold(recent, |total, counts| total + counts)
+        .ratio()
+        .map(|sli| 1.0 - burn_rate(slo, sli))
+}
+
+/// Fires when both windows are burning fast enough to spend `budget_spent` of the error budget
+/// within the long window, the short window makes sure the alert stops soon after a fix
+#[derive(Debug, PartialEq)]
+pub struct BurnRateAlert {
+    pub long_minutes: i64,
+    pub short_minutes: i64,
+    pub budget_spent: f64,
+}
+
+pub const BURN_RATE_ALERTS: [BurnRateAlert; 2] = [
+    // 2% of the budget within an hour, a burn rate of 14.4 for a 30 day window
+    BurnRateAlert {
+        long_minutes: 60,
+        short_minutes: 5,
+        budget_spent: 0.02,
+    },
+    // 5% of the budget within six hours, a burn rate of 6 for a 30 day window
+    BurnRateAlert {
+        long_minutes: 360,
+        short_minutes: 30,
+        budget_spent: 0.05,
+    },
+];
+
+impl BurnRateAlert {
+    /// The burn rate that both windows have to reach
+    pub fn threshold(&self, slo: &Slo) -> f64 {
+        self.budget_spent * slo.window().num_minutes() as f64 / self.long_minutes as f64
+    }
+}
+
+/// The first burn rate alert that is firing, along with the burn rate over its long window
+pub fn burning(
+    slo: &Slo,
+    timeline: &[Status],
+    max_gap: Duration,
+    now: NaiveDateTime,
+) -> Option<(&'static BurnRateAlert, f64)> {
+    BURN_RATE_ALERTS.iter().find_map(|alert| {
+        let threshold = alert.threshold(slo);
+        let burn_rate_since = |minutes| {
+            sli(
+                slo,
+                timeline,
+                now - Duration::minutes(minutes),
+                max_gap,
+                now,
+            )
+            .map(|sli| burn_rate(slo, sli))
+        };
+
+        let long = burn_rate_since(alert.long_minutes)?;
+        let short = burn_rate_since(alert.short_minutes)?;
+        (long >= threshold && short >= threshold).then_some((alert, long))
+    })
+}
+
+/// The error budget of an objective, for showing on the dashboard
+pub struct ErrorBudget {
+    pub slo: Slo,
+    pub remaining: Option<f64>,
+}
+
+impl ErrorBudget {
+    /// Work out the error budget left from the hourly rollups over the window of the objective and
+    /// the raw samples since the latest one
+    pub fn load(
+        rollup_repo: &dyn RollupRepository,
+        status_repo: &dyn StatusRepository,
+        slo: &Slo,
+        project: &Project,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<Self> {
+        let max_gap = max_sample_gap(project);
+        let rollups = rollup_repo.get_project_rollups_since(
+            project.id,
+            RollupPeriod::Hour,
+            now - slo.window(),
+        )?;
+        let timeline = status_repo
+            .get_project_timeline(project.id, rolled_up_until(slo, &rollups, now) - max_gap)?;
+
+        Ok(Self {
+            remaining: remaining_budget(slo, &rollups, &timeline, max_gap, now),
+            slo: slo.clone(),
+        })
+    }
+
+    pub fn formatted_remaining(&self) -> String {
+        self.remaining
+            .map(|r| format!("{:.1}%", r * 100.0))
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.remaining.is_some_and(|r| r <= 0.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::{NewSlo, Slo, Status, StatusRollup, fixtures};
+    use crate::slo::{BURN_RATE_ALERTS, burning, remaining_budget, sli};
+    use chrono::{Duration, NaiveDateTime};
+
+    fn now() -> NaiveDateTime {
+        fixtures::at(2020, 9, 25, 12, 0)
+    }
+
+    fn slo(objective: f64, latency_threshold_ms: Option<i32>) -> Slo {
+        Slo {
+            id: 0,
+            created: now(),
+            project: 0,
+            objective,
+            window_days: 30,
+            latency_threshold_ms,
+            burning: false,
+        }
+    }
+
+    /// A sample every minute for the last `minutes`, failing when `failing` says so
+    fn timeline(minutes: i64, failing: impl Fn(i64) -> bool) -> Vec<Status> {
+        (0..minutes)
+            .map(|minute| fixtures::status(0, now() - Duration::minutes(minute), !failing(minute)))
+            .collect()
+    }
+
+    #[test]
+    fn availability_sli() {
+        // The latest sample hasn't held for any time yet
+        let timeline = timeline(101, |minute| (1..=10).contains(&minute));
+
+        let sli = sli(
+            &slo(99.9, None),
+            &timeline,
+            now() - Duration::minutes(100),
+            Duration::minutes(5),
+            now(),
+        );
+        assert_eq!(sli, Some(0.9));
+    }
+
+    #[test]
+    fn latency_sli() {
+        let mut timeline = timeline(10, |minute| minute == 0);
+        timeline[1].time = 600;
+
+        let sli = sli(
+            &slo(99.0, Some(500)),
+            &timeline,
+            now() - Duration::minutes(10),
+            Duration::minutes(5),
+            now(),
+        );
+        assert_eq!(sli, Some(0.8));
+    }
+
+    #[test]
+    fn budget() {
+        let slo = slo(90.0, None);
+
+        let remaining = remaining_budget(
+            &slo,
+            &[],
+            &timeline(101, |m| (1..=5).contains(&m)),
+            Duration::minutes(5),
+            now(),
+        );
+        assert!((remaining.unwrap() - 0.5).abs() < 1e-9);
+
+        let overspent = remaining_budget(
+            &slo,
+            &[],
+            &timeline(100, |m| m < 20),
+            Duration::minutes(5),
+            now(),
+        );
+        assert!(overspent.unwrap() < 0.0);
+
+        assert_eq!(
+            remaining_budget(&slo, &[], &[], Duration::minutes(5), now()),
+            None
+        );
+    }
+
+    /// Hourly rollups starting 20 days ago, long after the raw samples have been pruned
+    fn rollups(hours: i64, rollup: impl Fn(i64, &mut StatusRollup)) -> Vec<StatusRollup> {
+        (0..hours)
+            .map(|hour| {
+                let start = now() - Duration::days(20) + Duration::hours(hour);
+                let mut r = fixtures::rollup(0, start);
+                rollup(hour, &mut r);
+                r
+            })
+            .collect()
+    }
+
+    #[test]
+    fn budget_from_rollups() {
+        let slo = slo(90.0, None);
+        // Down for one hour in twenty
+        let rollups = rollups(20, |hour, r| {
+            if hour == 0 {
+                r.down_seconds = 3600;
+            } else {
+                r.up_seconds = 3600;
+            }
+        });
+
+        let remaining = remaining_budget(&slo, &rollups, &[], Duration::minutes(5), now());
+        assert!((remaining.unwrap() - 0.5).abs() < 1e-9);
+
+        // Raw samples since the rollups count too, an hour up at the end
+        let recent = timeline(61, |_| false);
+        let remaining = remaining_budget(&slo, &rollups, &recent, Duration::minutes(5), now());
+        assert!((remaining.unwrap() - (1.0 - (1.0 / 21.0) / 0.1)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn latency_budget_from_rollups() {
+        let slo = slo(90.0, Some(500));
+        let rollups = rollups(2, |hour, r| {
+            r.samples = 10;
+            r.failures = 1;
+            r.latency_histogram = if hour == 0 {
+                Some(r#"{"100":8,"600":1}"#.to_string())
+            } else {
+                // Rolled up before latencies were kept
+                None
+            };
+        });
+
+        let remaining = remaining_budget(&slo, &rollups, &[], Duration::minutes(5), now());
+        assert!((remaining.unwrap() + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn burn_rate_thresholds() {
+        assert!((BURN_RATE_ALERTS[0].threshold(&slo(99.9, None)) - 14.4).abs() < 1e-9);
+        assert!((BURN_RATE_ALERTS[1].threshold(&slo(99.9, None)) - 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn burning_fast() {
+        let (alert, rate) = burning(
+            &slo(99.9, None),
+            &timeline(420, |minute| minute < 10),
+            Duration::minutes(5),
+            now(),
+        )
+        .unwrap();
+
+        assert_eq!(alert, &BURN_RATE_ALERTS[0]);
+        assert!(rate > 14.4);
+    }
+
+    #[test]
+    fn not_burning_once_fixed() {
+        // Failed for a while a few hours ago, the long window still sees it but the short doesn't
+        let timeline = timeline(420, |minute| (180..190).contains(&minute));
+
+        assert_eq!(
+            burning(&slo(99.9, None), &timeline, Duration::minutes(5), now()),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_slo() {
+        let valid = NewSlo {
+            project: 0,
+            objective: 99.9,
+            window_days: 30,
+            latency_threshold_ms: None,
+        };
+        assert!(valid.validate().is_ok());
+
+        assert!(
+            NewSlo {
+                objective: 100.0,
+                ..valid.clone()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            NewSlo {
+                window_days: 365,
+                ..valid.clone()
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            NewSlo {
+                latency_threshold_ms: Some(0),
+                ..valid
+            }
+            .validate()
+            .is_err()
+        );
+    }
+}
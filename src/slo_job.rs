@@ -0,0 +1,192 @@
+use crate::data::project_repository::ProjectRepository;
+use crate::data::rollup_repository::RollupRepository;
+use crate::data::slo_repository::SloRepository;
+use crate::data::status_repository::StatusRepository;
+use crate::db::Database;
+use crate::models::{Project, Slo};
+use crate::notifications::webhook::WebhookPayload;
+use crate::notifications::{Notification, Notifiers};
+use crate::slo;
+use crate::slo::{BURN_RATE_ALERTS, BurnRateAlert, ErrorBudget};
+use crate::uptime::max_sample_gap;
+use chrono::{NaiveDateTime, Utc};
+use std::time::Duration;
+use tracing::error;
+
+/// How often error budgets are checked for burn rate alerts, the shortest alert window is 5
+/// minutes
+const SLO_JOB_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Everything needed to check the SLOs
+pub trait SloJobRepository:
+    ProjectRepository + SloRepository + StatusRepository + RollupRepository
+{
+}
+
+impl<T: ProjectRepository + SloRepository + StatusRepository + RollupRepository> SloJobRepository
+    for T
+{
+}
+
+/// Periodically check the error budget of every SLO, telling subscribers when one starts to burn
+/// too fast
+pub async fn run_slo_job(db: Database, notifiers: Notifiers) {
+    let _span = tracing::info_span!("SLO Job");
+
+    loop {
+        actix_rt::time::sleep(SLO_JOB_INTERVAL).await;
+
+        if let Err(e) = check_slos(&db, &notifiers).await {
+            error!("Failed to check SLOs: {e:?}");
+        }
+    }
+}
+
+async fn check_slos(db: &dyn SloJobRepository, notifiers: &Notifiers) -> anyhow::Result<()> {
+    let slos = db.get_all_slos()?;
+    if slos.is_empty() {
+        return Ok(());
+    }
+
+    let projects_list = db.get_all_projects()?;
+    let longest_alert = BURN_RATE_ALERTS
+        .iter()
+        .map(|a| chrono::Duration::minutes(a.long_minutes))
+        .max()
+        .unwrap_or_default();
+    let now = Utc::now().naive_utc();
+
+    for slo in slos {
+        let Some(project) = projects_list.iter().find(|p| p.id == slo.project) else {
+            continue;
+        };
+
+        // One SLO failing to load shouldn't stop the others from being checked
+        if let Err(e) = check_slo(db, notifiers, project, &slo, longest_alert, now).await {
+            error!(
+                "Failed to check SLO '{}' in project {}: {e:?}",
+                slo.describe(),
+                project.name
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn check_slo(
+    db: &dyn SloJobRepository,
+    notifiers: &Notifiers,
+    project: &Project,
+    slo: &Slo,
+    longest_alert: chrono::Duration,
+    now: NaiveDateTime,
+) -> anyhow::Result<()> {
+    let max_gap = max_sample_gap(project);
+
+    // Go back a little further, the sample before the window still counts until the next one
+    let timeline = db.get_project_timeline(project.id, now - longest_alert - max_gap)?;
+
+    match slo::burning(slo, &timeline, max_gap, now) {
+        Some((alert, burn_rate)) if !slo.burning => {
+            notify_burning(db, notifiers, project, slo, alert, burn_rate, now).await?;
+            db.set_burning(slo.id, true)?;
+        }
+        None if slo.burning => {
+            tracing::info!(
+                "Error budget for '{}' in project {} is no longer burning too fast",
+                slo.describe(),
+                project.name
+            );
+            db.set_burning(slo.id, false)?;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+async fn notify_burning(
+    db: &dyn SloJobRepository,
+    notifiers: &Notifiers,
+    project: &Project,
+    slo: &Slo,
+    alert: &BurnRateAlert,
+    burn_rate: f64,
+    now: NaiveDateTime,
+) -> anyhow::Result<()> {
+    let budget = ErrorBudget::load(db, db, slo, project, now)?;
+
+    let message = format!(
+        "Error budget for '{}' is burning {burn_rate:.1}x faster than it can sustain over the last {} minutes, {} of the budget is left",
+        slo.describe(),
+        alert.long_minutes,
+        budget.formatted_remaining()
+    );
+
+    notifiers
+        .notify_all_subscribers(Notification {
+            title: format!("Error budget burning in project '{}'", project.name),
+            email_body: format!("{message}\n"),
+            sms_body: format!("YouUp, Project '{}': {message}", project.name),
+            payload: WebhookPayload {
+                project_id: project.id,
+                project_name: project.name.clone(),
+                status_code: None,
+                message: Some(message),
+                time: Utc::now().format("%+").to_string(),
+                outage_started: None,
+                outage_seconds: None,
+            },
+        })
+        .await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app;
+    use crate::data::in_memory::InMemoryDatabase;
+    use crate::data::slo_repository::SloRepository;
+    use crate::models::{NewSlo, fixtures};
+    use crate::notifications::mailer::Mailer;
+    use crate::notifications::sms::SMSNotifier;
+    use crate::notifications::webhook::WebhookNotifier;
+    use crate::slo_job::check_slos;
+    use chrono::{Duration, Utc};
+    use std::sync::Arc;
+
+    #[actix_rt::test]
+    async fn burning_slos_are_marked() {
+        let repos = InMemoryDatabase::default();
+        repos.tables().projects.push(fixtures::project(1));
+        repos
+            .create_slo(NewSlo {
+                project: 1,
+                objective: 99.9,
+                window_days: 30,
+                latency_threshold_ms: None,
+            })
+            .unwrap();
+        // Failing for the last 10 minutes
+        let now = Utc::now().naive_utc();
+        for minute in 0..420 {
+            repos.tables().status.push(fixtures::status(
+                1,
+                now - Duration::minutes(minute),
+                minute >= 10,
+            ));
+        }
+        let notifiers = app::notifiers(
+            &repos,
+            &Arc::new(Mailer::default()),
+            &Arc::new(SMSNotifier::default()),
+            &Arc::new(WebhookNotifier::default()),
+        );
+
+        check_slos(&repos, &notifiers).await.unwrap();
+
+        assert!(repos.get_all_slos().unwrap()[0].burning);
+    }
+}
@@ -0,0 +1,308 @@
+use crate::models::NewStatus;
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+/// How many spooled samples are inserted at a time when the database comes back
+pub const REPLAY_BATCH_SIZE: usize = 500;
+
+/// An append-only file of status samples that couldn't be stored, one JSON sample per line.
+/// Samples keep the time they were taken so they land in the right place once replayed
+pub struct StatusSpool {
+    path: PathBuf,
+    capacity: usize,
+    /// How many samples are in the file, counted when the spool is first used
+    len: Option<usize>,
+}
+
+impl StatusSpool {
+    pub fn new(path: impl Into<PathBuf>, capacity: usize) -> Self {
+        Self {
+            path: path.into(),
+            capacity,
+            len: None,
+        }
+    }
+
+    pub fn len(&mut self) -> anyhow::Result<usize> {
+        match self.len {
+            Some(len) => Ok(len),
+            None => {
+                let len = self.read()?.len();
+                self.len = Some(len);
+                Ok(len)
+            }
+        }
+    }
+
+    pub fn is_empty(&mut self) -> anyhow::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Add a sample to the end of the spool, returns false if it is full and the sample was dropped
+    pub fn append(&mut self, status: &NewStatus) -> anyhow::Result<bool> {
+        if self.len()? >= self.capacity {
+            return Ok(false);
+        }
+
+        let mut line = serde_json::to_string(status).context("Unable to serialize status")?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.path)
+            .context("Unable to open status spool")?;
+
+        // Finish off a line that was only partly written before a crash, so that it doesn't take
+        // this sample down with it
+        if file
+            .metadata()
+            .context("Unable to read status spool")?
+            .len()
+            > 0
+        {
+            let mut last = [0; 1];
+            file.seek(SeekFrom::End(-1))
+                .and_then(|_| file.read_exact(&mut last))
+                .context("Unable to read status spool")?;
+            if last[0] != b'\n' {
+                line.insert(0, '\n');
+            }
+        }
+
+        file.write_all(line.as_bytes())
+            .context("Unable to write to status spool")?;
+        file.sync_data().context("Unable to sync status spool")?;
+
+        self.len = self.len.map(|len| len + 1);
+        Ok(true)
+    }
+
+    /// Every sample in the spool, oldest first. Lines that can't be read, such as one that was
+    /// only partly written before a crash, are skipped
+    pub fn read(&self) -> anyhow::Result<Vec<NewStatus>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).context("Unable to open status spool"),
+        };
+
+        let mut statuses = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.context("Unable to read status spool")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<NewStatus>(&line) {
+                Ok(status) => statuses.push(status),
+                Err(e) => tracing::warn!("Skipping unreadable spooled status: {e:?}"),
+            }
+        }
+        Ok(statuses)
+    }
+
+    /// When the oldest sample in the spool was taken
+    pub fn oldest(&mut self) -> anyhow::Result<Option<NaiveDateTime>> {
+        if self.is_empty()? {
+            return Ok(None);
+        }
+        Ok(self.read()?.iter().map(|s| s.created).min())
+    }
+
+    /// Insert the spooled samples `REPLAY_BATCH_SIZE` at a time, oldest first. Samples that
+    /// haven't been inserted when a batch fails are kept for next time. The spool is only locked
+    /// while it is read and written, so samples can still be spooled during a slow insert.
+    /// Returns how many samples were inserted
+    pub fn replay(
+        spool: &Mutex<Self>,
+        mut insert: impl FnMut(&[NewStatus]) -> anyhow::Result<()>,
+    ) -> anyhow::Result<usize> {
+        let statuses = {
+            let mut spool = Self::lock(spool)?;
+            if spool.is_empty()? {
+                return Ok(0);
+            }
+            spool.read()?
+        };
+
+        let mut inserted = 0;
+        for batch in statuses.chunks(REPLAY_BATCH_SIZE) {
+            if let Err(e) = insert(batch) {
+                tracing::warn!("Failed to replay spooled status: {e:?}");
+                break;
+            }
+            inserted += batch.len();
+        }
+
+        if inserted > 0 {
+            // Anything spooled while inserting is after the samples that were read
+            let mut spool = Self::lock(spool)?;
+            let statuses = spool.read()?;
+            spool.replace(&statuses[inserted.min(statuses.len())..])?;
+        }
+        Ok(inserted)
+    }
+
+    pub fn lock(spool: &Mutex<Self>) -> anyhow::Result<MutexGuard<'_, Self>> {
+        spool
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Status spool lock is poisoned"))
+    }
+
+    /// Swap the contents of the spool for `statuses`, written to a new file first so that a crash
+    /// doesn't lose the samples that are left
+    fn replace(&mut self, statuses: &[NewStatus]) -> anyhow::Result<()> {
+        if statuses.is_empty() {
+            match std::fs::remove_file(&self.path) {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).context("Unable to remove status spool"),
+            }
+        } else {
+            let temp = self.path.with_extension("tmp");
+            let mut file = File::create(&temp).context("Unable to create status spool")?;
+            for status in statuses {
+                let line = serde_json::to_string(status).context("Unable to serialize status")?;
+                writeln!(file, "{line}").context("Unable to write to status spool")?;
+            }
+            file.sync_all().context("Unable to sync status spool")?;
+            std::fs::rename(&temp, &self.path).context("Unable to replace status spool")?;
+        }
+
+        self.len = Some(statuses.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::NewStatus;
+    use crate::spool::{REPLAY_BATCH_SIZE, StatusSpool};
+    use chrono::NaiveDate;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    fn spool_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("you_up_spool_{}_{name}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn status(minute: u32) -> NewStatus {
+        NewStatus {
+            project: 1,
+            time: 100,
+            status_code: Some(200),
+            created: NaiveDate::from_ymd_opt(2020, 9, 25)
+                .unwrap()
+                .and_hms_opt(10, minute % 60, 0)
+                .unwrap(),
+            success: true,
+            message: None,
+            degraded: false,
+            steps: None,
+            location: "local".to_string(),
+        }
+    }
+
+    #[test]
+    fn append_and_read() {
+        let path = spool_path("append");
+        let mut spool = StatusSpool::new(&path, 2);
+
+        assert!(spool.append(&status(1)).unwrap());
+        assert!(spool.append(&status(2)).unwrap());
+        // Full, the sample is dropped
+        assert!(!spool.append(&status(3)).unwrap());
+
+        // Samples survive a restart with their original times
+        let mut reopened = StatusSpool::new(&path, 2);
+        assert_eq!(reopened.len().unwrap(), 2);
+        assert_eq!(reopened.read().unwrap(), vec![status(1), status(2)]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn skips_partial_lines() {
+        let path = spool_path("partial");
+        let mut spool = StatusSpool::new(&path, 10);
+        spool.append(&status(1)).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"project\":1,\"ti")
+            .unwrap();
+
+        assert_eq!(spool.read().unwrap(), vec![status(1)]);
+
+        // Samples spooled after the partial line are still readable
+        spool.append(&status(2)).unwrap();
+        assert_eq!(spool.read().unwrap(), vec![status(1), status(2)]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn replay_in_batches() {
+        let path = spool_path("replay");
+        let spool = Mutex::new(StatusSpool::new(&path, REPLAY_BATCH_SIZE * 3));
+        for minute in 0..(REPLAY_BATCH_SIZE * 2 + 1) {
+            spool
+                .lock()
+                .unwrap()
+                .append(&status(minute as u32))
+                .unwrap();
+        }
+        assert_eq!(
+            spool.lock().unwrap().oldest().unwrap(),
+            Some(status(0).created)
+        );
+
+        // The database goes away after the first batch
+        let mut batches = Vec::new();
+        let inserted = StatusSpool::replay(&spool, |batch| {
+            if batches.is_empty() {
+                batches.push(batch.len());
+                Ok(())
+            } else {
+                anyhow::bail!("Database unavailable")
+            }
+        })
+        .unwrap();
+        assert_eq!(inserted, REPLAY_BATCH_SIZE);
+        let mut unlocked = spool.lock().unwrap();
+        assert_eq!(unlocked.len().unwrap(), REPLAY_BATCH_SIZE + 1);
+        assert_eq!(
+            unlocked.read().unwrap().first(),
+            Some(&status(REPLAY_BATCH_SIZE as u32))
+        );
+        drop(unlocked);
+
+        // Samples can be spooled while a batch is being inserted, and are kept for next time
+        let mut spooled = false;
+        let inserted = StatusSpool::replay(&spool, |_| {
+            if !spooled {
+                spooled = spool.try_lock().unwrap().append(&status(59))?;
+            }
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(inserted, REPLAY_BATCH_SIZE + 1);
+        assert_eq!(spool.lock().unwrap().read().unwrap(), vec![status(59)]);
+
+        let inserted = StatusSpool::replay(&spool, |_| Ok(())).unwrap();
+        assert_eq!(inserted, 1);
+        assert!(spool.lock().unwrap().is_empty().unwrap());
+        assert_eq!(spool.lock().unwrap().oldest().unwrap(), None);
+        assert!(!path.exists());
+    }
+}
@@ -1,4 +1,153 @@
+use crate::models::Outage;
+use crate::{settings, time_formatter};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Downtime {
     pub duration: String,
 }
+
+/// Outages that are too short are left out of the downtime shown for each day
+fn is_shown(outage: &Outage, now: NaiveDateTime) -> bool {
+    outage.duration(now).num_minutes() > settings::get_minimum_downtime_minutes()
+}
+
+impl Downtime {
+    /// The part of each outage that falls on the given day, oldest first. Outages that span
+    /// midnight are shown on every day they cover, outages that are too short are left out
+    pub fn on_day(outages: &[Outage], day: NaiveDate, now: NaiveDateTime) -> Vec<Downtime> {
+        let start_of_day = day.and_hms_opt(0, 0, 0).unwrap();
+        let end_of_day = start_of_day + Duration::days(1);
+
+        outages
+            .iter()
+            .rev()
+            .filter(|o| is_shown(o, now))
+            .map(|o| o.overlap(start_of_day, end_of_day, now))
+            .filter(|d| *d > Duration::zero())
+            .map(|d| Downtime {
+                duration: time_formatter::format_duration(&d),
+            })
+            .collect()
+    }
+
+    /// How long the project was down on the given day, across the same outages as `on_day`
+    pub fn total_on_day(outages: &[Outage], day: NaiveDate, now: NaiveDateTime) -> Duration {
+        let start_of_day = day.and_hms_opt(0, 0, 0).unwrap();
+        let end_of_day = start_of_day + Duration::days(1);
+
+        outages
+            .iter()
+            .filter(|o| is_shown(o, now))
+            .map(|o| o.overlap(start_of_day, end_of_day, now))
+            .fold(Duration::zero(), |total, d| total + d)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::{Outage, fixtures};
+    use crate::template::index::downtime::Downtime;
+    use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        fixtures::at(2020, 9, day, hour, minute)
+    }
+
+    fn outage(started: NaiveDateTime, ended: Option<NaiveDateTime>) -> Outage {
+        fixtures::outage(0, started, ended)
+    }
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2020, 9, day).unwrap()
+    }
+
+    #[test]
+    fn compute_simple_downtime() {
+        assert!(Downtime::on_day(&[], day(25), at(25, 12, 0)).is_empty());
+    }
+
+    #[test]
+    fn compute_downtime() {
+        let x = Downtime::on_day(
+            &[outage(at(25, 10, 0), Some(at(25, 10, 59)))],
+            day(25),
+            at(25, 12, 0),
+        );
+
+        assert_eq!(x.first().unwrap().duration, "59 minutes");
+        assert_eq!(x.len(), 1);
+    }
+
+    #[test]
+    fn compute_downtime_end_of_day() {
+        let x = Downtime::on_day(&[outage(at(25, 1, 0), None)], day(25), at(26, 12, 0));
+
+        assert_eq!(x.first().unwrap().duration, "23 hours");
+        assert_eq!(x.len(), 1);
+    }
+
+    #[test]
+    fn compute_downtime_never_up() {
+        let x = Downtime::on_day(&[outage(at(24, 22, 0), None)], day(25), at(26, 12, 0));
+
+        assert_eq!(x.first().unwrap().duration, "24 hours");
+        assert_eq!(x.len(), 1);
+    }
+
+    #[test]
+    fn compute_downtime_ongoing() {
+        let x = Downtime::on_day(&[outage(at(25, 9, 0), None)], day(25), at(25, 10, 30));
+
+        assert_eq!(x.first().unwrap().duration, "1 hour 30 minutes");
+    }
+
+    #[test]
+    fn compute_downtime_over_midnight() {
+        let outages = [outage(at(25, 23, 0), Some(at(26, 1, 30)))];
+
+        assert_eq!(
+            Downtime::on_day(&outages, day(25), at(27, 0, 0))
+                .first()
+                .unwrap()
+                .duration,
+            "1 hour"
+        );
+        assert_eq!(
+            Downtime::on_day(&outages, day(26), at(27, 0, 0))
+                .first()
+                .unwrap()
+                .duration,
+            "1 hour 30 minutes"
+        );
+        assert!(Downtime::on_day(&outages, day(27), at(27, 0, 0)).is_empty());
+    }
+
+    #[test]
+    fn short_outages_are_ignored() {
+        let outages = [
+            outage(at(25, 10, 0), Some(at(25, 10, 1))),
+            outage(at(25, 23, 59), Some(at(26, 0, 30))),
+        ];
+
+        // The second outage is long enough, even though only a minute of it is on the first day
+        let x = Downtime::on_day(&outages, day(25), at(27, 0, 0));
+        assert_eq!(x.len(), 1);
+
+        assert_eq!(
+            Downtime::total_on_day(&outages, day(25), at(27, 0, 0)),
+            Duration::minutes(1)
+        );
+    }
+
+    #[test]
+    fn short_outages_are_left_out_of_the_total() {
+        let outages = [outage(at(25, 10, 0), Some(at(25, 10, 1)))];
+
+        assert!(Downtime::on_day(&outages, day(25), at(27, 0, 0)).is_empty());
+        assert_eq!(
+            Downtime::total_on_day(&outages, day(25), at(27, 0, 0)),
+            Duration::zero()
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use crate::models::Status;
+use crate::checks::StepTiming;
+use crate::models::{DailyStatusCounts, Status};
 use crate::project_status::ProjectStatusTypes;
 use crate::template::index::downtime::Downtime;
 use crate::template::template_tooltip::StatusTooltipTemplate;
@@ -6,7 +7,10 @@ use askama::Template;
 
 #[derive(Clone)]
 pub struct StatusDay {
+    /// Raw samples, only loaded for today and the hours that haven't been rolled up yet
     pub status: Vec<Status>,
+    /// How many samples were taken on the days before today
+    pub counts: Option<DailyStatusCounts>,
     pub date: String,
     pub downtime: Vec<Downtime>,
 }
@@ -14,10 +18,31 @@ pub struct StatusDay {
 impl StatusDay {
     pub fn get_overall_status(&self) -> ProjectStatusTypes {
         if self.status.is_empty() {
-            return ProjectStatusTypes::Unknown;
+            return match &self.counts {
+                Some(counts) if counts.samples > 0 => {
+                    if self.downtime.is_empty() {
+                        ProjectStatusTypes::Operational
+                    } else if counts.failures == counts.samples {
+                        ProjectStatusTypes::Failed
+                    } else {
+                        ProjectStatusTypes::Failing
+                    }
+                }
+                _ => ProjectStatusTypes::Unknown,
+            };
         }
         if self.downtime.is_empty() && !self.status.is_empty() {
-            return ProjectStatusTypes::Operational;
+            // Only the latest sample matters for degradation, it's not a problem if it has passed
+            return if self
+                .status
+                .first()
+                .map(|s| s.is_degraded())
+                .unwrap_or(false)
+            {
+                ProjectStatusTypes::Degraded
+            } else {
+                ProjectStatusTypes::Operational
+            };
         }
 
         let initial_status = if self.status.iter().all(|x| !x.is_success()) {
@@ -43,9 +68,39 @@ impl StatusDay {
             .expect("Unable to render tooltip")
     }
 
+    pub fn degraded_colour(&self) -> String {
+        ProjectStatusTypes::Degraded.get_colour()
+    }
+
+    pub fn degraded_reason(&self) -> Option<String> {
+        self.status
+            .iter()
+            .find(|s| s.is_degraded())
+            .and_then(|s| s.message.clone())
+    }
+
+    pub fn failure_reason(&self) -> Option<String> {
+        self.status
+            .iter()
+            .find(|s| !s.is_success())
+            .and_then(|s| s.message.clone())
+    }
+
+    /// Timings of each step in the latest sample, for multi step checks
+    pub fn latest_steps(&self) -> Vec<StepTiming> {
+        self.status
+            .first()
+            .map(|s| s.step_timings())
+            .unwrap_or_default()
+    }
+
     pub fn avg_request_time(&self) -> u32 {
         if self.status.is_empty() {
-            0
+            self.counts
+                .as_ref()
+                .and_then(|c| c.avg_latency_ms)
+                .and_then(|ms| u32::try_from(ms).ok())
+                .unwrap_or(0)
         } else {
             self.status.iter().map(|s| s.time).sum::<i32>() as u32 / (self.status.len() as u32)
         }
@@ -1,25 +1,23 @@
-use crate::data::project_repository::ProjectRepositoryData;
-use crate::data::status_repository::StatusRepositoryData;
-use crate::db::Database;
-use crate::diesel::BelongingToDsl;
-use crate::diesel::GroupedBy;
-use crate::models::{IncidentStatusType, IncidentStatusUpdate, Incidents, Project, Status};
+use crate::data::alert_repository::AlertRepository;
+use crate::data::incident_repository::{IncidentRepository, IncidentWithUpdates};
+use crate::data::outage_repository::OutageRepository;
+use crate::data::project_repository::ProjectRepository;
+use crate::data::rollup_repository::RollupRepository;
+use crate::data::status_repository::StatusRepository;
+use crate::models::{Project, Status};
 use crate::project_status::ProjectStatusTypes;
-use crate::schema::incidents::dsl::incidents;
-use crate::schema::{incident_status_type, incident_status_update};
+use crate::rollup::StatusHistory;
+use crate::settings;
 use crate::settings::{CUSTOM_HTML, CUSTOM_SCRIPT, CUSTOM_STYLE, PersistedSettings};
 use crate::template::index::downtime::Downtime;
 use crate::template::index::status_day::StatusDay;
 use crate::template::template_admin_login::AdminLogin;
-use crate::{get_db, get_pool, settings, time_formatter};
+use crate::uptime::ProjectUptime;
 use actix_identity::Identity;
 use actix_web::web::Data;
 use actix_web::{HttpResponse, get, head};
 use askama::Template;
-use chrono::{Duration, NaiveDateTime, Timelike, Utc};
-use diesel::dsl::sql;
-use diesel::sql_types::Bool;
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use chrono::{Duration, Timelike, Utc};
 use std::convert::TryInto;
 use std::ops::Sub;
 
@@ -39,113 +37,79 @@ impl IndexTemplate {
     pub fn is_operational_today(&self) -> bool {
         self.projects
             .iter()
-            .all(|p| p.today.get_overall_status() == ProjectStatusTypes::Operational)
+            .all(|p| p.current_status() == ProjectStatusTypes::Operational)
     }
 }
 
 pub struct IncidentDay {
     pub date: String,
-    pub incidents: Vec<(Incidents, Vec<(IncidentStatusUpdate, IncidentStatusType)>)>,
+    pub incidents: Vec<IncidentWithUpdates>,
 }
 
 pub struct ProjectStatus {
     pub project: Project,
     pub days: Vec<StatusDay>,
     pub today: StatusDay,
+    /// The latest sample from each location that is checking this project
+    pub locations: Vec<Status>,
+    /// Notifications are paused as the project keeps changing between up and down
+    pub flapping: bool,
+    pub uptime: ProjectUptime,
 }
 
-pub async fn compute_downtime_periods(status_on_day: &[Status]) -> Vec<Downtime> {
-    if !status_on_day.is_empty() && status_on_day.iter().all(|s| !s.is_success()) {
-        let first_day = status_on_day.first().unwrap().created;
-
-        // Get 00:00:00 on the next day
-        let end_of_first_day = first_day
-            .checked_add_signed(Duration::days(1))
-            .unwrap()
-            .with_hour(0)
-            .unwrap()
-            .with_minute(0)
-            .unwrap()
-            .with_second(0)
-            .unwrap();
-
-        // Find the duration between the last point that we have info about and the first point
-        // If the end of the day is in the future, then this downtime occurs today, and we will state that it is down until now
-        // If this day is in the past then we will assume it was down for the rest of the day
-        //TODO: we might want to check when it's up the next day
-        // also might want to look back to before the first result of the day?
-        let duration = Utc::now()
-            .naive_utc()
-            .min(end_of_first_day)
-            .signed_duration_since(first_day);
-
-        return vec![Downtime {
-            duration: time_formatter::format_duration(&duration),
-        }];
-    }
-
-    let mut downtime = Vec::with_capacity(status_on_day.len());
-    let mut downtime_period_start: Option<NaiveDateTime> = status_on_day
-        .first()
-        .map(|e| Some(e.created))
-        .unwrap_or(None);
-
-    for item in status_on_day.iter() {
-        if item.is_success() {
-            if let Some(tmp) = downtime_period_start {
-                let period_duration = tmp.signed_duration_since(item.created);
-
-                if period_duration.num_minutes() > settings::get_minimum_downtime_minutes() {
-                    downtime.push(Downtime {
-                        duration: time_formatter::format_duration(&period_duration),
-                    });
-                }
-                downtime_period_start = None;
-            }
+impl ProjectStatus {
+    pub fn current_status(&self) -> ProjectStatusTypes {
+        if self.flapping {
+            ProjectStatusTypes::Flapping
         } else {
-            // If we are currently down then we will skip until we find the next up
-            if downtime_period_start.is_none() {
-                downtime_period_start = Some(item.created)
-            }
+            self.today.get_overall_status()
         }
     }
-    if let Some(tmp) = downtime_period_start {
-        // If it was still down at the last reading of the day then assume it was down for the rest of that entire day
-
-        let time_of_first_request = status_on_day.first().map(|s| s.created).unwrap();
-
-        let end_of_day = time_of_first_request
-            .checked_add_signed(Duration::days(1))
-            .unwrap()
-            .with_hour(0)
-            .unwrap()
-            .with_minute(0)
-            .unwrap()
-            .with_second(0)
-            .unwrap();
-
-        // If this day was in the past then take its end_of_day however if we are considering today then define the end to be the current time
-        let clamped_end_of_day = end_of_day.min(Utc::now().naive_utc());
-
-        let period_duration = clamped_end_of_day.signed_duration_since(tmp);
-        if period_duration.num_minutes() > settings::get_minimum_downtime_minutes() {
-            downtime.push(Downtime {
-                duration: time_formatter::format_duration(&period_duration),
-            });
+
+    pub fn location_status(&self, status: &Status) -> ProjectStatusTypes {
+        if !status.is_success() {
+            ProjectStatusTypes::Failed
+        } else if status.is_degraded() {
+            ProjectStatusTypes::Degraded
+        } else {
+            ProjectStatusTypes::Operational
         }
     }
+}
 
-    downtime
+/// Everything that the status page shows
+pub trait IndexRepository:
+    ProjectRepository
+    + StatusRepository
+    + RollupRepository
+    + AlertRepository
+    + OutageRepository
+    + IncidentRepository
+{
 }
 
-pub async fn root(
-    pool: Database,
+impl<
+    T: ProjectRepository
+        + StatusRepository
+        + RollupRepository
+        + AlertRepository
+        + OutageRepository
+        + IncidentRepository,
+> IndexRepository for T
+{
+}
+
+pub type IndexRepositoryData = Data<Box<dyn IndexRepository>>;
+
+#[get("/")]
+pub async fn get_index(
     settings: Data<PersistedSettings>,
-    projects_repo: ProjectRepositoryData,
-    status_repo: StatusRepositoryData,
+    repos: IndexRepositoryData,
     identity: Option<Identity>,
 ) -> HttpResponse {
-    let projects_list = match projects_repo.get_all_enabled_projects() {
+    let repos = repos.get_ref().as_ref();
+
+    let projects_list = match repos.get_all_enabled_projects() {
         Ok(projects) => projects,
         Err(err) => {
             tracing::warn!("Failed to get projects: {:?}", err);
@@ -153,14 +117,50 @@ pub async fn root(
         }
     };
 
-    let status_list: Vec<_> = status_repo.get_status_last_30_days();
+    let latest_by_location = repos.get_latest_status_by_location().unwrap_or_else(|e| {
+        tracing::warn!("Failed to get status by location: {e:?}");
+        Vec::new()
+    });
+
+    let alerts = repos.get_all_alerts().unwrap_or_else(|e| {
+        tracing::warn!("Failed to get alerts: {e:?}");
+        Vec::new()
+    });
 
     let history_size = settings::get_history_size();
 
     let now = Utc::now();
 
+    let history = match StatusHistory::load(
+        repos,
+        repos,
+        &projects_list,
+        history_size.try_into().unwrap(),
+        now.naive_utc(),
+    ) {
+        Ok(history) => history,
+        Err(err) => {
+            tracing::warn!("Failed to get status history: {:?}", err);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let outages = repos
+        .get_outages_since(now.naive_utc() - Duration::days(history_size.try_into().unwrap()))
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to get outages: {e:?}");
+            Vec::new()
+        });
+
     let mut p = Vec::with_capacity(projects_list.len());
     for proj in projects_list {
+        let timeline = history.recent_timeline(proj.id);
+        let project_outages: Vec<_> = outages
+            .iter()
+            .filter(|o| o.project == proj.id)
+            .cloned()
+            .collect();
+
         let mut days: Vec<StatusDay> = Vec::with_capacity(history_size);
         for x in (0..history_size).rev() {
             let then = now
@@ -173,16 +173,17 @@ pub async fn root(
                 .unwrap()
                 .naive_utc();
 
-            let status_on_day: Vec<_> = status_list
+            let status_on_day: Vec<_> = timeline
                 .iter()
-                .filter(|s| s.project == proj.id && s.created.date() == then.date())
+                .filter(|s| s.created.date() == then.date())
                 .cloned()
                 .collect();
 
-            let downtime = compute_downtime_periods(status_on_day.as_slice()).await;
+            let downtime = Downtime::on_day(&project_outages, then.date(), now.naive_utc());
 
             days.push(StatusDay {
                 status: status_on_day,
+                counts: history.daily_counts(proj.id, then.date()),
                 date: then.format("%Y/%m/%d").to_string(),
                 downtime,
             });
@@ -190,33 +191,34 @@ pub async fn root(
 
         let today = days.last().unwrap().clone();
 
+        let locations = latest_by_location
+            .iter()
+            .filter(|s| s.project == proj.id)
+            .cloned()
+            .collect();
+        let flapping = alerts.iter().any(|a| a.project == proj.id && a.flapping);
+        let uptime = history.uptime(&proj, now.naive_utc());
+
         p.push(ProjectStatus {
             project: proj,
             days,
             today,
+            locations,
+            flapping,
+            uptime,
         })
     }
 
     let mut incident_days = Vec::with_capacity(10);
 
-    let mut pool = get_pool!(pool);
-
-    let all_incidents: Vec<_> = incidents
-        .filter(sql::<Bool>("created > DATE_SUB(NOW(), INTERVAL 10 day)"))
-        .load::<Incidents>(&mut pool)
-        .unwrap();
-
-    let incident_days_status = IncidentStatusUpdate::belonging_to(&all_incidents)
-        .order(incident_status_update::dsl::created.desc())
-        .inner_join(incident_status_type::table)
-        .filter(incident_status_type::dsl::id.eq(incident_status_update::dsl::id))
-        .load(&mut pool)
-        .unwrap()
-        .grouped_by(&all_incidents);
-    let incidents_and_status = all_incidents
-        .into_iter()
-        .zip(incident_days_status)
-        .collect::<Vec<_>>();
+    let incidents_and_status =
+        match repos.get_incidents_since(Utc::now().naive_utc() - Duration::days(10)) {
+            Ok(incidents_and_status) => incidents_and_status,
+            Err(err) => {
+                tracing::warn!("Failed to get incidents: {:?}", err);
+                return HttpResponse::InternalServerError().finish();
+            }
+        };
 
     for n in 0..10 {
         let date = Utc::now().sub(Duration::days(n));
@@ -248,131 +250,6 @@ pub async fn root(
     HttpResponse::Ok().body(template)
 }
 
-#[cfg(test)]
-mod test {
-    use crate::models::Status;
-    use crate::template::index::template_index::compute_downtime_periods;
-    use chrono::{TimeZone, Utc};
-    use std::ops::Sub;
-
-    #[actix_rt::test]
-    async fn compute_simple_downtime() {
-        let x = compute_downtime_periods(&[Status {
-            created: Utc::now().naive_utc(),
-            status_code: 200,
-            id: 0,
-            project: 0,
-            time: 0,
-        }])
-        .await;
-
-        assert!(x.is_empty())
-    }
-
-    #[actix_rt::test]
-    async fn compute_downtime() {
-        let x = compute_downtime_periods(&[
-            Status {
-                created: Utc::now().naive_utc(),
-                status_code: 200,
-                id: 3,
-                project: 0,
-                time: 10,
-            },
-            Status {
-                created: Utc::now().naive_utc().sub(chrono::Duration::hours(1)),
-                status_code: 503,
-                id: 2,
-                project: 0,
-                time: 10,
-            },
-            Status {
-                created: Utc::now().naive_utc().sub(chrono::Duration::hours(2)),
-                status_code: 200,
-                id: 1,
-                project: 0,
-                time: 10,
-            },
-        ])
-        .await;
-
-        assert_eq!(x.first().unwrap().duration, "59 minutes");
-        assert_eq!(x.len(), 1);
-    }
-
-    #[actix_rt::test]
-    async fn compute_downtime_end_of_day() {
-        let x = compute_downtime_periods(&[
-            Status {
-                created: Utc
-                    .with_ymd_and_hms(2020, 9, 25, 23, 0, 0)
-                    .unwrap()
-                    .naive_utc(),
-                // Utc::now().naive_utc().sub(chrono::Duration::hours(1)),
-                status_code: 200,
-                id: 2,
-                project: 0,
-                time: 10,
-            },
-            Status {
-                created: Utc
-                    .with_ymd_and_hms(2020, 9, 25, 1, 0, 0)
-                    .unwrap()
-                    .naive_utc(),
-                status_code: 404,
-                id: 1,
-                project: 0,
-                time: 10,
-            },
-        ])
-        .await;
-
-        assert_eq!(x.first().unwrap().duration, "23 hours");
-        assert_eq!(x.len(), 1);
-    }
-
-    #[actix_rt::test]
-    async fn compute_downtime_never_up() {
-        let x = compute_downtime_periods(&[
-            Status {
-                created: Utc
-                    .with_ymd_and_hms(2020, 9, 25, 0, 0, 0)
-                    .unwrap()
-                    .naive_utc(),
-                status_code: 404,
-                id: 1,
-                project: 0,
-                time: 10,
-            },
-            Status {
-                created: Utc
-                    .with_ymd_and_hms(2020, 9, 25, 12, 0, 0)
-                    .unwrap()
-                    .naive_utc(),
-                status_code: 404,
-                id: 2,
-                project: 0,
-                time: 10,
-            },
-        ])
-        .await;
-
-        assert_eq!(x.first().unwrap().duration, "24 hours");
-        assert_eq!(x.len(), 1);
-    }
-}
-
-#[get("/")]
-pub async fn get_index(
-    settings: Data<PersistedSettings>,
-    projects_repo: ProjectRepositoryData,
-    status_repo: StatusRepositoryData,
-    identity: Option<Identity>,
-) -> HttpResponse {
-    let pool = get_db!();
-    root(pool, settings, projects_repo, status_repo, identity).await
-}
-
 #[head("/")]
 pub async fn head_index() -> HttpResponse {
     HttpResponse::Ok().finish()
@@ -1,27 +1,56 @@
-use crate::data::project_repository::ProjectRepositoryData;
-use crate::db::Database;
-use crate::models::Project;
-use crate::schema::projects::dsl::projects;
+use crate::alerting::AlertPolicy;
+use crate::checks::latency::LatencyThresholds;
+use crate::checks::{CHECK_TYPES, heartbeat, validate_check_config};
+use crate::data::certificate_repository::CertificateRepository;
+use crate::data::heartbeat_repository::HeartbeatRepository;
+use crate::data::project_repository::ProjectRepository;
+use crate::data::rollup_repository::RollupRepository;
+use crate::data::slo_repository::SloRepository;
+use crate::data::status_repository::StatusRepository;
+use crate::models::{Heartbeat, Project, ProjectCertificate, ProjectSettings};
 use crate::settings::{CUSTOM_SCRIPT, CUSTOM_STYLE, PersistedSettings};
+use crate::slo::ErrorBudget;
 use crate::template::template_admin_login::AdminLogin;
-use crate::{get_db, get_pool};
 use actix_identity::Identity;
 use actix_web::get;
 use actix_web::post;
 use actix_web::web::Data;
 use actix_web::{HttpResponse, web::Form};
+use anyhow::Context;
 use askama::Template;
-use diesel::RunQueryDsl;
+use chrono::Utc;
 use serde::Deserialize;
 
 #[derive(Template)]
 #[template(path = "admin_dashboard.html")]
 pub struct AdminDashboardTemplate {
     pub projects: Vec<Project>,
+    pub certificates: Vec<ProjectCertificate>,
+    pub heartbeats: Vec<Heartbeat>,
+    pub budgets: Vec<ErrorBudget>,
+    pub check_types: &'static [&'static str],
+    pub errors: Vec<String>,
     pub custom_script: String,
     pub custom_style: String,
 }
 
+impl AdminDashboardTemplate {
+    pub fn certificate_for(&self, project: &Project) -> Option<&ProjectCertificate> {
+        self.certificates.iter().find(|c| c.project == project.id)
+    }
+
+    pub fn heartbeat_for(&self, project: &Project) -> Option<&Heartbeat> {
+        self.heartbeats.iter().find(|h| h.project == project.id)
+    }
+
+    pub fn budgets_for(&self, project: &Project) -> Vec<&ErrorBudget> {
+        self.budgets
+            .iter()
+            .filter(|b| b.slo.project == project.id)
+            .collect()
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ProjectUpdate {
     project_id: i32,
@@ -29,17 +58,215 @@ pub struct ProjectUpdate {
     description: String,
     url: String,
     enabled: Option<String>,
+    check_type: String,
+    check_config: String,
+    check_interval: i32,
+    latency_warn_ms: String,
+    latency_critical_ms: String,
+    latency_use_p95: Option<String>,
+    notify_degraded: Option<String>,
+    down_after_failures: i32,
+    up_after_successes: i32,
+    flap_threshold: String,
+    flap_window_minutes: i32,
+    sla_target: String,
+}
+
+impl ProjectUpdate {
+    fn latency_thresholds(&self) -> anyhow::Result<LatencyThresholds> {
+        let parse = |ms: &str| -> anyhow::Result<Option<i32>> {
+            let ms = ms.trim();
+            if ms.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(
+                    ms.parse().context("Latency thresholds must be a number")?,
+                ))
+            }
+        };
+
+        let thresholds = LatencyThresholds {
+            warn_ms: parse(&self.latency_warn_ms)?,
+            critical_ms: parse(&self.latency_critical_ms)?,
+            use_p95: self.latency_use_p95.clone().unwrap_or_default() == "on",
+        };
+        thresholds.validate()?;
+        Ok(thresholds)
+    }
+
+    fn alert_policy(&self) -> anyhow::Result<AlertPolicy> {
+        let flap_threshold = self.flap_threshold.trim();
+        let policy = AlertPolicy {
+            down_after: self.down_after_failures,
+            up_after: self.up_after_successes,
+            flap_threshold: if flap_threshold.is_empty() {
+                None
+            } else {
+                Some(
+                    flap_threshold
+                        .parse()
+                        .context("Flap threshold must be a number")?,
+                )
+            },
+            flap_window_minutes: self.flap_window_minutes,
+        };
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    fn sla_target(&self) -> anyhow::Result<Option<f64>> {
+        let sla_target = self.sla_target.trim();
+        if sla_target.is_empty() {
+            return Ok(None);
+        }
+
+        let sla_target: f64 = sla_target
+            .parse()
+            .context("SLA target must be a percentage")?;
+        anyhow::ensure!(
+            sla_target > 0.0 && sla_target <= 100.0,
+            "SLA target must be between 0 and 100%"
+        );
+        Ok(Some(sla_target))
+    }
+
+    /// Validate every section of the form, giving all the problems at once so that nothing is
+    /// saved unless the whole form is valid
+    fn project_settings(&self) -> Result<ProjectSettings, Vec<String>> {
+        let check = validate_check_config(&self.check_type, &self.check_config).and_then(|()| {
+            anyhow::ensure!(
+                self.check_interval > 0,
+                "Check interval must be a positive number of seconds"
+            );
+            Ok(())
+        });
+
+        match (
+            check,
+            self.latency_thresholds(),
+            self.alert_policy(),
+            self.sla_target(),
+        ) {
+            (Ok(()), Ok(thresholds), Ok(policy), Ok(sla_target)) => Ok(ProjectSettings {
+                check_type: self.check_type.clone(),
+                check_config: Some(self.check_config.clone()).filter(|c| !c.trim().is_empty()),
+                check_interval: self.check_interval,
+                latency_warn_ms: thresholds.warn_ms,
+                latency_critical_ms: thresholds.critical_ms,
+                latency_use_p95: thresholds.use_p95,
+                notify_degraded: self.notify_degraded.clone().unwrap_or_default() == "on",
+                down_after_failures: policy.down_after,
+                up_after_successes: policy.up_after,
+                flap_threshold: policy.flap_threshold,
+                flap_window_minutes: policy.flap_window_minutes,
+                sla_target,
+            }),
+            (check, thresholds, policy, sla_target) => Err([
+                check
+                    .err()
+                    .map(|e| format!("Invalid check for '{}': {e}", self.name)),
+                thresholds
+                    .err()
+                    .map(|e| format!("Invalid latency thresholds for '{}': {e}", self.name)),
+                policy
+                    .err()
+                    .map(|e| format!("Invalid alerting for '{}': {e}", self.name)),
+                sla_target
+                    .err()
+                    .map(|e| format!("Invalid SLA for '{}': {e}", self.name)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect()),
+        }
+    }
+}
+
+/// Everything that the dashboard shows, for the handlers that render it after making a change
+pub trait DashboardRepository:
+    ProjectRepository
+    + CertificateRepository
+    + HeartbeatRepository
+    + SloRepository
+    + StatusRepository
+    + RollupRepository
+{
+}
+
+impl<
+    T: ProjectRepository
+        + CertificateRepository
+        + HeartbeatRepository
+        + SloRepository
+        + StatusRepository
+        + RollupRepository,
+> DashboardRepository for T
+{
 }
 
-async fn admin_dashboard(pool: Database, settings: Data<PersistedSettings>) -> HttpResponse {
-    let mut pool = get_pool!(pool);
+pub type DashboardRepositoryData = Data<Box<dyn DashboardRepository>>;
+
+pub(crate) async fn admin_dashboard(
+    repos: &dyn DashboardRepository,
+    settings: Data<PersistedSettings>,
+    errors: Vec<String>,
+) -> HttpResponse {
+    let projects_list = match repos.get_all_projects() {
+        Ok(projects_list) => projects_list,
+        Err(e) => {
+            tracing::warn!("Failed to get projects: {e:?}");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let certificates = match repos.get_all_certificates() {
+        Ok(certificates) => certificates,
+        Err(e) => {
+            tracing::warn!("Failed to get certificates: {e:?}");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let heartbeats = match repos.get_all_heartbeats() {
+        Ok(heartbeats) => heartbeats,
+        Err(e) => {
+            tracing::warn!("Failed to get heartbeats: {e:?}");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
 
-    let projects_list = projects
-        .load::<Project>(&mut pool)
-        .expect("Unable to load projects");
+    let slos = match repos.get_all_slos() {
+        Ok(slos) => slos,
+        Err(e) => {
+            tracing::warn!("Failed to get SLOs: {e:?}");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let now = Utc::now().naive_utc();
+    let budgets = slos
+        .into_iter()
+        .filter_map(|slo| {
+            let project = projects_list.iter().find(|p| p.id == slo.project)?;
+            Some(
+                ErrorBudget::load(repos, repos, &slo, project, now).unwrap_or_else(|e| {
+                    tracing::warn!("Failed to get error budget for SLO: {e:?}");
+                    ErrorBudget {
+                        slo,
+                        remaining: None,
+                    }
+                }),
+            )
+        })
+        .collect();
 
     let template = AdminDashboardTemplate {
         projects: projects_list,
+        certificates,
+        heartbeats,
+        budgets,
+        check_types: CHECK_TYPES,
+        errors,
         custom_script: settings.get_setting(CUSTOM_SCRIPT),
         custom_style: settings.get_setting(CUSTOM_STYLE),
     }
@@ -52,6 +279,7 @@ async fn admin_dashboard(pool: Database, settings: Data<PersistedSettings>) -> H
 pub async fn get_admin_dashboard(
     id: Option<Identity>,
     settings: Data<PersistedSettings>,
+    repos: DashboardRepositoryData,
 ) -> HttpResponse {
     if !id.is_logged_in() {
         return HttpResponse::PermanentRedirect()
@@ -59,9 +287,7 @@ pub async fn get_admin_dashboard(
             .finish();
     }
 
-    let pool = get_db!();
-
-    admin_dashboard(pool, settings).await
+    admin_dashboard(repos.get_ref().as_ref(), settings, Vec::new()).await
 }
 
 #[post("/admin/dashboard")]
@@ -69,25 +295,41 @@ pub async fn post_admin_dashboard(
     id: Option<Identity>,
     settings: Data<PersistedSettings>,
     updates: Form<ProjectUpdate>,
-    project: ProjectRepositoryData,
+    repos: DashboardRepositoryData,
 ) -> HttpResponse {
     let _span = tracing::info_span!("Admin Project Update", ?updates);
 
-    let pool = get_db!();
-
     if !id.is_logged_in() {
         return HttpResponse::PermanentRedirect()
             .append_header((http::header::LOCATION.as_str(), "/admin"))
             .finish();
     }
 
-    let _ = project.update_project(
-        updates.project_id,
-        updates.name.as_str(),
-        updates.enabled.clone().unwrap_or_default() == "on",
-        &updates.url,
-        &updates.description,
-    );
+    let repos = repos.get_ref().as_ref();
+
+    let errors = match updates.project_settings() {
+        Ok(project_settings) => repos
+            .update_project(
+                updates.project_id,
+                updates.name.as_str(),
+                updates.enabled.clone().unwrap_or_default() == "on",
+                &updates.url,
+                &updates.description,
+            )
+            .and_then(|_| repos.update_project_settings(updates.project_id, &project_settings))
+            .and_then(|_| {
+                if project_settings.check_type == heartbeat::CHECK_TYPE {
+                    repos.ensure_heartbeat(updates.project_id)
+                } else {
+                    Ok(())
+                }
+            })
+            .err()
+            .map(|e| format!("Unable to update '{}': {e}", updates.name))
+            .into_iter()
+            .collect(),
+        Err(errors) => errors,
+    };
 
-    admin_dashboard(pool, settings).await
+    admin_dashboard(repos, settings, errors).await
 }
@@ -1,19 +1,14 @@
 use crate::data::incident_repository::IncidentRepositoryData;
 use crate::data::project_repository::ProjectRepositoryData;
-use crate::db::Database;
 use crate::models::{IncidentStatusType, NewIncident, NewIncidentStatusUpdate, Project};
-use crate::schema::incident_status_type::dsl::incident_status_type;
-use crate::schema::projects::dsl::projects;
 use crate::settings::{CUSTOM_SCRIPT, CUSTOM_STYLE, PersistedSettings};
 use crate::template::template_admin_login::AdminLogin;
-use crate::{get_db, get_pool};
 use actix_identity::Identity;
 use actix_web::get;
 use actix_web::post;
 use actix_web::web::Data;
 use actix_web::{HttpResponse, web::Form};
 use askama::Template;
-use diesel::RunQueryDsl;
 use serde::Deserialize;
 use tracing_futures::Instrument;
 use uuid::Uuid;
@@ -37,7 +32,8 @@ pub struct ProjectUpdate {
 
 async fn admin_incidents_new(
     id: Option<Identity>,
-    pool: Database,
+    project_repo: ProjectRepositoryData,
+    incident_repo: IncidentRepositoryData,
     settings: Data<PersistedSettings>,
 ) -> HttpResponse {
     if !id.is_logged_in() {
@@ -46,17 +42,21 @@ async fn admin_incidents_new(
             .finish();
     }
 
-    let mut pool = get_pool!(pool);
-
-    let status_types = incident_status_type
-        .load::<IncidentStatusType>(&mut pool)
-        .expect("Unable to load incident status types");
-
-    // let projects_list = project_repo.get_all_projects();
+    let status_types = match incident_repo.get_incident_status_types() {
+        Ok(status_types) => status_types,
+        Err(e) => {
+            tracing::warn!("Failed to get incident status types: {e:?}");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
 
-    let projects_list = projects
-        .load::<Project>(&mut pool)
-        .expect("Unable to load projects");
+    let projects_list = match project_repo.get_all_projects() {
+        Ok(projects_list) => projects_list,
+        Err(e) => {
+            tracing::warn!("Failed to get projects: {e:?}");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
 
     let template = AdminNewIncidentTemplate {
         status_types,
@@ -72,14 +72,14 @@ async fn admin_incidents_new(
 #[get("/admin/incidents/new")]
 pub async fn get_admin_incidents_new(
     id: Option<Identity>,
+    project_repo: ProjectRepositoryData,
+    incident_repo: IncidentRepositoryData,
     settings: Data<PersistedSettings>,
 ) -> HttpResponse {
     let request_id = Uuid::new_v4();
     let span = tracing::info_span!("Admin Incidents New GET", request_id = %request_id);
 
-    let pool = get_db!();
-
-    admin_incidents_new(id, pool, settings)
+    admin_incidents_new(id, project_repo, incident_repo, settings)
         .instrument(span)
         .await
 }
@@ -95,8 +95,6 @@ pub async fn post_admin_incidents_new(
     let request_id = Uuid::new_v4();
     let span = tracing::info_span!("Admin Incidents New POST", request_id = %request_id);
 
-    let pool = get_db!();
-
     let project = match project_repo.get_project_by_name(&form_data.project) {
         Ok(project) => project,
         Err(e) => {
@@ -137,7 +135,7 @@ pub async fn post_admin_incidents_new(
         status_type: status_type.id,
     });
 
-    admin_incidents_new(id, pool, settings)
+    admin_incidents_new(id, project_repo, incident_repo, settings)
         .instrument(span)
         .await
 }
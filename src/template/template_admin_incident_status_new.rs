@@ -1,11 +1,7 @@
 use crate::data::incident_repository::IncidentRepositoryData;
-use crate::db::Database;
-use crate::diesel::RunQueryDsl;
 use crate::models::{IncidentStatusType, NewIncidentStatusUpdate};
-use crate::schema::incident_status_type::dsl::incident_status_type;
 use crate::settings::{CUSTOM_SCRIPT, CUSTOM_STYLE, PersistedSettings};
 use crate::template::template_admin_login::AdminLogin;
-use crate::{get_db, get_pool};
 use actix_identity::Identity;
 use actix_web::get;
 use actix_web::post;
@@ -35,7 +31,7 @@ pub struct StatusUpdate {
 async fn admin_incident_status_new(
     incident_id: i32,
     id: Option<Identity>,
-    pool: Database,
+    incident_repo: IncidentRepositoryData,
     settings: Data<PersistedSettings>,
 ) -> HttpResponse {
     if !id.is_logged_in() {
@@ -44,11 +40,13 @@ async fn admin_incident_status_new(
             .finish();
     }
 
-    let mut pool = get_pool!(pool);
-
-    let status_types = incident_status_type
-        .load::<IncidentStatusType>(&mut pool)
-        .expect("Unable to load incident status types");
+    let status_types = match incident_repo.get_incident_status_types() {
+        Ok(status_types) => status_types,
+        Err(e) => {
+            tracing::warn!("Failed to get incident status types: {e:?}");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
 
     let template = AdminNewIncidentStatusTemplate {
         incident_id,
@@ -65,14 +63,13 @@ async fn admin_incident_status_new(
 pub async fn get_admin_incident_status_new(
     path: Path<(i32,)>,
     id: Option<Identity>,
+    incident_repo: IncidentRepositoryData,
     settings: Data<PersistedSettings>,
 ) -> HttpResponse {
     let request_id = Uuid::new_v4();
     let span = tracing::info_span!("Admin Incidents New Status GET", request_id = %request_id);
 
-    let pool = get_db!();
-
-    admin_incident_status_new(path.into_inner().0, id, pool, settings)
+    admin_incident_status_new(path.into_inner().0, id, incident_repo, settings)
         .instrument(span)
         .await
 }
@@ -88,8 +85,6 @@ pub async fn post_admin_incident_status_new(
     let request_id = Uuid::new_v4();
     let span = tracing::info_span!("Admin Incidents New Status POST", request_id = %request_id);
 
-    let pool = get_db!();
-
     let status_type = incident_repo
         .get_incident_status_type_by_title(&form_data.status_type)
         .expect("Unknown status type");
@@ -107,7 +102,7 @@ pub async fn post_admin_incident_status_new(
         status_type: status_type.id,
     });
 
-    admin_incident_status_new(incident, id, pool, settings)
+    admin_incident_status_new(incident, id, incident_repo, settings)
         .instrument(span)
         .await
 }
@@ -0,0 +1,110 @@
+use crate::data::incident_repository::IncidentRepositoryData;
+use crate::data::outage_repository::OutageRepositoryData;
+use crate::data::project_repository::ProjectRepositoryData;
+use crate::data::rollup_repository::RollupRepositoryData;
+use crate::data::status_repository::StatusRepositoryData;
+use crate::report::{MonthlyReport, ReportMonth};
+use crate::settings::{CUSTOM_SCRIPT, CUSTOM_STYLE, PersistedSettings};
+use crate::template::template_admin_login::AdminLogin;
+use actix_identity::Identity;
+use actix_web::HttpResponse;
+use actix_web::get;
+use actix_web::web::{Data, Path};
+use askama::Template;
+use chrono::Utc;
+
+/// How many months can be picked from the reports page
+const REPORT_MONTHS: u32 = 12;
+
+#[derive(Template)]
+#[template(path = "admin_reports.html")]
+pub struct AdminReportsTemplate {
+    pub months: Vec<ReportMonth>,
+    pub custom_script: String,
+    pub custom_style: String,
+}
+
+#[get("/admin/reports")]
+pub async fn get_admin_reports(
+    id: Option<Identity>,
+    settings: Data<PersistedSettings>,
+) -> HttpResponse {
+    let _span = tracing::info_span!("Admin Reports");
+
+    if !id.is_logged_in() {
+        return HttpResponse::PermanentRedirect()
+            .append_header((http::header::LOCATION.as_str(), "/admin"))
+            .finish();
+    }
+
+    // The current month is included so far, for keeping an eye on it
+    let current = ReportMonth::containing(Utc::now().naive_utc());
+
+    let template = AdminReportsTemplate {
+        months: (0..REPORT_MONTHS).map(|i| current.minus(i)).collect(),
+        custom_script: settings.get_setting(CUSTOM_SCRIPT),
+        custom_style: settings.get_setting(CUSTOM_STYLE),
+    }
+    .render()
+    .expect("Unable to render template");
+    HttpResponse::Ok().body(template)
+}
+
+#[get("/admin/reports/{month}/{format}")]
+pub async fn get_admin_report(
+    id: Option<Identity>,
+    path: Path<(String, String)>,
+    project_repo: ProjectRepositoryData,
+    rollup_repo: RollupRepositoryData,
+    status_repo: StatusRepositoryData,
+    outage_repo: OutageRepositoryData,
+    incident_repo: IncidentRepositoryData,
+) -> HttpResponse {
+    let _span = tracing::info_span!("Admin Report", ?path);
+
+    if !id.is_logged_in() {
+        return HttpResponse::PermanentRedirect()
+            .append_header((http::header::LOCATION.as_str(), "/admin"))
+            .finish();
+    }
+
+    let (month, format) = path.into_inner();
+    let Ok(month) = month.parse::<ReportMonth>() else {
+        return HttpResponse::NotFound().finish();
+    };
+    let content_type = match format.as_str() {
+        "csv" => "text/csv; charset=utf-8",
+        "html" => "text/html; charset=utf-8",
+        _ => return HttpResponse::NotFound().finish(),
+    };
+
+    let report = match MonthlyReport::generate(
+        month,
+        project_repo.get_ref().as_ref(),
+        rollup_repo.get_ref().as_ref(),
+        status_repo.get_ref().as_ref(),
+        outage_repo.get_ref().as_ref(),
+        incident_repo.get_ref().as_ref(),
+        Utc::now().naive_utc(),
+    ) {
+        Ok(report) => report,
+        Err(e) => {
+            tracing::error!("Failed to generate report for {month}: {e:?}");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let body = if format == "csv" {
+        report.to_csv()
+    } else {
+        report.render().expect("Unable to render template")
+    };
+
+    HttpResponse::Ok()
+        .append_header((http::header::CONTENT_TYPE.as_str(), content_type))
+        .append_header((
+            http::header::CONTENT_DISPOSITION.as_str(),
+            format!("attachment; filename=\"youup-report-{month}.{format}\""),
+        ))
+        .body(body)
+}
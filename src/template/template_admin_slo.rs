@@ -0,0 +1,106 @@
+use crate::models::NewSlo;
+use crate::settings::PersistedSettings;
+use crate::template::template_admin_dashboard::{DashboardRepositoryData, admin_dashboard};
+use crate::template::template_admin_login::AdminLogin;
+use actix_identity::Identity;
+use actix_web::post;
+use actix_web::web::Data;
+use actix_web::{HttpResponse, web::Form};
+use anyhow::Context;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct SloNew {
+    project_id: i32,
+    objective: String,
+    window_days: i32,
+    latency_threshold_ms: String,
+}
+
+impl SloNew {
+    fn slo(&self) -> anyhow::Result<NewSlo> {
+        let latency_threshold_ms = self.latency_threshold_ms.trim();
+        let slo = NewSlo {
+            project: self.project_id,
+            objective: self
+                .objective
+                .trim()
+                .parse()
+                .context("Objective must be a percentage")?,
+            window_days: self.window_days,
+            latency_threshold_ms: if latency_threshold_ms.is_empty() {
+                None
+            } else {
+                Some(
+                    latency_threshold_ms
+                        .parse()
+                        .context("Latency threshold must be a number")?,
+                )
+            },
+        };
+        slo.validate()?;
+        Ok(slo)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SloDelete {
+    slo_id: i32,
+}
+
+#[post("/admin/slo/new")]
+pub async fn post_admin_slo_new(
+    id: Option<Identity>,
+    settings: Data<PersistedSettings>,
+    new: Form<SloNew>,
+    repos: DashboardRepositoryData,
+) -> HttpResponse {
+    let _span = tracing::info_span!("Admin SLO New", ?new);
+
+    if !id.is_logged_in() {
+        return HttpResponse::PermanentRedirect()
+            .append_header((http::header::LOCATION.as_str(), "/admin"))
+            .finish();
+    }
+
+    let error = new
+        .slo()
+        .and_then(|slo| repos.create_slo(slo))
+        .err()
+        .map(|e| format!("Unable to add SLO: {e}"));
+
+    admin_dashboard(
+        repos.get_ref().as_ref(),
+        settings,
+        error.into_iter().collect(),
+    )
+    .await
+}
+
+#[post("/admin/slo/delete")]
+pub async fn post_admin_slo_delete(
+    id: Option<Identity>,
+    settings: Data<PersistedSettings>,
+    delete: Form<SloDelete>,
+    repos: DashboardRepositoryData,
+) -> HttpResponse {
+    let _span = tracing::info_span!("Admin SLO Delete", ?delete);
+
+    if !id.is_logged_in() {
+        return HttpResponse::PermanentRedirect()
+            .append_header((http::header::LOCATION.as_str(), "/admin"))
+            .finish();
+    }
+
+    let error = repos
+        .delete_slo(delete.slo_id)
+        .err()
+        .map(|e| format!("Unable to delete SLO: {e}"));
+
+    admin_dashboard(
+        repos.get_ref().as_ref(),
+        settings,
+        error.into_iter().collect(),
+    )
+    .await
+}
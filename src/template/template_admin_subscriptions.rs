@@ -1,18 +1,16 @@
+use crate::data::email_subscription_repository::EmailSubscriberRepositoryData;
 use crate::data::sms_subscription_repository::SmsSubscriberRepositoryData;
 use crate::data::webhook_subscription_repository::WebhookSubscriberRepositoryData;
-use crate::db::Database;
 use crate::models::{EmailSubscription, SmsSubscription, WebhookSubscription};
-use crate::schema::email_subscriptions::dsl::email_subscriptions;
+use crate::settings;
 use crate::settings::{CUSTOM_SCRIPT, CUSTOM_STYLE, PersistedSettings};
 use crate::template::template_admin_login::AdminLogin;
-use crate::{get_db, get_pool, settings};
 use actix_identity::Identity;
 use actix_web::get;
 use actix_web::post;
 use actix_web::web::Data;
 use actix_web::{HttpResponse, web::Form};
 use askama::Template;
-use diesel::RunQueryDsl;
 use serde::Deserialize;
 use uuid::Uuid;
 
@@ -32,8 +30,8 @@ pub struct ProjectUpdate {}
 
 async fn admin_subscription(
     id: Option<Identity>,
-    pool: Database,
     settings: Data<PersistedSettings>,
+    email_subscriptions_repo: EmailSubscriberRepositoryData,
     sms_subscriptions_repo: SmsSubscriberRepositoryData,
     webhook_subscriptions_repo: WebhookSubscriberRepositoryData,
 ) -> HttpResponse {
@@ -47,11 +45,13 @@ async fn admin_subscription(
             .finish();
     }
 
-    let mut pool = get_pool!(pool);
-
-    let subscriptions = email_subscriptions
-        .load::<EmailSubscription>(&mut pool)
-        .expect("Unable to load subscriptions");
+    let subscriptions = match email_subscriptions_repo.get_all() {
+        Ok(subscriptions) => subscriptions,
+        Err(e) => {
+            tracing::warn!("Failed to get email subscriptions: {e:?}");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
 
     let template = AdminSubscriptionTemplate {
         subscriptions,
@@ -70,15 +70,14 @@ async fn admin_subscription(
 pub async fn get_admin_subscriptions(
     id: Option<Identity>,
     settings: Data<PersistedSettings>,
+    email_subscriptions_repo: EmailSubscriberRepositoryData,
     sms_subscriptions_repo: SmsSubscriberRepositoryData,
     webhook_subscriptions_repo: WebhookSubscriberRepositoryData,
 ) -> HttpResponse {
-    let pool = get_db!();
-
     admin_subscription(
         id,
-        pool,
         settings,
+        email_subscriptions_repo,
         sms_subscriptions_repo,
         webhook_subscriptions_repo,
     )
@@ -91,15 +90,14 @@ pub async fn post_admin_subscriptions(
     id: Option<Identity>,
     settings: Data<PersistedSettings>,
     _updates: Option<Form<ProjectUpdate>>,
+    email_subscriptions_repo: EmailSubscriberRepositoryData,
     sms_subscriptions_repo: SmsSubscriberRepositoryData,
     webhook_subscriptions_repo: WebhookSubscriberRepositoryData,
 ) -> HttpResponse {
-    let pool = get_db!();
-
     admin_subscription(
         id,
-        pool,
         settings,
+        email_subscriptions_repo,
         sms_subscriptions_repo,
         webhook_subscriptions_repo,
     )
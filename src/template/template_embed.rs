@@ -1,8 +1,13 @@
+use crate::data::alert_repository::AlertRepositoryData;
+use crate::data::outage_repository::OutageRepositoryData;
 use crate::data::project_repository::ProjectRepositoryData;
+use crate::data::rollup_repository::RollupRepositoryData;
 use crate::data::status_repository::StatusRepositoryData;
+use crate::rollup::StatusHistory;
 use crate::settings;
+use crate::template::index::downtime::Downtime;
 use crate::template::index::status_day::StatusDay;
-use crate::template::index::template_index::{ProjectStatus, compute_downtime_periods};
+use crate::template::index::template_index::ProjectStatus;
 use actix_web::HttpResponse;
 use actix_web::get;
 use actix_web::web::Path;
@@ -26,10 +31,37 @@ pub async fn get_embed(
     id: Path<(i32,)>,
     projects: ProjectRepositoryData,
     status_repo: StatusRepositoryData,
+    rollup_repo: RollupRepositoryData,
+    alert_repo: AlertRepositoryData,
+    outage_repo: OutageRepositoryData,
 ) -> HttpResponse {
     if let Some(proj) = projects.get_project_by_id(id.as_ref().0) {
-        let status_list: Vec<_> = status_repo.get_status_last_30_days();
         let history_size = settings::get_history_size();
+        let history = match StatusHistory::load(
+            rollup_repo.get_ref().as_ref(),
+            status_repo.get_ref().as_ref(),
+            std::slice::from_ref(&proj),
+            history_size.try_into().unwrap(),
+            Utc::now().naive_utc(),
+        ) {
+            Ok(history) => history,
+            Err(err) => {
+                tracing::warn!("Failed to get status history: {:?}", err);
+                return HttpResponse::InternalServerError().finish();
+            }
+        };
+        let timeline = history.recent_timeline(proj.id);
+        let outages: Vec<_> = outage_repo
+            .get_outages_since(
+                Utc::now().naive_utc() - chrono::Duration::days(history_size.try_into().unwrap()),
+            )
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to get outages: {e:?}");
+                Vec::new()
+            })
+            .into_iter()
+            .filter(|o| o.project == proj.id)
+            .collect();
 
         let mut days: Vec<StatusDay> = Vec::with_capacity(history_size);
         for x in (0..history_size).rev() {
@@ -44,16 +76,17 @@ pub async fn get_embed(
                 .unwrap()
                 .naive_utc();
 
-            let status_on_day: Vec<_> = status_list
+            let status_on_day: Vec<_> = timeline
                 .iter()
-                .filter(|s| s.project == proj.id && s.created.date() == then.date())
+                .filter(|s| s.created.date() == then.date())
                 .cloned()
                 .collect();
 
-            let downtime = compute_downtime_periods(status_on_day.as_slice()).await;
+            let downtime = Downtime::on_day(&outages, then.date(), now.naive_utc());
 
             days.push(StatusDay {
                 status: status_on_day,
+                counts: history.daily_counts(proj.id, then.date()),
                 date: then.format("%Y/%m/%d").to_string(),
                 downtime,
             });
@@ -61,10 +94,18 @@ pub async fn get_embed(
 
         let today = days.last().unwrap().clone();
 
+        let uptime = history.uptime(&proj, Utc::now().naive_utc());
         let ps = ProjectStatus {
             project: proj,
             days,
             today,
+            locations: Vec::new(),
+            flapping: alert_repo
+                .get_alert_by_project(id.as_ref().0)
+                .ok()
+                .flatten()
+                .is_some_and(|a| a.flapping),
+            uptime,
         };
 
         let template = EmbedTemplate {
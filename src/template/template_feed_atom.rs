@@ -1,12 +1,19 @@
 use crate::data::incident_repository::IncidentRepositoryData;
+use crate::data::outage_repository::OutageRepositoryData;
+use crate::data::project_repository::ProjectRepositoryData;
+use crate::settings;
 use actix_web::HttpResponse;
 use actix_web::get;
 use atom_syndication::{ContentBuilder, EntryBuilder, FeedBuilder, LinkBuilder};
-use chrono::{Offset, TimeZone, Utc};
+use chrono::{Duration, Offset, TimeZone, Utc};
 use uuid::Uuid;
 
-async fn atom_feed(incidents: IncidentRepositoryData) -> HttpResponse {
-    let entries = incidents
+async fn atom_feed(
+    incidents: IncidentRepositoryData,
+    projects: ProjectRepositoryData,
+    outages: OutageRepositoryData,
+) -> HttpResponse {
+    let mut entries = incidents
         .get_all_incidents()
         .iter()
         .map(|i| (i, incidents.get_status_updates_by_incident(i)))
@@ -40,6 +47,51 @@ async fn atom_feed(incidents: IncidentRepositoryData) -> HttpResponse {
         })
         .collect::<Vec<_>>();
 
+    let now = Utc::now().naive_utc();
+    let projects = projects.get_all_enabled_projects().unwrap_or_else(|e| {
+        tracing::warn!("Failed to get projects: {e:?}");
+        Vec::new()
+    });
+    let outages = outages
+        .get_outages_since(now - Duration::days(settings::get_history_size().try_into().unwrap()))
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to get outages: {e:?}");
+            Vec::new()
+        });
+
+    entries.extend(
+        outages
+            .iter()
+            .filter_map(|o| Some((o, projects.iter().find(|p| p.id == o.project)?)))
+            .map(|(outage, project)| {
+                EntryBuilder::default()
+                    .title(format!(
+                        "Outage in '{}' on {}",
+                        project.name,
+                        outage.formatted_started()
+                    ))
+                    .content(
+                        ContentBuilder::default()
+                            .value(outage.summary(now))
+                            .content_type(Some("text".to_string()))
+                            .build(),
+                    )
+                    .links(vec![
+                        LinkBuilder::default()
+                            .href(settings::get_host_url())
+                            .build(),
+                    ])
+                    .id(format!("outage-{}", outage.id))
+                    .published(Utc.fix().from_local_datetime(&outage.started).unwrap())
+                    .updated(
+                        Utc.fix()
+                            .from_local_datetime(&outage.ended_or(now))
+                            .unwrap(),
+                    )
+                    .build()
+            }),
+    );
+
     let feed = FeedBuilder::default()
         .id(Uuid::new_v4().to_string())
         .title("YouUp incidents for 'test'")
@@ -55,6 +107,10 @@ async fn atom_feed(incidents: IncidentRepositoryData) -> HttpResponse {
 }
 
 #[get("/feed/atom")]
-pub async fn get_atom_feed(pool: IncidentRepositoryData) -> HttpResponse {
-    atom_feed(pool).await
+pub async fn get_atom_feed(
+    pool: IncidentRepositoryData,
+    projects: ProjectRepositoryData,
+    outages: OutageRepositoryData,
+) -> HttpResponse {
+    atom_feed(pool, projects, outages).await
 }
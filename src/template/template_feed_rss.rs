@@ -1,11 +1,18 @@
 use crate::data::incident_repository::IncidentRepositoryData;
+use crate::data::outage_repository::OutageRepositoryData;
+use crate::data::project_repository::ProjectRepositoryData;
+use crate::settings;
 use actix_web::HttpResponse;
 use actix_web::get;
-use chrono::{TimeZone, Utc};
+use chrono::{Duration, TimeZone, Utc};
 use rss::{ChannelBuilder, GuidBuilder, ItemBuilder};
 
-async fn rss_feed(incidents: IncidentRepositoryData) -> HttpResponse {
-    let items = incidents
+async fn rss_feed(
+    incidents: IncidentRepositoryData,
+    projects: ProjectRepositoryData,
+    outages: OutageRepositoryData,
+) -> HttpResponse {
+    let mut items = incidents
         .get_all_incidents()
         .iter()
         .map(|i| (i, incidents.get_status_updates_by_incident(i)))
@@ -40,6 +47,42 @@ async fn rss_feed(incidents: IncidentRepositoryData) -> HttpResponse {
         })
         .collect::<Vec<_>>();
 
+    let now = Utc::now().naive_utc();
+    let projects = projects.get_all_enabled_projects().unwrap_or_else(|e| {
+        tracing::warn!("Failed to get projects: {e:?}");
+        Vec::new()
+    });
+    let outages = outages
+        .get_outages_since(now - Duration::days(settings::get_history_size().try_into().unwrap()))
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to get outages: {e:?}");
+            Vec::new()
+        });
+
+    items.extend(
+        outages
+            .iter()
+            .filter_map(|o| Some((o, projects.iter().find(|p| p.id == o.project)?)))
+            .map(|(outage, project)| {
+                let guid = GuidBuilder::default()
+                    .value(format!("outage-{}", outage.id))
+                    .permalink(false)
+                    .build();
+
+                ItemBuilder::default()
+                    .title(format!(
+                        "Outage in '{}' on {}",
+                        project.name,
+                        outage.formatted_started()
+                    ))
+                    .content(escaper::encode_minimal(&outage.summary(now)))
+                    .link(settings::get_host_url())
+                    .guid(guid)
+                    .pub_date(Utc.from_utc_datetime(&outage.started).to_rfc2822())
+                    .build()
+            }),
+    );
+
     let channel = ChannelBuilder::default()
         .title("YouUp incidents for 'test'")
         .link("test")
@@ -53,6 +96,10 @@ async fn rss_feed(incidents: IncidentRepositoryData) -> HttpResponse {
 }
 
 #[get("/feed/rss")]
-pub async fn get_rss_feed(pool: IncidentRepositoryData) -> HttpResponse {
-    rss_feed(pool).await
+pub async fn get_rss_feed(
+    pool: IncidentRepositoryData,
+    projects: ProjectRepositoryData,
+    outages: OutageRepositoryData,
+) -> HttpResponse {
+    rss_feed(pool, projects, outages).await
 }
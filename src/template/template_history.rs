@@ -1,9 +1,13 @@
+use crate::data::outage_repository::OutageRepositoryData;
 use crate::data::project_repository::ProjectRepositoryData;
+use crate::data::rollup_repository::RollupRepositoryData;
 use crate::data::status_repository::StatusRepositoryData;
 use crate::models::Project;
 use crate::project_status::ProjectStatusTypes;
+use crate::rollup::{HISTORY_DAYS, StatusHistory};
 use crate::settings::{CUSTOM_HTML, CUSTOM_SCRIPT, CUSTOM_STYLE, PersistedSettings};
 use crate::template::template_admin_login::AdminLogin;
+use crate::template::template_uptime::day_status;
 use crate::time_utils::get_days_from_month;
 use actix_identity::Identity;
 use actix_web::web::Data;
@@ -33,6 +37,8 @@ pub struct UptimeTemplate {
 pub async fn get_incident_history(
     projects_repo: ProjectRepositoryData,
     status_repo: StatusRepositoryData,
+    rollup_repo: RollupRepositoryData,
+    outage_repo: OutageRepositoryData,
     settings: Data<PersistedSettings>,
     identity: Option<Identity>,
 ) -> HttpResponse {
@@ -44,39 +50,43 @@ pub async fn get_incident_history(
         }
     };
 
-    let status_list = status_repo.get_status_last_90_days();
+    let now = Utc::now();
+
+    let history = match StatusHistory::load(
+        rollup_repo.get_ref().as_ref(),
+        status_repo.get_ref().as_ref(),
+        &projects,
+        HISTORY_DAYS,
+        now.naive_utc(),
+    ) {
+        Ok(history) => history,
+        Err(err) => {
+            tracing::warn!("Failed to get status history: {:?}", err);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    let outages = outage_repo
+        .get_outages_since(now.naive_utc() - chrono::Duration::days(90))
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to get outages: {e:?}");
+            Vec::new()
+        });
 
     let mut months = Vec::new();
 
     let month_range = 3;
 
-    let now = Utc::now();
     for i in (0..month_range).rev() {
         let month = now.sub(chrono::Duration::weeks(i * 4));
 
-        let status_on_month = status_list
-            .iter()
-            .filter(|s| s.created.month() == month.month() && s.created.year() == s.created.year())
-            .collect::<Vec<_>>();
-
         let mut status_days = Vec::new();
 
         //TODO: length of month
         for day in 0..get_days_from_month(month.year(), month.month()) {
-            let status_on_day = status_on_month
-                .iter()
-                .filter(|s| s.created.day0() == day)
-                .collect::<Vec<_>>();
-
-            status_days.push(if status_on_day.is_empty() {
-                ProjectStatusTypes::Unknown
-            } else if status_on_day.iter().all(|s| !s.is_success()) {
-                ProjectStatusTypes::Failed
-            } else if status_on_day.iter().all(|s| s.is_success()) {
-                ProjectStatusTypes::Operational
-            } else {
-                ProjectStatusTypes::Failing
-            })
+            let date = month.with_day0(day).unwrap().date_naive();
+            let checked = history.checked_on(date);
+
+            status_days.push(day_status(checked, &outages, date, now.naive_utc()))
         }
 
         //TODO: needs to find status day for each day in month then find the overall for each day and show that as a square
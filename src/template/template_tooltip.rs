@@ -1,4 +1,4 @@
-use crate::StatusDay;
+use crate::template::index::status_day::StatusDay;
 use askama::Template;
 
 #[derive(Template)]
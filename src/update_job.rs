@@ -1,216 +1,969 @@
-use crate::models::{NewStatus, Project, Status};
+use crate::models::{
+    NewOutage, NewProjectAlert, NewProjectCertificate, NewStatus, Outage, Project, ProjectAlert,
+    Status,
+};
 
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
-use reqwest::Client;
-
-use crate::data::sms_subscription_repository::SmsSubscriberRepository;
-use crate::data::webhook_subscription_repository::WebhookSubscriberRepository;
-use crate::db;
+use crate::alerting::AlertEvent;
+use crate::checks::certificate::{
+    CertificateInfo, EXPIRY_NOTIFICATION_THRESHOLDS, crossed_threshold,
+};
+use crate::checks::latency::{P95_SAMPLES, p95};
+use crate::checks::{Check, CheckContext, CheckResult, check_for_project};
+use crate::data::alert_repository::AlertRepository;
+use crate::data::certificate_repository::{CertificateRepository, CertificateRepositoryBox};
+use crate::data::heartbeat_repository::HeartbeatRepositoryBox;
+use crate::data::outage_repository::OutageRepository;
+use crate::data::project_repository::ProjectRepository;
+use crate::data::status_repository::StatusRepository;
 use crate::db::Database;
-use crate::notifications::mailer::Mailer;
-use crate::notifications::sms::SMSNotifier;
-use crate::notifications::webhook::{WebhookNotifier, WebhookPayload};
-use crate::schema::status as stat;
-use chrono::Utc;
-use http::StatusCode;
-use std::ops::{Deref, DerefMut};
-use std::sync::Arc;
+use crate::notifications::webhook::WebhookPayload;
+use crate::notifications::{Notification, Notifiers};
+use crate::spool::StatusSpool;
+use crate::{alerting, quorum, settings, time_formatter};
+use actix_rt::spawn;
+use actix_web::web::Data;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
 use tracing::error;
 
 lazy_static! {
-    static ref PENDING_STATUS_UPDATES: Mutex<Vec<NewStatus>> = Mutex::new(Vec::new());
+    /// Samples that couldn't be stored, kept on disk until the database is back
+    static ref STATUS_SPOOL: Mutex<StatusSpool> = Mutex::new(StatusSpool::new(
+        if cfg!(test) {
+            std::env::temp_dir()
+                .join(format!("you_up_status_spool_{}.jsonl", std::process::id()))
+                .to_string_lossy()
+                .to_string()
+        } else {
+            settings::status_spool_path()
+        },
+        settings::status_spool_size()
+    ));
 }
 
-#[tracing::instrument(skip(db))]
-pub fn submit_status(db: Database, status: NewStatus) {
-    match db.get() {
-        Ok(mut conn) => {
-            match diesel::insert_into(stat::table)
-                .values(status.clone())
-                .execute(&mut conn)
-            {
-                Ok(_) => {}
-                Err(_) => {
-                    error!("Failed to insert {status:?} into db");
-                    if let Ok(mut lock) = PENDING_STATUS_UPDATES.lock() {
-                        lock.deref_mut().push(status)
-                    } else {
-                        error!("Failed to lock pending queue");
-                    }
-                }
-            }
-        }
-        Err(e) => {
-            error!("Failed to get pool in submit {e:?}");
-        }
+/// How often the spool is replayed into the database
+const SPOOL_REPLAY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Keep a sample in the spool until it can be stored
+fn spool_status(status: &NewStatus) {
+    let Ok(mut spool) = STATUS_SPOOL.lock() else {
+        error!("Failed to lock status spool, dropping {status:?}");
+        return;
+    };
+    match spool.append(status) {
+        Ok(true) => {}
+        Ok(false) => error!("Status spool is full, dropping {status:?}"),
+        Err(e) => error!("Failed to spool {status:?}: {e:?}"),
+    }
+}
+
+/// When the oldest sample that is waiting in the spool was taken, periods after this can't be
+/// rolled up until it has been stored
+pub fn spooled_since() -> anyhow::Result<Option<NaiveDateTime>> {
+    StatusSpool::lock(&STATUS_SPOOL)?.oldest()
+}
+
+#[tracing::instrument(skip(status_repo))]
+pub fn submit_status(status_repo: &dyn StatusRepository, status: NewStatus) {
+    if let Err(e) = status_repo.add_statuses(std::slice::from_ref(&status)) {
+        error!("Failed to insert {status:?} into db: {e:?}");
+        spool_status(&status);
     }
 }
 
+/// Insert the samples that were spooled while the database was unavailable, in batches
 #[tracing::instrument(skip(db))]
 pub async fn process_pending_status_updates_job(db: Database) {
     let _span = tracing::info_span!("Process pending updates job");
 
     loop {
-        if let Ok(mut lock) = PENDING_STATUS_UPDATES.lock() {
-            let status = lock.deref().first();
-            if let Some(status) = status {
-                match db.get() {
-                    Ok(mut conn) => {
-                        if diesel::insert_into(stat::table)
-                            .values(status)
-                            .execute(&mut conn)
-                            .is_ok()
-                        {
-                            lock.deref_mut().remove(0);
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to get pool in pending update job {e:?}");
-                    }
-                }
-            }
+        match StatusSpool::replay(&STATUS_SPOOL, |batch| db.add_statuses(batch)) {
+            Ok(0) => {}
+            Ok(inserted) => tracing::info!("Stored {inserted} spooled status samples"),
+            Err(e) => error!("Failed to replay status spool: {e:?}"),
         }
 
-        actix_rt::time::sleep(Duration::from_secs(90)).await;
+        actix_rt::time::sleep(SPOOL_REPLAY_INTERVAL).await;
     }
 }
 
-pub async fn check_domain(c: &Client, domain: &Project) -> (Duration, StatusCode) {
-    let req = c.get(&domain.url).send();
-    let req_start_time = Instant::now();
-    let response = req.await;
-    let req_duration = req_start_time.elapsed();
-    let status = response
-        .map(|v| v.status())
-        .unwrap_or(StatusCode::NOT_FOUND);
+/// How long to wait before each retry of a failed check
+const RETRY_BACKOFF: [Duration; 3] = [
+    Duration::from_secs(15),
+    Duration::from_secs(30),
+    Duration::from_secs(60),
+];
+
+/// How often the scheduler looks for projects that are due to be checked
+pub(crate) const SCHEDULER_TICK: Duration = Duration::from_secs(5);
 
-    (req_duration, status)
+/// The shortest interval a project can be checked at
+const MINIMUM_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Everything needed to check a project, shared between all running checks
+struct UpdateJobState {
+    db: Database,
+    context: CheckContext,
+    notifiers: Notifiers,
+    certificate_repo: CertificateRepositoryBox,
+    /// Limits how many checks can run at once, permits are not held while waiting to retry
+    workers: Semaphore,
+}
+
+/// Tracks when each project is next due to be checked
+#[derive(Default)]
+pub(crate) struct Schedule {
+    next_run: HashMap<i32, Instant>,
+    in_flight: HashSet<i32>,
 }
 
-pub async fn check_domain_retry(c: &Client, domain: &Project) -> (Duration, StatusCode) {
+impl Schedule {
+    /// Check if a project should be checked now, if so it is marked as in flight until
+    /// [Schedule::finished] is called
+    pub(crate) fn take_if_due(&mut self, project: i32, interval: Duration, now: Instant) -> bool {
+        if self.in_flight.contains(&project)
+            || self.next_run.get(&project).is_some_and(|next| *next > now)
+        {
+            return false;
+        }
+
+        self.next_run
+            .insert(project, now + interval.max(MINIMUM_CHECK_INTERVAL));
+        self.in_flight.insert(project);
+        true
+    }
+
+    pub(crate) fn finished(&mut self, project: i32) {
+        self.in_flight.remove(&project);
+    }
+}
+
+async fn run_check(check: &dyn Check, workers: &Semaphore) -> CheckResult {
+    let _permit = workers.acquire().await.expect("Check workers closed");
+    check.run().await
+}
+
+/// Run a check, retrying a few times on failure to avoid reporting transient network issues
+pub(crate) async fn check_with_retry(check: &dyn Check, workers: &Semaphore) -> CheckResult {
     // Initial req
-    let (mut dur, mut sc) = check_domain(c, domain).await;
+    let mut result = run_check(check, workers).await;
 
     // If failed, try a few more times
-    if !sc.is_success() {
-        let mut tries = 3;
-        while tries > 0 {
-            // Try again in a few secs
-            actix_rt::time::sleep(Duration::from_secs(45)).await;
+    if !result.success && check.retryable() {
+        for backoff in RETRY_BACKOFF {
+            // Try again in a few secs, without holding up other projects
+            actix_rt::time::sleep(backoff).await;
 
             // If one of these is success then maybe we just dropped a packet
-            let (new_dur, nsc) = check_domain(c, domain).await;
-            if nsc.is_success() {
-                dur = new_dur;
-                sc = nsc;
+            let new_result = run_check(check, workers).await;
+            if new_result.success {
+                result = new_result;
                 break;
             }
-
-            tries -= 1;
         }
     }
 
     // If we are still failed here its probably real
-    (dur, sc)
+    result
+}
+
+/// Store the certificate seen by a check and warn subscribers as it gets close to expiring,
+/// returns the threshold that subscribers were warned about
+async fn process_certificate(
+    certificate_repo: &dyn CertificateRepository,
+    notifiers: &Notifiers,
+    project: &Project,
+    certificate: &CertificateInfo,
+) -> anyhow::Result<Option<i32>> {
+    certificate_repo.save_certificate(NewProjectCertificate {
+        project: project.id,
+        not_after: certificate.not_after,
+        issuer: certificate.issuer.clone(),
+        sans: certificate.sans.join(", "),
+    })?;
+
+    let last_notified = certificate_repo
+        .get_certificate_by_project(project.id)?
+        .and_then(|c| c.notified_threshold);
+    let days_remaining = certificate.days_remaining();
+
+    let threshold = crossed_threshold(days_remaining, last_notified);
+    if let Some(threshold) = threshold {
+        let message = format!(
+            "Certificate for project '{}' expires in {days_remaining} days",
+            project.name
+        );
+
+        notifiers
+            .notify_all_subscribers(Notification {
+                title: format!("Certificate expiring in project '{}'", project.name),
+                email_body: format!(
+                    "{message}, on {}\nIssuer: {}\n",
+                    certificate.not_after, certificate.issuer
+                ),
+                sms_body: format!("YouUp, {message}"),
+                payload: WebhookPayload {
+                    project_id: project.id,
+                    project_name: project.name.clone(),
+                    status_code: None,
+                    message: Some(message),
+                    time: Utc::now().format("%+").to_string(),
+                    outage_started: None,
+                    outage_seconds: None,
+                },
+            })
+            .await;
+
+        certificate_repo.set_notified_threshold(project.id, Some(threshold))?;
+    } else if last_notified.is_some()
+        && days_remaining > i64::from(EXPIRY_NOTIFICATION_THRESHOLDS[0])
+    {
+        // The certificate has been renewed, start warning again for the next one
+        certificate_repo.set_notified_threshold(project.id, None)?;
+    }
+
+    Ok(threshold)
+}
+
+async fn check_project(state: &UpdateJobState, domain: &Project) {
+    tracing::info!("Checking {}", domain.name);
+
+    // Check if domain is up, store in db and wait
+    let result = match check_for_project(&state.context, domain) {
+        Ok(check) => check_with_retry(check.as_ref(), &state.workers).await,
+        Err(e) => {
+            error!("Unable to create check for {}: {e:?}", domain.name);
+            CheckResult::failure(
+                Duration::ZERO,
+                None,
+                format!("Invalid check configuration: {e}"),
+            )
+        }
+    };
+
+    if let Some(certificate) = &result.certificate
+        && let Err(e) = process_certificate(
+            state.certificate_repo.as_ref(),
+            &state.notifiers,
+            domain,
+            certificate,
+        )
+        .await
+    {
+        error!("Failed to process certificate for {}: {e:?}", domain.name);
+    }
+
+    record_result(
+        &state.db,
+        &state.notifiers,
+        domain,
+        &settings::location(),
+        &result,
+        Utc::now().naive_utc(),
+    )
+    .await;
+}
+
+/// Everything needed to record the result of a check
+pub trait ResultRepository: StatusRepository + AlertRepository + OutageRepository {}
+
+impl<T: StatusRepository + AlertRepository + OutageRepository> ResultRepository for T {}
+
+pub type ResultRepositoryData = Data<Box<dyn ResultRepository>>;
+
+/// Leave out the sample taken from `location` at `checked`, the closest one as the database may
+/// have rounded its time to the second
+fn without_sample(mut recent: Vec<Status>, location: &str, checked: NaiveDateTime) -> Vec<Status> {
+    let stored = recent
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.location == location)
+        .map(|(i, s)| (i, (s.created - checked).abs()))
+        .filter(|(_, difference)| *difference < chrono::Duration::seconds(1))
+        .min_by_key(|(_, difference)| *difference)
+        .map(|(i, _)| i);
+    if let Some(i) = stored {
+        recent.remove(i);
+    }
+    recent
 }
 
-#[tracing::instrument(skip(webhook_subscription_repo, sms_subscription_repo))]
+/// Store the result of checking a project from a location at `checked`, notifying subscribers
+/// once enough locations agree that it has gone down. The sample is stored, or spooled if the
+/// database is unavailable, before anything else, the alert state is kept up to date on a best
+/// effort basis
+pub async fn record_result(
+    db: &dyn ResultRepository,
+    notifiers: &Notifiers,
+    project: &Project,
+    location: &str,
+    result: &CheckResult,
+    checked: NaiveDateTime,
+) {
+    let result = &apply_latency_thresholds(db, project, location, result);
+    submit_status(
+        db,
+        NewStatus {
+            project: project.id,
+            //TODO: change the type of this field
+            time: result.duration.as_millis() as i32,
+            status_code: result.status_code.map(i32::from),
+            success: result.success,
+            message: result.message.clone(),
+            degraded: result.degraded,
+            steps: if result.steps.is_empty() {
+                None
+            } else {
+                serde_json::to_string(&result.steps).ok()
+            },
+            location: location.to_string(),
+            created: checked,
+        },
+    );
+
+    let policy = project.alert_policy();
+    let now = Utc::now().naive_utc();
+
+    // Get the most recent status from each location, and enough history to detect flapping
+    let window = settings::location_stale_after().max(chrono::Duration::seconds(
+        i64::from(project.check_interval) * 3,
+    ));
+    // Enough history to find the first failure once a project has failed `down_after` times
+    let history = window.max(chrono::Duration::seconds(
+        i64::from(project.check_interval) * i64::from(policy.down_after + 1),
+    ));
+    let history = if policy.flap_threshold.is_some() {
+        history.max(policy.flap_window())
+    } else {
+        history
+    };
+    // The sample that was just stored is left out, the result is added to the history below
+    let recent_status = match db.get_recent_status_for_project(project.id, now - history) {
+        Ok(recent_status) => without_sample(recent_status, location, checked),
+        Err(e) => {
+            error!("Failed to load recent status for {}: {e:?}", project.name);
+            return;
+        }
+    };
+    let previous_alert = match db.get_alert_by_project(project.id) {
+        Ok(previous_alert) => previous_alert,
+        Err(e) => {
+            error!("Failed to load alert state for {}: {e:?}", project.name);
+            return;
+        }
+    };
+
+    let mut latest = quorum::latest_by_location(&recent_status)
+        .into_iter()
+        .filter(|(_, status)| now.signed_duration_since(status.created) <= window)
+        .map(|(location, status)| (location.to_string(), status.success))
+        .collect::<BTreeMap<_, _>>();
+    latest.insert(location.to_string(), result.success);
+    let failures = latest.values().filter(|s| !**s).count();
+    let is_down = quorum::is_down(failures, latest.len(), settings::check_quorum());
+
+    let timeline = quorum::consensus(
+        recent_status.clone(),
+        settings::check_quorum(),
+        settings::location_stale_after(),
+    );
+    let changes = if policy.flap_threshold.is_some() {
+        alerting::count_changes(
+            timeline
+                .iter()
+                .rev()
+                .filter(|s| now.signed_duration_since(s.created) <= policy.flap_window())
+                .map(|s| !s.success)
+                .chain([is_down]),
+        )
+    } else {
+        0
+    };
+
+    let previous_state = previous_alert
+        .as_ref()
+        .map(ProjectAlert::state)
+        .unwrap_or_default();
+    let (alert, event) = previous_state.next(&policy, is_down, changes);
+    if alert.flapping != previous_state.flapping {
+        tracing::info!(
+            "Project {} {} flapping",
+            project.name,
+            if alert.flapping { "started" } else { "stopped" }
+        );
+    }
+
+    if let Err(e) = db.save_alert(NewProjectAlert {
+        project: project.id,
+        down: alert.down,
+        streak: alert.streak,
+        flapping: alert.flapping,
+        changed: match &previous_alert {
+            Some(previous_alert) if event.is_none() => previous_alert.changed,
+            _ => now,
+        },
+    }) {
+        error!("Failed to save alert state for {}: {e:?}", project.name);
+    }
+
+    // The outage started with the first failure that led to the project being down
+    let started = if is_down {
+        quorum::failing_since(&timeline).unwrap_or(now)
+    } else {
+        now
+    };
+    let ended_outage = update_outage(db, project, event, alert.down, result, started, now)
+        .unwrap_or_else(|e| {
+            error!("Failed to update outage for {}: {e:?}", project.name);
+            None
+        });
+
+    if event == Some(AlertEvent::Down) {
+        let failing_locations = latest
+            .iter()
+            .filter(|(_, success)| !**success)
+            .map(|(location, _)| location.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        notifiers
+            .notify_all_subscribers(Notification {
+                title: format!("Alert in project '{}'", project.name),
+                email_body: format!(
+                    "Service is now down, {} at {}\nFailing locations: {failing_locations}\n",
+                    result.describe(),
+                    Utc::now().format("%+")
+                ),
+                sms_body: format!(
+                    "YouUp, Project '{}' down, {}",
+                    project.name,
+                    result.describe()
+                ),
+                payload: WebhookPayload {
+                    project_id: project.id,
+                    project_name: project.name.clone(),
+                    status_code: result.status_code,
+                    message: result.message.clone(),
+                    time: Utc::now().format("%+").to_string(),
+                    outage_started: None,
+                    outage_seconds: None,
+                },
+            })
+            .await;
+    }
+
+    if let Some(outage) = &ended_outage {
+        notify_recovery(notifiers, project, outage).await;
+    }
+
+    if project.notify_degraded
+        && !alert.flapping
+        && let Some(previous) = recent_status.iter().find(|s| s.location == location)
+    {
+        notify_degraded_change(notifiers, project, previous, result).await;
+    }
+}
+
+/// Keep the outages of a project in step with its alert state, returning the outage that has
+/// just ended if the project came back up. New outages start at `started`
+fn update_outage(
+    db: &dyn OutageRepository,
+    project: &Project,
+    event: Option<AlertEvent>,
+    down: bool,
+    result: &CheckResult,
+    started: NaiveDateTime,
+    now: NaiveDateTime,
+) -> anyhow::Result<Option<Outage>> {
+    let status_code = result.status_code.map(i32::from);
+
+    match event {
+        Some(AlertEvent::Down) => {
+            db.open_outage(NewOutage {
+                project: project.id,
+                started,
+                cause: Some(result.describe()),
+                first_status_code: status_code,
+                last_status_code: status_code,
+            })?;
+            Ok(None)
+        }
+        Some(AlertEvent::Up) => {
+            let Some(outage) = db.get_open_outage(project.id)? else {
+                return Ok(None);
+            };
+            db.close_outage(outage.id, now)?;
+            Ok(Some(Outage {
+                ended: Some(now),
+                ..outage
+            }))
+        }
+        None if down && !result.success => {
+            if let Some(outage) = db.get_open_outage(project.id)?
+                && outage.last_status_code != status_code
+            {
+                db.set_last_status_code(outage.id, status_code)?;
+            }
+            Ok(None)
+        }
+        None => Ok(None),
+    }
+}
+
+/// Tell subscribers that a project is back up, along with how long it was down for
+async fn notify_recovery(notifiers: &Notifiers, project: &Project, outage: &Outage) {
+    notifiers
+        .notify_all_subscribers(recovery_notification(project, outage, Utc::now()))
+        .await;
+}
+
+/// The recovery message for an outage that has just ended at `now`
+fn recovery_notification(project: &Project, outage: &Outage, now: DateTime<Utc>) -> Notification {
+    let down_since = outage.started;
+    let outage_duration = outage.duration(now.naive_utc());
+    let duration = Some(time_formatter::format_duration(&outage_duration))
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "less than a minute".to_string());
+
+    let status_code = outage.last_status_code.and_then(|c| u16::try_from(c).ok());
+    let last_failure = match (status_code, &outage.cause) {
+        (Some(status_code), _) => format!("status code {status_code}"),
+        (None, Some(cause)) => cause.clone(),
+        (None, None) => "Unknown failure".to_string(),
+    };
+
+    let message = format!("Project '{}' is back up after {duration}", project.name);
+
+    Notification {
+        title: format!("Recovery in project '{}'", project.name),
+        email_body: format!(
+            "Service is back up at {}\nDown since: {}\nDowntime: {duration}\nLast failure: {last_failure}\n",
+            now.format("%+"),
+            down_since.and_utc().format("%+")
+        ),
+        sms_body: format!("YouUp, {message}"),
+        payload: WebhookPayload {
+            project_id: project.id,
+            project_name: project.name.clone(),
+            status_code,
+            message: Some(message),
+            time: now.format("%+").to_string(),
+            outage_started: Some(down_since.and_utc().format("%+").to_string()),
+            outage_seconds: Some(outage_duration.num_seconds()),
+        },
+    }
+}
+
+/// Degrade or fail a successful result that was slower than the project's latency thresholds
+fn apply_latency_thresholds(
+    db: &dyn StatusRepository,
+    project: &Project,
+    location: &str,
+    result: &CheckResult,
+) -> CheckResult {
+    let mut result = result.clone();
+    let thresholds = project.latency_thresholds();
+    if !thresholds.is_set() || !result.success {
+        return result;
+    }
+
+    let latency_ms = result.duration.as_millis() as u64;
+    let latency_ms = if thresholds.use_p95 {
+        match db.get_recent_latencies(project.id, location, P95_SAMPLES - 1) {
+            Ok(recent) => {
+                let mut samples = recent
+                    .into_iter()
+                    .map(|time| time.max(0) as u64)
+                    .collect::<Vec<_>>();
+                samples.push(latency_ms);
+                p95(&samples).unwrap_or(latency_ms)
+            }
+            Err(e) => {
+                error!("Failed to load latencies for {}: {e:?}", project.name);
+                latency_ms
+            }
+        }
+    } else {
+        latency_ms
+    };
+
+    thresholds.apply(&mut result, latency_ms);
+    result
+}
+
+/// Tell subscribers when a project becomes degraded, or when it is healthy again
+async fn notify_degraded_change(
+    notifiers: &Notifiers,
+    project: &Project,
+    previous: &Status,
+    result: &CheckResult,
+) {
+    let is_degraded = result.success && result.degraded;
+    let (title, message) = if !previous.is_degraded() && is_degraded {
+        (
+            format!("Degraded performance in project '{}'", project.name),
+            format!(
+                "Project '{}' is degraded, {}",
+                project.name,
+                result.describe()
+            ),
+        )
+    } else if previous.is_degraded() && result.success && !result.degraded {
+        (
+            format!("Project '{}' recovered", project.name),
+            format!("Project '{}' is no longer degraded", project.name),
+        )
+    } else {
+        return;
+    };
+
+    notifiers
+        .notify_all_subscribers(Notification {
+            title,
+            email_body: format!("{message} at {}\n", Utc::now().format("%+")),
+            sms_body: format!("YouUp, {message}"),
+            payload: WebhookPayload {
+                project_id: project.id,
+                project_name: project.name.clone(),
+                status_code: result.status_code,
+                message: Some(message),
+                time: Utc::now().format("%+").to_string(),
+                outage_started: None,
+                outage_seconds: None,
+            },
+        })
+        .await;
+}
+
+#[tracing::instrument(skip(db, notifiers, certificate_repo, heartbeat_repo))]
 pub async fn run_update_job(
-    mailer: Arc<Mailer>,
-    sms: Arc<SMSNotifier>,
-    webhook: Arc<WebhookNotifier>,
-    sms_subscription_repo: SmsSubscriberRepository,
-    webhook_subscription_repo: WebhookSubscriberRepository,
+    db: Database,
+    notifiers: Notifiers,
+    certificate_repo: CertificateRepositoryBox,
+    heartbeat_repo: HeartbeatRepositoryBox,
 ) {
     let _span = tracing::info_span!("Update Job");
 
-    let c = Client::builder().build().unwrap();
+    let state = Rc::new(UpdateJobState {
+        db,
+        context: CheckContext::new(Some(heartbeat_repo), true)
+            .expect("Unable to create check context"),
+        notifiers,
+        certificate_repo,
+        workers: Semaphore::new(settings::max_concurrent_checks()),
+    });
+    let schedule = Rc::new(RefCell::new(Schedule::default()));
+    // The projects are still checked while the database is unavailable, the samples are spooled
+    let mut projects_list = Vec::new();
 
     loop {
-        actix_rt::time::sleep(Duration::from_secs(90)).await;
+        actix_rt::time::sleep(SCHEDULER_TICK).await;
 
-        let db = match db::get_db_connection() {
-            Ok(db) => db,
-            Err(e) => {
-                error!("Failed to get database, can't run update job: {e:?}");
+        match state.db.get_all_projects() {
+            Ok(loaded) => projects_list = loaded,
+            Err(e) => error!("Failed to load projects in update job {e:?}"),
+        }
+
+        let now = Instant::now();
+        for project in projects_list.iter().cloned() {
+            let interval = Duration::from_secs(project.check_interval.max(0) as u64);
+            if !schedule.borrow_mut().take_if_due(project.id, interval, now) {
                 continue;
             }
+
+            let state = state.clone();
+            let schedule = schedule.clone();
+            spawn(async move {
+                check_project(&state, &project).await;
+                schedule.borrow_mut().finished(project.id);
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::app;
+    use crate::checks::CheckResult;
+    use crate::checks::certificate::CertificateInfo;
+    use crate::data::certificate_repository::CertificateRepository;
+    use crate::data::in_memory::InMemoryDatabase;
+    use crate::data::outage_repository::OutageRepository;
+    use crate::db::Database;
+    use crate::models::{Outage, Project, WebhookSubscription, fixtures};
+    use crate::notifications::mailer::Mailer;
+    use crate::notifications::sms::SMSNotifier;
+    use crate::notifications::webhook::WebhookNotifier;
+    use crate::spool::StatusSpool;
+    use crate::update_job::{
+        MINIMUM_CHECK_INTERVAL, STATUS_SPOOL, Schedule, process_certificate, record_result,
+        recovery_notification,
+    };
+    use actix_web::web::{Data, Json};
+    use actix_web::{App, HttpResponse, HttpServer, web};
+    use chrono::Utc;
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    type Received = Data<Mutex<Vec<String>>>;
+
+    async fn webhook(received: Received, payload: Json<serde_json::Value>) -> HttpResponse {
+        received
+            .lock()
+            .unwrap()
+            .push(payload["message"].as_str().unwrap_or_default().to_string());
+        HttpResponse::Ok().finish()
+    }
+
+    fn expiring_in(days: i64) -> CertificateInfo {
+        CertificateInfo {
+            not_after: Utc::now().naive_utc()
+                + chrono::Duration::days(days)
+                + chrono::Duration::hours(1),
+            issuer: "CN=Test".to_string(),
+            sans: vec!["localhost".to_string()],
+        }
+    }
+
+    #[test]
+    fn projects_run_on_their_own_interval() {
+        let mut schedule = Schedule::default();
+        let start = Instant::now();
+
+        assert!(schedule.take_if_due(1, Duration::from_secs(30), start));
+        assert!(schedule.take_if_due(2, Duration::from_secs(120), start));
+        schedule.finished(1);
+        schedule.finished(2);
+
+        let later = start + Duration::from_secs(60);
+        assert!(schedule.take_if_due(1, Duration::from_secs(30), later));
+        assert!(!schedule.take_if_due(2, Duration::from_secs(120), later));
+    }
+
+    #[test]
+    fn in_flight_projects_are_not_scheduled_again() {
+        let mut schedule = Schedule::default();
+        let start = Instant::now();
+
+        assert!(schedule.take_if_due(1, Duration::from_secs(30), start));
+
+        let later = start + Duration::from_secs(300);
+        assert!(!schedule.take_if_due(1, Duration::from_secs(30), later));
+        schedule.finished(1);
+        assert!(schedule.take_if_due(1, Duration::from_secs(30), later));
+    }
+
+    #[test]
+    fn interval_is_clamped() {
+        let mut schedule = Schedule::default();
+        let start = Instant::now();
+
+        assert!(schedule.take_if_due(1, Duration::ZERO, start));
+        schedule.finished(1);
+        assert!(!schedule.take_if_due(1, Duration::ZERO, start + Duration::from_secs(1)));
+        assert!(schedule.take_if_due(1, Duration::ZERO, start + MINIMUM_CHECK_INTERVAL));
+    }
+
+    #[actix_rt::test]
+    async fn certificate_expiry_is_notified_once_per_threshold() {
+        let received = Received::new(Mutex::new(vec![]));
+        let server = {
+            let received = received.clone();
+            HttpServer::new(move || {
+                App::new()
+                    .app_data(received.clone())
+                    .route("/", web::post().to(webhook))
+            })
+        }
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let address = server.addrs()[0];
+        actix_rt::spawn(server.run());
+
+        let repos = InMemoryDatabase::default();
+        repos
+            .tables()
+            .webhook_subscriptions
+            .push(WebhookSubscription {
+                id: 1,
+                created: fixtures::at(2020, 1, 1, 0, 0),
+                url: format!("http://{address}/"),
+                enabled: true,
+            });
+        let notifiers = app::notifiers(
+            &repos,
+            &Arc::new(Mailer::default()),
+            &Arc::new(SMSNotifier::default()),
+            &Arc::new(WebhookNotifier::default()),
+        );
+        let project = fixtures::project(1);
+        let check = |days| {
+            let certificate = expiring_in(days);
+            let repos = repos.clone();
+            let notifiers = &notifiers;
+            let project = &project;
+            async move { process_certificate(&repos, notifiers, project, &certificate).await }
         };
 
-        match db.get() {
-            Ok(mut conn) => {
-                let projects_list = crate::schema::projects::dsl::projects
-                    .load::<Project>(&mut conn)
-                    .expect("Unable to load projects");
-
-                for domain in &projects_list {
-                    tracing::info!("Checking {}", domain.name);
-
-                    // Check if domain is up, store in db and wait
-                    let (req_duration, status) = check_domain_retry(&c, domain).await;
-
-                    // Get the most recent status
-                    let most_recent_status = stat::table
-                        .filter(stat::dsl::project.eq(domain.id))
-                        .order_by(stat::dsl::created.desc())
-                        .limit(1)
-                        .load::<Status>(&mut db.get().unwrap());
-
-                    submit_status(
-                        db.clone(),
-                        NewStatus {
-                            project: domain.id,
-                            //TODO: change the type of this field
-                            time: req_duration.as_millis() as i32,
-                            status_code: status.as_u16() as i32,
-                        },
-                    );
-
-                    if let Ok(stat) = most_recent_status
-                        && let Some(stat2) = stat.first()
-                        && stat2.is_success()
-                        && !status.is_success()
-                    {
-                        mailer.send_to_subscribers(
-                                    &db,
-                                    "YouUp <alerts@you-up.net>",
-                                    format!("Alert in project '{}'", domain.name),
-                                    format!(
-                                        "Service is now down, received a status code of {} at {}\nPrevious status: {stat:?}\nCurrent status: {stat2:?}\n",
-                                        status.as_str(),
-                                        Utc::now().format("%+")
-                                    ),
-                                );
-
-                        sms.notify_all_subscribers(
-                            &sms_subscription_repo,
-                            &format!(
-                                "YouUp, Project '{}' down, code {}",
-                                domain.name,
-                                status.as_str()
-                            ),
-                        )
-                        .await;
-
-                        webhook
-                            .notify_all_subscribers(
-                                &webhook_subscription_repo,
-                                WebhookPayload {
-                                    project_id: domain.id,
-                                    project_name: domain.name.clone(),
-                                    status_code: status.as_u16(),
-                                    time: Utc::now().format("%+").to_string(),
-                                },
-                            )
-                            .await;
-                    }
-                }
-            }
-            Err(e) => {
-                error!("Failed to get pool in update job {e:?}");
-            }
+        assert_eq!(check(20).await.unwrap(), Some(30));
+        assert_eq!(check(20).await.unwrap(), None);
+        assert_eq!(check(19).await.unwrap(), None);
+        assert_eq!(check(10).await.unwrap(), Some(14));
+        assert_eq!(check(10).await.unwrap(), None);
+
+        // Renewing the certificate starts the warnings over
+        assert_eq!(check(200).await.unwrap(), None);
+        assert_eq!(
+            repos
+                .get_certificate_by_project(1)
+                .unwrap()
+                .unwrap()
+                .notified_threshold,
+            None
+        );
+        assert_eq!(check(20).await.unwrap(), Some(30));
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                "Certificate for project '' expires in 20 days",
+                "Certificate for project '' expires in 10 days",
+                "Certificate for project '' expires in 20 days",
+            ]
+        );
+    }
+
+    #[test]
+    fn recovery_message_describes_the_outage() {
+        let project = Project {
+            name: "API".to_string(),
+            ..fixtures::project(3)
+        };
+        let outage = fixtures::outage(3, fixtures::at(2024, 5, 1, 10, 0), None);
+        let now = fixtures::at(2024, 5, 1, 12, 30).and_utc();
+
+        let notification = recovery_notification(&project, &outage, now);
+
+        assert_eq!(notification.title, "Recovery in project 'API'");
+        assert_eq!(
+            notification.email_body,
+            "Service is back up at 2024-05-01T12:30:00+00:00\nDown since: 2024-05-01T10:00:00+00:00\nDowntime: 2 hours 30 minutes\nLast failure: status code 503\n"
+        );
+        assert_eq!(
+            notification.sms_body,
+            "YouUp, Project 'API' is back up after 2 hours 30 minutes"
+        );
+        assert_eq!(notification.payload.status_code, Some(503));
+        assert_eq!(
+            notification.payload.outage_started.as_deref(),
+            Some("2024-05-01T10:00:00+00:00")
+        );
+        assert_eq!(notification.payload.outage_seconds, Some(9000));
+    }
+
+    #[test]
+    fn short_recovery_without_a_status_code() {
+        let outage = Outage {
+            last_status_code: None,
+            cause: Some("Connection refused".to_string()),
+            ..fixtures::outage(3, fixtures::at(2024, 5, 1, 10, 0), None)
+        };
+        let now = fixtures::at(2024, 5, 1, 10, 0).and_utc() + chrono::Duration::seconds(40);
+
+        let notification = recovery_notification(&fixtures::project(3), &outage, now);
+
+        assert!(
+            notification
+                .email_body
+                .contains("Downtime: less than a minute\n")
+        );
+        assert!(
+            notification
+                .email_body
+                .contains("Last failure: Connection refused\n")
+        );
+        assert_eq!(notification.payload.status_code, None);
+        assert_eq!(notification.payload.outage_seconds, Some(40));
+    }
+
+    #[actix_rt::test]
+    async fn samples_are_spooled_while_the_database_is_unavailable() {
+        let unavailable: Database = Pool::builder()
+            .connection_timeout(Duration::from_millis(100))
+            .build_unchecked(ConnectionManager::new("/nonexistent/you_up.db"));
+        let notifiers = app::notifiers(
+            &InMemoryDatabase::default(),
+            &Arc::new(Mailer::default()),
+            &Arc::new(SMSNotifier::default()),
+            &Arc::new(WebhookNotifier::default()),
+        );
+        let checked = Utc::now().naive_utc();
+
+        record_result(
+            &unavailable,
+            &notifiers,
+            &fixtures::project(7),
+            "local",
+            &CheckResult::failure(Duration::from_millis(10), Some(503), "Unavailable"),
+            checked,
+        )
+        .await;
+
+        let spooled = StatusSpool::lock(&STATUS_SPOOL).unwrap().read().unwrap();
+        assert!(
+            spooled
+                .iter()
+                .any(|s| s.project == 7 && s.created == checked && s.status_code == Some(503))
+        );
+        StatusSpool::replay(&STATUS_SPOOL, |_| Ok(())).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn outages_start_at_the_first_failure() {
+        let repos = InMemoryDatabase::default();
+        let project = Project {
+            down_after_failures: 3,
+            ..fixtures::project(1)
+        };
+        repos.tables().projects.push(project.clone());
+        let notifiers = app::notifiers(
+            &repos,
+            &Arc::new(Mailer::default()),
+            &Arc::new(SMSNotifier::default()),
+            &Arc::new(WebhookNotifier::default()),
+        );
+        let failure = CheckResult::failure(Duration::from_millis(10), Some(503), "Unavailable");
+
+        record_result(
+            &repos,
+            &notifiers,
+            &project,
+            "local",
+            &CheckResult::success(Duration::from_millis(10), Some(200)),
+            Utc::now().naive_utc(),
+        )
+        .await;
+        for _ in 0..3 {
+            record_result(
+                &repos,
+                &notifiers,
+                &project,
+                "local",
+                &failure,
+                Utc::now().naive_utc(),
+            )
+            .await;
         }
+
+        let first_failure = repos.tables().status[1].created;
+        let outage = repos.get_open_outage(project.id).unwrap().unwrap();
+        assert_eq!(outage.started, first_failure);
     }
 }
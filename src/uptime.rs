@@ -0,0 +1,283 @@
+use crate::models::{Project, RollupTotals, Status};
+use crate::settings;
+use chrono::{Duration, NaiveDateTime};
+
+/// The periods that uptime is reported over, ending now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UptimeWindow {
+    Day,
+    Week,
+    Month,
+    Quarter,
+}
+
+impl UptimeWindow {
+    pub const ALL: [UptimeWindow; 4] = [
+        UptimeWindow::Day,
+        UptimeWindow::Week,
+        UptimeWindow::Month,
+        UptimeWindow::Quarter,
+    ];
+
+    pub fn duration(&self) -> Duration {
+        match self {
+            UptimeWindow::Day => Duration::days(1),
+            UptimeWindow::Week => Duration::days(7),
+            UptimeWindow::Month => Duration::days(30),
+            UptimeWindow::Quarter => Duration::days(90),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            UptimeWindow::Day => "24 hours",
+            UptimeWindow::Week => "7 days",
+            UptimeWindow::Month => "30 days",
+            UptimeWindow::Quarter => "90 days",
+        }
+    }
+}
+
+/// How long a project was known to be up and down for during a window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Uptime {
+    pub window: UptimeWindow,
+    pub up: Duration,
+    pub down: Duration,
+}
+
+impl Uptime {
+    /// The percentage of the known time that the project was up, time without samples isn't
+    /// counted either way
+    pub fn percentage(&self) -> Option<f64> {
+        let known = self.up + self.down;
+        if known.is_zero() {
+            None
+        } else {
+            Some(self.up.num_milliseconds() as f64 * 100.0 / known.num_milliseconds() as f64)
+        }
+    }
+
+    pub fn formatted_percentage(&self) -> String {
+        self.percentage()
+            .map(|p| format!("{p:.2}%"))
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    /// Whether the uptime is below an SLA target, given as a percentage
+    pub fn breaches(&self, target: f64) -> bool {
+        self.percentage().is_some_and(|p| p < target)
+    }
+}
+
+/// The uptime of a project over each window, along with its SLA target
+#[derive(Debug, Clone)]
+pub struct ProjectUptime {
+    pub windows: Vec<Uptime>,
+    pub sla_target: Option<f64>,
+}
+
+impl ProjectUptime {
+    /// `rolled_up` has the hourly rollups of this project added up over each window, `timeline` must
+    /// only contain samples for this project, see [uptime]
+    pub fn new(
+        project: &Project,
+        rolled_up: &[(UptimeWindow, RollupTotals)],
+        timeline: &[Status],
+        now: NaiveDateTime,
+    ) -> Self {
+        let max_gap = max_sample_gap(project);
+        Self {
+            windows: UptimeWindow::ALL
+                .iter()
+                .map(|window| {
+                    let totals = rolled_up
+                        .iter()
+                        .find(|(w, _)| w == window)
+                        .map(|(_, totals)| totals);
+                    uptime(totals, timeline, *window, max_gap, now)
+                })
+                .collect(),
+            sla_target: project.sla_target,
+        }
+    }
+
+    /// SLAs are measured over 30 days
+    pub fn sla_breached(&self) -> bool {
+        self.sla_target.is_some_and(|target| {
+            self.windows
+                .iter()
+                .any(|u| u.window == UptimeWindow::Month && u.breaches(target))
+        })
+    }
+}
+
+/// The longest a sample is assumed to hold for, after that the project is in an unknown state
+/// until the next sample
+pub fn max_sample_gap(project: &Project) -> Duration {
+    settings::location_stale_after().max(Duration::seconds(i64::from(project.check_interval) * 2))
+}
+
+/// Weigh each sample of a project timeline by how long it held for, until the next sample or at
+/// most `max_gap`, and total them up over the window ending at `now`.
+/// Time that has already been rolled up comes from the `rolled_up` hourly totals of the window,
+/// `timeline` is only used after the last rollup. `timeline` must only contain samples for one
+/// project, ordered newest first
+pub fn uptime(
+    rolled_up: Option<&RollupTotals>,
+    timeline: &[Status],
+    window: UptimeWindow,
+    max_gap: Duration,
+    now: NaiveDateTime,
+) -> Uptime {
+    let from = now - window.duration();
+    let rolled_up_until = rolled_up.and_then(|totals| totals.rolled_up_until());
+
+    let (mut up, mut down) = up_and_down_since(
+        timeline,
+        rolled_up_until.map_or(from, |until| until.max(from)),
+        max_gap,
+        now,
+    );
+    if let Some(totals) = rolled_up {
+        up += totals.up();
+        down += totals.down();
+    }
+
+    Uptime { window, up, down }
+}
+
+/// How long a project was known to be up and down for between `from` and `now`, see [uptime]
+pub fn up_and_down_since(
+    timeline: &[Status],
+    from: NaiveDateTime,
+    max_gap: Duration,
+    now: NaiveDateTime,
+) -> (Duration, Duration) {
+    let mut up = Duration::zero();
+    let mut down = Duration::zero();
+    let mut next_sample = now;
+
+    for status in timeline {
+        let start = status.created.max(from);
+        let end = next_sample.min(status.created + max_gap).min(now);
+        next_sample = status.created;
+
+        if end > start {
+            if status.is_success() {
+                up += end - start;
+            } else {
+                down += end - start;
+            }
+        }
+
+        if status.created <= from {
+            break;
+        }
+    }
+
+    (up, down)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::models::{Status, fixtures};
+    use crate::uptime::{UptimeWindow, uptime};
+    use chrono::{Duration, NaiveDateTime};
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        fixtures::at(2020, 9, 25, hour, minute)
+    }
+
+    fn status(created: NaiveDateTime, success: bool) -> Status {
+        fixtures::status(0, created, success)
+    }
+
+    #[test]
+    fn weighted_by_time() {
+        // One failure that held for almost an hour, then a burst of retries that all passed.
+        // Counting samples would give 80% uptime
+        let timeline = [
+            status(at(12, 0), true),
+            status(at(11, 59), true),
+            status(at(11, 58), true),
+            status(at(11, 0), false),
+            status(at(10, 0), true),
+        ];
+
+        let uptime = uptime(
+            None,
+            &timeline,
+            UptimeWindow::Day,
+            Duration::hours(2),
+            at(12, 0),
+        );
+
+        assert_eq!(uptime.up, Duration::minutes(62));
+        assert_eq!(uptime.down, Duration::minutes(58));
+        assert_eq!(uptime.formatted_percentage(), "51.67%");
+    }
+
+    #[test]
+    fn gaps_are_unknown() {
+        let timeline = [status(at(12, 0), true), status(at(6, 0), false)];
+
+        let uptime = uptime(
+            None,
+            &timeline,
+            UptimeWindow::Day,
+            Duration::minutes(30),
+            at(12, 10),
+        );
+
+        assert_eq!(uptime.up, Duration::minutes(10));
+        assert_eq!(uptime.down, Duration::minutes(30));
+    }
+
+    #[test]
+    fn clipped_to_window() {
+        let now = at(12, 0);
+        let timeline = [
+            status(now, true),
+            status(now - Duration::days(2), false),
+            status(now - Duration::days(3), true),
+        ];
+
+        let day = uptime(None, &timeline, UptimeWindow::Day, Duration::days(2), now);
+        assert_eq!(day.percentage(), Some(0.0));
+
+        let week = uptime(None, &timeline, UptimeWindow::Week, Duration::days(2), now);
+        assert_eq!(week.up, Duration::days(1));
+        assert_eq!(week.down, Duration::days(2));
+    }
+
+    #[test]
+    fn no_samples() {
+        let uptime = uptime(
+            None,
+            &[],
+            UptimeWindow::Month,
+            Duration::hours(1),
+            at(12, 0),
+        );
+
+        assert_eq!(uptime.percentage(), None);
+        assert_eq!(uptime.formatted_percentage(), "-");
+        assert!(!uptime.breaches(99.9));
+    }
+
+    #[test]
+    fn sla_breach() {
+        let timeline = [status(at(12, 0), true), status(at(11, 0), false)];
+        let uptime = uptime(
+            None,
+            &timeline,
+            UptimeWindow::Day,
+            Duration::hours(2),
+            at(12, 0),
+        );
+
+        assert!(uptime.breaches(99.9));
+        assert!(!uptime.breaches(0.0));
+    }
+}